@@ -1,455 +1,5231 @@
-use eyre::Result;
+use crate::backend::ExpenseBackend;
+use eyre::{eyre, Result, WrapErr};
+use fuzzy_matcher::FuzzyMatcher;
 use inquire::{autocompletion::Replacement, Autocomplete};
 use std::collections::HashMap;
 
 pub struct App {
     settings: crate::settings::Settings,
-    notion_api: notion::NotionApi,
+    backend: std::sync::Arc<dyn crate::backend::ExpenseBackend>,
+    queue: crate::queue::Queue,
     categories_cache: Option<Vec<notion::models::Page>>,
+    category_cache: crate::cache::CategoryCache,
+    /// Pages of the database the Trip relation points to, cached like the categories.
+    trips_cache: Option<Vec<notion::models::Page>>,
+    trip_cache: crate::cache::CategoryCache,
+    /// Skip the caches and fetch the categories and trips again.
+    refresh: bool,
+    /// Print JSON for scripts instead of text.
+    json: bool,
+    /// Working from the local copy `sync` keeps.
+    offline: bool,
     last_date: Option<notion::chrono::NaiveDate>,
+    /// Categories the amount entered last is split across.
+    split: Option<crate::split::Split>,
+    /// Previously used titles, best autocomplete candidates first.
+    title_history: Vec<String>,
+    /// What was typed at the prompts in this and the earlier sessions.
+    prompt_history: crate::queue::PromptHistory,
+    /// Fields of the recent entries, newest first.
+    history: Vec<crate::cli::AddArgs>,
+    /// Pages created during this session, oldest first.
+    created: Vec<notion::ids::PageId>,
+    /// Keep the fields answered so far in the draft file, so they survive a crash.
+    drafting: bool,
+    /// Review the entry even when nothing is left to prompt for, since the
+    /// values were read off a receipt.
+    review_all: bool,
+    /// Entries still being uploaded in the background, oldest first.
+    uploads: Vec<tokio::task::JoinHandle<(crate::queue::Entry, Result<notion::models::Page>)>>,
 }
 
-fn select_page(
-    pages: &Vec<notion::models::Page>,
-    preselect: Option<&String>,
-) -> Result<notion::ids::PageId> {
-    struct Page<'a> {
-        page: &'a notion::models::Page,
-    }
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NextAction {
+    AddMore,
+    Undo,
+    Done,
+}
 
-    impl<'a> std::fmt::Display for Page<'a> {
-        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-            write!(f, "{}", self.page.title().unwrap_or("Untitled".to_string()))
+impl std::fmt::Display for NextAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::AddMore => write!(f, "Add one more row"),
+            Self::Undo => write!(f, "Undo the last entry"),
+            Self::Done => write!(f, "Done"),
         }
     }
+}
 
-    let options: Vec<Page> = pages.into_iter().map(|page| Page { page }).collect();
-    let pos = preselect.and_then(|ps| options.iter().position(|p| p.to_string() == ps.as_str()));
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ReviewAction {
+    Save,
+    EditName,
+    EditAmount,
+    EditDate,
+    EditCategory,
+    EditType,
+    Itemize,
+    Attach,
+    Discard,
+}
 
-    let mut select = inquire::Select::new("Category:", options);
-    if let Some(pos) = pos {
-        select = select.with_starting_cursor(pos);
+impl std::fmt::Display for ReviewAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Save => write!(f, "Save"),
+            Self::EditName => write!(f, "Edit name"),
+            Self::EditAmount => write!(f, "Edit amount"),
+            Self::EditDate => write!(f, "Edit date"),
+            Self::EditCategory => write!(f, "Edit category"),
+            Self::EditType => write!(f, "Edit type"),
+            Self::Itemize => write!(f, "Itemize"),
+            Self::Attach => write!(f, "Attach receipt"),
+            Self::Discard => write!(f, "Discard"),
+        }
     }
+}
 
-    let result = select.prompt()?;
+/// The name, amount, date, category and type of new entry properties, as far as they are set.
+fn entry_fields(
+    properties: &HashMap<String, notion::models::properties::PropertyValue>,
+    names: &crate::settings::PropertyNames,
+    categories: &[notion::models::Page],
+) -> crate::cli::AddArgs {
+    use notion::models::properties::{DateOrDateTime, PropertyValue};
 
-    Ok(result.page.id.clone())
-}
+    let name = match properties.get(&names.name) {
+        Some(PropertyValue::Title { title, .. }) => {
+            Some(title.iter().map(|text| text.plain_text()).collect())
+        }
+        _ => None,
+    };
 
-fn page_property_to_string(page: &notion::models::Page, name: &str) -> Option<String> {
-    match page.properties.properties.get(name) {
-        Some(notion::models::properties::PropertyValue::Date { id: _, date }) => match date {
-            Some(date) => match date.start {
-                notion::models::properties::DateOrDateTime::Date(date) => Some(date.to_string()),
-                _ => None,
-            },
-            _ => None,
+    let amount = match properties.get(&names.amount) {
+        Some(PropertyValue::Number {
+            number: Some(number),
+            ..
+        }) => number.as_f64().map(|amount| format!("{:.2}", amount)),
+        _ => None,
+    };
+
+    let date = match properties.get(&names.date) {
+        Some(PropertyValue::Date {
+            date: Some(date), ..
+        }) => match date.start {
+            DateOrDateTime::Date(date) => Some(date),
+            DateOrDateTime::DateTime(date_time) => {
+                Some(date_time.with_timezone(&notion::chrono::Local).date_naive())
+            }
         },
-        Some(notion::models::properties::PropertyValue::Number { id: _, number }) => {
-            number.clone().map(|v| v.to_string())
-        }
-        Some(_) => todo!(),
-        None => None,
-    }
-}
+        _ => None,
+    };
 
-fn database_sorting(
-    property: impl Into<String>,
-    page_size: u8,
-) -> notion::models::search::DatabaseQuery {
-    notion::models::search::DatabaseQuery {
-        sorts: Some(vec![notion::models::search::DatabaseSort {
-            property: Some(property.into()),
-            timestamp: None,
-            direction: notion::models::search::SortDirection::Descending,
-        }]),
-        paging: Some(notion::models::paging::Paging {
-            start_cursor: None,
-            page_size: Some(page_size),
+    let category = match properties.get(&names.category) {
+        Some(PropertyValue::Select {
+            select: Some(select),
+            ..
+        }) => select.name.clone(),
+        Some(PropertyValue::Relation {
+            relation: Some(relation),
+            ..
+        }) => relation.first().and_then(|value| {
+            categories
+                .iter()
+                .find(|page| page.id == value.id)
+                .and_then(|page| page.title())
         }),
-        filter: None,
+        _ => None,
+    };
+
+    let entry_type = match properties.get(&names.entry_type) {
+        Some(PropertyValue::Select {
+            select: Some(select),
+            ..
+        }) => select.name.clone(),
+        _ => None,
+    };
+
+    let notes = match properties.get(&names.notes) {
+        Some(PropertyValue::Text { rich_text, .. }) if !rich_text.is_empty() => {
+            Some(rich_text.iter().map(|text| text.plain_text()).collect())
+        }
+        _ => None,
+    };
+
+    let tags = match properties.get(&names.tags) {
+        Some(PropertyValue::MultiSelect {
+            multi_select: Some(values),
+            ..
+        }) => values
+            .iter()
+            .filter_map(|value| value.name.clone())
+            .collect(),
+        _ => vec![],
+    };
+
+    crate::cli::AddArgs {
+        name,
+        amount,
+        date,
+        category,
+        entry_type,
+        notes,
+        tags,
+        payer: properties
+            .get(&names.payer)
+            .and_then(crate::report::payer_name),
+        reimbursable: matches!(
+            properties.get(&names.reimbursable),
+            Some(PropertyValue::Checkbox { checkbox: true, .. })
+        ),
+        project: match properties.get(&names.project) {
+            Some(PropertyValue::Select {
+                select: Some(select),
+                ..
+            }) => select.name.clone(),
+            _ => None,
+        },
+        // Naming it would take the trip pages, which the history doesn't need.
+        trip: None,
+        attach: None,
     }
 }
 
-#[derive(Clone)]
-struct TitleCompleter {
-    input: String,
-    prev_titles: Vec<String>,
-    output: Vec<String>,
+/// One-line summary like `2024-05-02 · Groceries · 43.20 · Food`, with the type
+/// unless it's an expense.
+fn entry_summary(
+    fields: &crate::cli::AddArgs,
+    display: &crate::settings::DisplaySettings,
+) -> String {
+    let expense = crate::report::EntryType::Expense.name();
+
+    [
+        fields.date.map(|date| display.format_date(date)),
+        fields.name.clone(),
+        fields.amount.clone(),
+        fields.category.clone(),
+        fields
+            .entry_type
+            .clone()
+            .filter(|entry_type| !entry_type.eq_ignore_ascii_case(expense)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join(" · ")
 }
 
-impl TitleCompleter {
-    fn new(titles: Vec<&str>) -> Self {
-        Self {
-            prev_titles: titles.into_iter().map(|t| t.to_owned()).collect(),
-            ..Default::default()
-        }
-    }
+#[test]
+fn entry_summary_test() {
+    let properties: HashMap<String, notion::models::properties::PropertyValue> =
+        serde_json::from_value(serde_json::json!({
+            "Name": {"id": "title", "type": "title", "title": [
+                {"type": "text", "plain_text": "Groceries", "text": {"content": "Groceries", "link": null}}
+            ]},
+            "Amount": {"id": "a", "type": "number", "number": 43.2},
+            "Date": {"id": "d", "type": "date", "date": {"start": "2024-05-02"}},
+            "Category": {"id": "c", "type": "select", "select": {"name": "Food", "color": "default"}},
+        }))
+        .unwrap();
 
-    fn update_input(&mut self, input: &str) -> Result<(), inquire::CustomUserError> {
-        if self.input == input {
-            return Ok(());
-        }
+    let fields = entry_fields(&properties, &crate::settings::PropertyNames::default(), &[]);
 
-        self.input = input.to_owned();
-        self.output.clear();
+    assert_eq!(
+        entry_summary(&fields, &Default::default()),
+        "2024-05-02 · Groceries · 43.20 · Food"
+    );
+}
 
-        for item in &self.prev_titles {
-            if item.starts_with(input) {
-                self.output.push(item.to_string());
-            }
-        }
+/// A prompt was left with Esc or Ctrl+C, with the values entered until then.
+#[derive(Debug)]
+struct Canceled(crate::cli::AddArgs);
 
-        Ok(())
+impl std::fmt::Display for Canceled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Canceled")
     }
 }
 
-impl Default for TitleCompleter {
-    fn default() -> Self {
-        Self {
-            input: "".to_string(),
-            output: vec![],
-            prev_titles: vec![],
-        }
+impl std::error::Error for Canceled {}
+
+fn is_canceled(err: &eyre::Report) -> bool {
+    err.is::<Canceled>()
+        || matches!(
+            err.downcast_ref::<inquire::InquireError>(),
+            Some(
+                inquire::InquireError::OperationCanceled
+                    | inquire::InquireError::OperationInterrupted
+            )
+        )
+}
+
+/// `err` as a [`Canceled`] with `partial` filling in what wasn't entered, or
+/// unchanged when it isn't a cancellation.
+fn with_partial(err: eyre::Report, partial: &crate::cli::AddArgs) -> eyre::Report {
+    match err.downcast::<Canceled>() {
+        Ok(Canceled(entered)) => Canceled(entered.or(partial.clone())).into(),
+        Err(err) if is_canceled(&err) => Canceled(partial.clone()).into(),
+        Err(err) => err,
     }
 }
 
-impl Autocomplete for TitleCompleter {
-    fn get_suggestions(&mut self, input: &str) -> Result<Vec<String>, inquire::CustomUserError> {
-        self.update_input(input)?;
+#[test]
+fn with_partial_test() {
+    let partial = crate::cli::AddArgs {
+        name: Some("Coffee".to_string()),
+        amount: Some("3.50".to_string()),
+        ..Default::default()
+    };
 
-        Ok(self.output.clone())
-    }
+    let err = with_partial(inquire::InquireError::OperationCanceled.into(), &partial);
+    let err = with_partial(
+        err,
+        &crate::cli::AddArgs {
+            name: Some("Tea".to_string()),
+            category: Some("Food".to_string()),
+            ..Default::default()
+        },
+    );
+    let Ok(Canceled(entered)) = err.downcast::<Canceled>() else {
+        panic!("expected a cancellation");
+    };
+    assert_eq!(entered.name.as_deref(), Some("Coffee"));
+    assert_eq!(entered.amount.as_deref(), Some("3.50"));
+    assert_eq!(entered.category.as_deref(), Some("Food"));
 
-    fn get_completion(
-        &mut self,
-        input: &str,
-        highlighted_suggestion: Option<String>,
-    ) -> Result<Replacement, inquire::CustomUserError> {
-        self.update_input(input)?;
+    let err = with_partial(eyre!("Unknown category: Fod"), &partial);
+    assert!(!is_canceled(&err));
+}
 
-        Ok(match highlighted_suggestion {
-            Some(suggestion) => Replacement::Some(suggestion),
-            None => {
-                if self.output.len() == 1 {
-                    Replacement::Some(self.output[0].clone())
-                } else {
-                    Replacement::None
-                }
-            }
-        })
-    }
+/// Asks to resume `draft`, with the time it was saved at and the first fields.
+fn resume_message(draft: &crate::queue::Draft, today: notion::chrono::NaiveDate) -> String {
+    let saved_at = draft.saved_at.with_timezone(&notion::chrono::Local);
+    let saved_at = if saved_at.date_naive() == today {
+        saved_at.format("%H:%M")
+    } else {
+        saved_at.format("%Y-%m-%d %H:%M")
+    };
+
+    let fields = [
+        &draft.fields.name,
+        &draft.fields.amount,
+        &draft.fields.category,
+    ]
+    .into_iter()
+    .flatten()
+    .cloned()
+    .collect::<Vec<_>>()
+    .join(", ");
+
+    format!("Resume draft from {}: {}?", saved_at, fields)
 }
 
-#[derive(Debug)]
-enum Operator {
-    Add,
-    Sub,
-    Mul,
-    Div,
-}
-
-impl Operator {
-    fn from(c: &char) -> Option<Self> {
-        match c {
-            '+' => Some(Self::Add),
-            '-' => Some(Self::Sub),
-            '*' => Some(Self::Mul),
-            '/' => Some(Self::Div),
-            _ => None,
+#[test]
+fn resume_message_test() {
+    use notion::chrono::TimeZone;
+
+    let saved_at = notion::chrono::Local
+        .with_ymd_and_hms(2024, 5, 2, 14, 2, 0)
+        .unwrap()
+        .with_timezone(&notion::chrono::Utc);
+    let draft = crate::queue::Draft {
+        saved_at,
+        fields: crate::cli::AddArgs {
+            name: Some("Groceries".to_string()),
+            amount: Some("43.20".to_string()),
+            ..Default::default()
+        },
+    };
+
+    let today = notion::chrono::NaiveDate::from_ymd_opt(2024, 5, 2).unwrap();
+    assert_eq!(
+        resume_message(&draft, today),
+        "Resume draft from 14:02: Groceries, 43.20?"
+    );
+    assert_eq!(
+        resume_message(&draft, today.succ_opt().unwrap()),
+        "Resume draft from 2024-05-02 14:02: Groceries, 43.20?"
+    );
+}
+
+/// Whether `fields` are titled `title`, ignoring case.
+fn same_title(fields: &crate::cli::AddArgs, title: &str) -> bool {
+    fields
+        .name
+        .as_ref()
+        .is_some_and(|name| name.trim().eq_ignore_ascii_case(title.trim()))
+}
+
+/// The amount of the newest entry titled `title`.
+fn last_amount<'a>(history: &'a [crate::cli::AddArgs], title: &str) -> Option<&'a String> {
+    history
+        .iter()
+        .filter(|fields| same_title(fields, title))
+        .find_map(|fields| fields.amount.as_ref())
+}
+
+/// The category used most often for `title`, the most recently used one on a tie.
+fn frequent_category<'a>(history: &'a [crate::cli::AddArgs], title: &str) -> Option<&'a String> {
+    let mut counts: Vec<(&String, usize)> = vec![];
+    for category in history
+        .iter()
+        .filter(|fields| same_title(fields, title))
+        .filter_map(|fields| fields.category.as_ref())
+    {
+        match counts.iter_mut().find(|(name, _)| *name == category) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((category, 1)),
         }
     }
+
+    counts
+        .into_iter()
+        .rev()
+        .max_by_key(|(_, count)| *count)
+        .map(|(category, _)| category)
 }
 
-fn calc(expresion: &str) -> Result<f64> {
-    let mut op: Option<Operator> = None;
-    let mut pos: Option<usize> = None;
-    let mut stack: Vec<f64> = Vec::new();
+#[test]
+fn history_test() {
+    let fields = |name: &str, amount: &str, category: &str| crate::cli::AddArgs {
+        name: Some(name.to_string()),
+        amount: Some(amount.to_string()),
+        category: Some(category.to_string()),
+        ..Default::default()
+    };
 
-    for (i, c) in expresion.char_indices() {
-        if char::is_digit(c, 10) && pos.is_none() {
-            pos.replace(i);
-        } else if !char::is_digit(c, 10) && !matches!(c, ','|'.') {
-            if pos.is_some() {
-                let v = expresion[pos.take().unwrap()..i].parse()?;
-                stack.push(v);
+    let history = [
+        fields("Gym membership", "35.00", "Health"),
+        fields("Coffee", "3.50", "Food"),
+        fields("gym membership", "30.00", "Sport"),
+        fields("Gym membership", "30.00", "Sport"),
+        fields("Coffee", "3.00", "Treats"),
+    ];
 
-                if op.is_some() && stack.len() == 2 {
-                    let rhs: f64 = stack.remove(1);
-                    let lhs: f64 = stack.remove(0);
+    assert_eq!(
+        last_amount(&history, "gym membership").map(String::as_str),
+        Some("35.00")
+    );
+    assert_eq!(
+        frequent_category(&history, "Gym membership").map(String::as_str),
+        Some("Sport")
+    );
+    assert_eq!(
+        frequent_category(&history, "Coffee").map(String::as_str),
+        Some("Food")
+    );
+    assert_eq!(last_amount(&history, "Rent"), None);
+}
 
-                    let result = match op.take().unwrap() {
-                        Operator::Add => lhs + rhs,
-                        Operator::Sub => lhs - rhs,
-                        Operator::Mul => lhs * rhs,
-                        Operator::Div => lhs / rhs,
-                    };
+/// The last year in `title`, like 2024 in "Expenses 2024".
+fn title_year(title: &str) -> Option<i32> {
+    title
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|digits| digits.len() == 4)
+        .filter_map(|digits| digits.parse().ok())
+        .rfind(|year| (1900..2200).contains(year))
+}
 
-                    stack.push(result);
-                }
-            }
-            op = Operator::from(&c);
-        }
+/// `title` with its year moved on by one, or with this year added when it has
+/// none.
+fn next_year_title(title: &str, today: notion::chrono::NaiveDate) -> String {
+    use notion::chrono::Datelike;
 
+    match title_year(title) {
+        Some(year) => match title.rfind(&year.to_string()) {
+            Some(at) => format!("{}{}{}", &title[..at], year + 1, &title[at + 4..]),
+            None => title.to_string(),
+        },
+        None => format!("{} {}", title.trim(), today.year()),
     }
+}
+
+#[test]
+fn next_year_title_test() {
+    let today = notion::chrono::NaiveDate::from_ymd_opt(2026, 12, 28).unwrap();
+    assert_eq!(next_year_title("Expenses 2026", today), "Expenses 2027");
+    assert_eq!(next_year_title("2025 · Ausgaben", today), "2026 · Ausgaben");
+    assert_eq!(next_year_title("Expenses", today), "Expenses 2026");
+    assert_eq!(title_year("Flat 12345 in 2024"), Some(2024));
+}
 
-    if pos.is_some() {
-        let v = expresion[pos.take().unwrap()..].parse()?;
-        stack.push(v);
+/// The categories `init --create-database` starts the Categories database with.
+const STARTER_CATEGORIES: &[&str] = &[
+    "Groceries",
+    "Eating out",
+    "Housing",
+    "Utilities",
+    "Transport",
+    "Health",
+    "Shopping",
+    "Entertainment",
+    "Travel",
+    "Other",
+];
+
+/// `args` with what they leave out taken from `template` of `category`, the
+/// amount written in `format`.
+fn template_args(
+    args: &crate::cli::AddArgs,
+    template: &crate::settings::Template,
+    category: Option<&String>,
+    format: crate::calc::NumberFormat,
+) -> crate::cli::AddArgs {
+    let mut args = args.clone();
+    args.name.get_or_insert_with(|| template.title.clone());
+    if args.amount.is_none() {
+        args.amount = template.amount.map(|amount| format.format(amount));
+    }
+    if args.category.is_none() {
+        args.category = category.cloned();
     }
+    if args.tags.is_empty() {
+        args.tags = template.tags.clone();
+    }
+    args.entry_type
+        .get_or_insert_with(|| crate::report::EntryType::Expense.name().to_string());
+    args
+}
 
-    if op.is_some() && stack.len() == 2 {
-        let rhs: f64 = stack.remove(1);
-        let lhs: f64 = stack.remove(0);
+#[test]
+fn template_args_test() {
+    let template = crate::settings::Template {
+        title: "Coffee".to_string(),
+        amount: Some(rust_decimal::Decimal::new(1125, 3)),
+        tags: vec!["daily".to_string()],
+    };
+    let en = crate::calc::NumberFormat::for_locale("en");
+    let de = crate::calc::NumberFormat::for_locale("de");
+    let args = crate::cli::AddArgs {
+        amount: Some("4".to_string()),
+        ..Default::default()
+    };
 
-        let result = match op.take().unwrap() {
-            Operator::Add => lhs + rhs,
-            Operator::Sub => lhs - rhs,
-            Operator::Mul => lhs * rhs,
-            Operator::Div => lhs / rhs,
-        };
+    let args = template_args(&args, &template, Some(&"Food".to_string()), en);
+    assert_eq!(args.name.as_deref(), Some("Coffee"));
+    assert_eq!(args.amount.as_deref(), Some("4"));
+    assert_eq!(args.category.as_deref(), Some("Food"));
+    assert_eq!(args.tags, ["daily"]);
+    assert_eq!(args.date, None);
 
-        return Ok(result);
-    } else if op.is_none() && stack.len() == 1 {
-        return Ok(stack.remove(0));
-    }
+    let empty = crate::cli::AddArgs::default();
+    let amount = |format: crate::calc::NumberFormat| {
+        let filled = template_args(&empty, &template, None, format);
+        crate::calc::calc(&format.normalize(&filled.amount.unwrap())).unwrap()
+    };
+    assert_eq!(amount(en).to_string(), "1.125");
+    assert_eq!(amount(de).to_string(), "1.125");
+}
 
-    Err(eyre::Error::msg("Not expeceted"))
+/// The amount of `page` written in `format`, as the amount prompt takes it.
+fn page_amount_text(
+    page: &notion::models::Page,
+    property: &str,
+    format: crate::calc::NumberFormat,
+) -> Option<String> {
+    let amount = crate::report::page_amount(page, property)?;
+    Some(format.format(amount.to_string().parse().ok()?))
 }
 
 #[test]
-fn calc_test() {
-    let result = calc("10+10").unwrap();
-    assert_eq!(result, 20.0);
+fn page_amount_text_test() {
+    let page = crate::report::page(serde_json::json!({
+        "Amount": {"id": "a", "type": "number", "number": 1.125},
+    }));
+    let de = crate::calc::NumberFormat::for_locale("de");
 
-    let result = calc("10+10+10").unwrap();
-    assert_eq!(result, 30.0);
+    let text = page_amount_text(&page, "Amount", de).unwrap();
+    assert_eq!(text, "1,125");
+    assert_eq!(
+        crate::calc::calc(&de.normalize(&text)).unwrap().to_string(),
+        "1.125"
+    );
+    assert_eq!(page_amount_text(&page, "Cost", de), None);
+}
 
-    let result = calc("10+10.1+10").unwrap();
-    assert_eq!(result, 30.1);
+/// Entries of a category needed before its average is compared to.
+const MIN_AVERAGED_ENTRIES: usize = 3;
 
-    let result = calc("10+10+10-30").unwrap();
-    assert_eq!(result, 0.0);
+/// Whether `amount` is past `settings.limit`, or `settings.factor` times the
+/// average amount of `category` in `history`.
+fn is_unusually_large(
+    amount: rust_decimal::Decimal,
+    category: Option<&str>,
+    history: &[crate::cli::AddArgs],
+    settings: &crate::settings::LargeAmountSettings,
+) -> bool {
+    if settings.limit.is_some_and(|limit| amount > limit) {
+        return true;
+    }
+    let (Some(factor), Some(category)) = (settings.factor, category) else {
+        return false;
+    };
 
-    let result = calc("10-30+10+10+3").unwrap();
-    assert_eq!(result, 3.0);
+    let amounts: Vec<rust_decimal::Decimal> = history
+        .iter()
+        .filter(|fields| {
+            fields
+                .category
+                .as_deref()
+                .is_some_and(|name| name.eq_ignore_ascii_case(category))
+        })
+        .filter_map(|fields| fields.amount.as_deref()?.trim().parse().ok())
+        .collect();
+    if amounts.len() < MIN_AVERAGED_ENTRIES {
+        return false;
+    }
 
-    let result = calc("10/2").unwrap();
-    assert_eq!(result, 5.0);
+    let average =
+        amounts.iter().sum::<rust_decimal::Decimal>() / rust_decimal::Decimal::from(amounts.len());
+    amount > average * factor
+}
 
-    let result = calc("10*2").unwrap();
-    assert_eq!(result, 20.0);
+#[test]
+fn large_amount_test() {
+    let fields = |amount: &str, category: &str| crate::cli::AddArgs {
+        amount: Some(amount.to_string()),
+        category: Some(category.to_string()),
+        ..Default::default()
+    };
+    let history = [
+        fields("43.50", "Food"),
+        fields("38.00", "Food"),
+        fields("45.00", "food"),
+        fields("900.00", "Rent"),
+    ];
+    let amount = |amount: &str| amount.parse::<rust_decimal::Decimal>().unwrap();
+    let settings = crate::settings::LargeAmountSettings::default();
 
-    let result = calc("10").unwrap();
-    assert_eq!(result, 10.0);
+    assert!(is_unusually_large(
+        amount("4350"),
+        Some("Food"),
+        &history,
+        &settings
+    ));
+    assert!(!is_unusually_large(
+        amount("43.5"),
+        Some("Food"),
+        &history,
+        &settings
+    ));
+    // A single entry isn't enough to tell.
+    assert!(!is_unusually_large(
+        amount("4350"),
+        Some("Rent"),
+        &history,
+        &settings
+    ));
 
-    let result = calc("10.1").unwrap();
-    assert_eq!(result, 10.1);
+    let settings = crate::settings::LargeAmountSettings {
+        factor: None,
+        limit: Some(amount("1000")),
+    };
+    assert!(is_unusually_large(
+        amount("4350"),
+        Some("Rent"),
+        &history,
+        &settings
+    ));
+    assert!(!is_unusually_large(
+        amount("43.5"),
+        None,
+        &history,
+        &settings
+    ));
+}
 
-    // let result = calc("10+(10*3)").unwrap();
-    // assert_eq!(result, 40.0);
+/// Whether `other` looks like the same receipt as `fields`: the same date,
+/// title and amount.
+fn is_duplicate(fields: &crate::cli::AddArgs, other: &crate::cli::AddArgs) -> bool {
+    let amount = |fields: &crate::cli::AddArgs| {
+        fields
+            .amount
+            .as_deref()
+            .and_then(|amount| amount.trim().parse::<rust_decimal::Decimal>().ok())
+    };
 
-    let result = calc("10-10").unwrap();
-    assert_eq!(result, 0.0);
+    fields.date.is_some()
+        && fields.date == other.date
+        && other
+            .name
+            .as_deref()
+            .is_some_and(|name| same_title(fields, name))
+        && matches!((amount(fields), amount(other)), (Some(a), Some(b)) if crate::calc::round(a - b, 2).is_zero())
+}
 
-    let result = calc("10*10").unwrap();
-    assert_eq!(result, 100.0);
+#[test]
+fn duplicate_test() {
+    let fields = |name: &str, amount: &str, day: u32| crate::cli::AddArgs {
+        name: Some(name.to_string()),
+        amount: Some(amount.to_string()),
+        date: notion::chrono::NaiveDate::from_ymd_opt(2024, 5, day),
+        ..Default::default()
+    };
 
-    let result = calc("10/10").unwrap();
-    assert_eq!(result, 1.0);
+    assert!(is_duplicate(
+        &fields("Groceries", "43.2", 2),
+        &fields(" groceries", "43.20", 2)
+    ));
+    assert!(!is_duplicate(
+        &fields("Groceries", "43.2", 2),
+        &fields("Groceries", "43.2", 3)
+    ));
+    assert!(!is_duplicate(
+        &fields("Groceries", "43.2", 2),
+        &fields("Groceries", "12", 2)
+    ));
+    assert!(!is_duplicate(
+        &fields("Groceries", "43.2", 2),
+        &fields("Bakery", "43.2", 2)
+    ));
 }
 
-impl App {
-    pub fn new() -> Result<Self> {
-        let settings = crate::settings::Settings::new()?;
-        let notion_api = notion::NotionApi::new(settings.notion.api_key.clone())?;
+/// Remaining budget of a category, in red once it's exceeded.
+fn budget_line(category: &str, spent: f64, limit: f64) -> String {
+    use std::io::IsTerminal;
 
-        Ok(Self {
-            settings,
-            notion_api,
-            categories_cache: None,
-            last_date: None,
-        })
+    if spent <= limit {
+        return format!(
+            "{}: {:.2} of {:.2} left this month",
+            category,
+            limit - spent,
+            limit
+        );
     }
 
-    pub async fn run() -> Result<()> {
-        let mut app = Self::new()?;
+    let line = format!(
+        "{}: {:.2} over the monthly budget of {:.2}",
+        category,
+        spent - limit,
+        limit
+    );
 
-        let db: notion::models::Database = app
-            .notion_api
-            .get_database(&app.settings.notion.database_id)
-            .await?;
+    if std::io::stdout().is_terminal() {
+        format!("\x1b[31m{}\x1b[0m", line)
+    } else {
+        line
+    }
+}
 
-        let confirm = inquire::Confirm::new("Want to add one more row?").with_default(true);
+/// A spinner shown on stderr until it's dropped, hidden when that isn't a
+/// terminal.
+fn spinner(message: &'static str) -> indicatif::ProgressBar {
+    let spinner = indicatif::ProgressBar::new_spinner()
+        .with_message(message)
+        .with_finish(indicatif::ProgressFinish::AndClear);
+    spinner.enable_steady_tick(std::time::Duration::from_millis(100));
+    spinner
+}
 
-        let last5 = app
-            .get_database_pages(
-                &app.settings.notion.database_id,
-                Some(database_sorting("Date", 5)),
-            )
-            .await?;
+/// The local copy of the Notion database used by `--offline`.
+fn open_mirror(settings: &crate::settings::Settings) -> Result<crate::sync::Mirror> {
+    let path = settings.data_dir()?.join("mirror.db");
+    crate::sync::Mirror::open(&path, &settings.properties)
+}
 
-        for page in last5.iter().rev() {
-            let date = page_property_to_string(&page, "Date").unwrap_or_default();
-            let amount = page_property_to_string(&page, "Amount").unwrap_or_default();
-            println!(
-                "{} {} {}",
-                date,
-                page.title().unwrap_or("Untitled".to_string()),
-                amount
-            );
+/// The backend `storage` picks.
+fn open_backend(
+    settings: &crate::settings::Settings,
+) -> Result<std::sync::Arc<dyn crate::backend::ExpenseBackend>> {
+    Ok(match settings.storage {
+        crate::settings::Storage::Notion => {
+            std::sync::Arc::new(crate::backend::NotionBackend::new(&settings.notion)?)
+        }
+        crate::settings::Storage::Sqlite => {
+            let path = settings.data_dir()?.join("expenses.db");
+            std::sync::Arc::new(crate::sqlite::SqliteBackend::open(
+                &path,
+                &settings.properties,
+            )?)
         }
+    })
+}
 
-        loop {
-            app.create_page(&db).await?;
+/// Settings of the profile given with `--profile`, or picked from the ones in
+/// the config when there are any.
+fn load_settings(global: &crate::cli::GlobalArgs) -> Result<crate::settings::Settings> {
+    use std::io::IsTerminal;
 
-            match confirm.clone().prompt() {
-                Ok(true) => continue,
-                _ => {
-                    break;
-                }
-            }
-        }
+    let config = global.config.as_deref();
+    let mut profile = global.profile.clone();
 
-        Ok(())
-    }
+    if profile.is_none() && std::io::stdin().is_terminal() {
+        let profiles = crate::settings::Settings::profiles(config)?;
+        if !profiles.is_empty() {
+            let default = "default".to_string();
+            let mut options = vec![default.clone()];
+            options.extend(profiles);
 
-    async fn create_page(&mut self, db: &notion::models::Database) -> Result<notion::models::Page> {
-        let properties = self.create_page_properties(&db.properties).await?;
+            profile = Some(inquire::Select::new("Profile:", options).prompt()?)
+                .filter(|choice| *choice != default);
+        }
+    }
 
-        let request = notion::models::PageCreateRequest {
-            parent: notion::models::Parent::Database {
-                database_id: db.id.clone(),
-            },
-            properties: notion::models::Properties { properties },
-        };
+    let mut settings = crate::settings::Settings::new(config, profile.as_deref())?;
+    let databases: Vec<String> = settings.notion.databases.keys().cloned().collect();
+    let database = match &global.database {
+        Some(name) => Some(name.clone()),
+        None if databases.len() > 1 && !global.sandbox && std::io::stdin().is_terminal() => {
+            let cursor = settings
+                .notion
+                .current_database()
+                .and_then(|current| databases.iter().position(|name| name == current))
+                .unwrap_or(databases.len() - 1);
+            Some(
+                inquire::Select::new("Database:", databases)
+                    .with_starting_cursor(cursor)
+                    .prompt()?,
+            )
+        }
+        None => None,
+    };
+    if let Some(name) = database {
+        settings.notion.use_database(&name)?;
+    }
 
-        self.notion_api
-            .create_page(request)
-            .await
-            .map_err(eyre::Error::new)
+    settings.read_only = global.read_only;
+    settings.sandbox = global.sandbox;
+    if global.sandbox && settings.storage == crate::settings::Storage::Notion {
+        settings.notion.database_id = settings.notion.sandbox.clone().ok_or_else(|| {
+            eyre!("Set `notion.sandbox` to the test database `--sandbox` works on")
+        })?;
     }
 
-    async fn get_database_pages(
-        &self,
-        database_id: &notion::ids::DatabaseId,
-        query: Option<notion::models::search::DatabaseQuery>,
-    ) -> Result<Vec<notion::models::Page>> {
-        let result = self
-            .notion_api
-            .query_database(database_id, query.unwrap_or_default())
-            .await?;
+    Ok(settings)
+}
 
-        Ok(result.results)
+/// Prints the predefined expenses as `title: category` lines.
+fn print_map(settings: &crate::settings::Settings) {
+    for name in settings.list() {
+        println!(
+            "{}: {}",
+            name,
+            settings.get(name).cloned().unwrap_or_default()
+        );
     }
+}
 
-    async fn create_page_properties(
-        &mut self,
-        db_properties: &HashMap<String, notion::models::properties::PropertyConfiguration>,
-    ) -> Result<HashMap<String, notion::models::properties::PropertyValue>> {
-        let mut properties: HashMap<String, notion::models::properties::PropertyValue> =
-            HashMap::new();
+/// The database the relation `property` points to.
+fn relation_database_id(
+    db: &notion::models::Database,
+    property: &str,
+) -> Option<notion::ids::DatabaseId> {
+    match db.properties.get(property) {
+        Some(notion::models::properties::PropertyConfiguration::Relation { relation, .. }) => {
+            Some(relation.database_id.clone())
+        }
+        _ => None,
+    }
+}
 
-        let mut preselect = None;
+/// Select prompt with the cursor on the option whose label equals `preselect`.
+fn select_with_preselect<T: std::fmt::Display>(
+    message: &str,
+    options: Vec<T>,
+    preselect: Option<&String>,
+) -> Result<T> {
+    let pos = preselect.and_then(|ps| options.iter().position(|p| p.to_string() == ps.as_str()));
 
-        if let Some(notion::models::properties::PropertyConfiguration::Title { id }) =
-            db_properties.get("Name")
-        {
-            let name = inquire::Text::new("Name:")
-                .with_autocomplete(TitleCompleter::new(self.settings.list()))
-                .prompt()?;
+    let mut select = inquire::Select::new(message, options);
+    if let Some(pos) = pos {
+        select = select.with_starting_cursor(pos);
+    }
 
-            let title = vec![notion::models::text::RichText::Text {
-                rich_text: notion::models::text::RichTextCommon {
-                    plain_text: name.clone(),
-                    href: None,
-                    annotations: None,
-                },
-                text: notion::models::text::Text {
-                    content: name.clone(),
-                    link: None,
-                },
-            }];
+    Ok(select.prompt()?)
+}
 
-            preselect = self.settings.get(name.as_ref());
+/// The picked category page, or `None` when a new category should be created.
+fn select_page(
+    pages: &[notion::models::Page],
+    preselect: Option<&String>,
+) -> Result<Option<notion::ids::PageId>> {
+    struct Page<'a> {
+        page: Option<&'a notion::models::Page>,
+    }
 
-            properties.insert(
-                "Name".to_string(),
-                notion::models::properties::PropertyValue::Title {
-                    id: id.clone(),
-                    title,
-                },
-            );
+    impl<'a> std::fmt::Display for Page<'a> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self.page {
+                Some(page) => write!(f, "{}", page.title().unwrap_or("Untitled".to_string())),
+                None => write!(f, "+ New category…"),
+            }
         }
+    }
 
-        if let Some(notion::models::properties::PropertyConfiguration::Number { id, .. }) =
-            db_properties.get("Amount")
-        {
-            let amount = inquire::Text::new("Amount:").prompt()?;
-            let amount = calc(&amount)?;
+    let mut options: Vec<Page> = pages.iter().map(|page| Page { page: Some(page) }).collect();
+    options.push(Page { page: None });
+    let result = select_with_preselect("Category:", options, preselect)?;
 
-            properties.insert(
-                "Amount".to_string(),
-                notion::models::properties::PropertyValue::Number {
-                    id: id.clone(),
-                    number: serde_json::Number::from_f64(amount),
-                },
-            );
+    Ok(result.page.map(|page| page.id.clone()))
+}
+
+/// Select prompt for the trip, starting at "No trip" unless `preselect` is one.
+fn select_trip(
+    pages: &[notion::models::Page],
+    preselect: Option<&String>,
+) -> Result<Option<notion::ids::PageId>> {
+    struct Trip<'a> {
+        page: Option<&'a notion::models::Page>,
+    }
+
+    impl<'a> std::fmt::Display for Trip<'a> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self.page {
+                Some(page) => write!(f, "{}", page.title().unwrap_or("Untitled".to_string())),
+                None => write!(f, "No trip"),
+            }
         }
+    }
 
-        if let Some(notion::models::properties::PropertyConfiguration::Date { id }) =
-            db_properties.get("Date")
-        {
-            let now = notion::chrono::offset::Local::now().date_naive();
-            let default_date = self.last_date.unwrap_or(now);
-
-            let date = inquire::DateSelect::new("Date:")
-                .with_default(default_date)
-                .with_min_date(now.checked_sub_days(notion::chrono::Days::new(30)).unwrap())
-                .with_max_date(now)
-                .with_week_start(notion::chrono::Weekday::Mon)
-                .prompt()?;
+    let options: Vec<Trip> = std::iter::once(Trip { page: None })
+        .chain(pages.iter().map(|page| Trip { page: Some(page) }))
+        .collect();
+    let result = select_with_preselect("Trip:", options, preselect)?;
 
-            properties.insert(
-                "Date".to_string(),
-                notion::models::properties::PropertyValue::Date {
-                    id: id.clone(),
-                    date: Some(notion::models::properties::DateValue {
-                        start: notion::models::properties::DateOrDateTime::Date(date),
-                        end: None,
-                        time_zone: None,
-                    }),
-                },
-            );
+    Ok(result.page.map(|page| page.id.clone()))
+}
+
+fn select_option(
+    message: &str,
+    options: &[notion::models::properties::SelectOption],
+    preselect: Option<&String>,
+) -> Result<notion::models::properties::SelectOption> {
+    struct SelectOption<'a> {
+        option: &'a notion::models::properties::SelectOption,
+    }
 
-            self.last_date = Some(date);
+    impl<'a> std::fmt::Display for SelectOption<'a> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", self.option.name)
         }
+    }
 
-        if let Some(notion::models::properties::PropertyConfiguration::Relation { id, relation }) =
-            db_properties.get("Category")
-        {
-            if self.categories_cache.is_none() {
-                self.categories_cache = self
-                    .get_database_pages(&relation.database_id, None)
-                    .await
-                    .ok();
-            }
+    let options: Vec<SelectOption> = options
+        .iter()
+        .map(|option| SelectOption { option })
+        .collect();
+    let result = select_with_preselect(message, options, preselect)?;
 
-            if let Some(pages) = &self.categories_cache {
-                let page_id = select_page(&pages, preselect)?;
+    Ok(result.option.clone())
+}
 
-                properties.insert(
-                    "Category".to_string(),
-                    notion::models::properties::PropertyValue::Relation {
-                        id: id.clone(),
-                        relation: Some(vec![notion::models::properties::RelationValue {
-                            id: page_id,
-                        }]),
-                    },
-                );
+/// Asks for the image or PDF of a receipt, `None` when it's left empty.
+fn prompt_attachment() -> Result<Option<std::path::PathBuf>> {
+    let input = inquire::Text::new("Receipt file:")
+        .with_help_message("Leave empty to skip")
+        .with_validator(|input: &str| {
+            if input.trim().is_empty() {
+                return Ok(inquire::validator::Validation::Valid);
             }
-        }
+
+            Ok(
+                match crate::attachment::check(&crate::attachment::typed_path(input)) {
+                    Ok(()) => inquire::validator::Validation::Valid,
+                    Err(err) => inquire::validator::Validation::Invalid(err.to_string().into()),
+                },
+            )
+        })
+        .prompt_skippable()?;
+
+    Ok(input
+        .filter(|input| !input.trim().is_empty())
+        .map(|input| crate::attachment::typed_path(&input)))
+}
+
+/// Asks for the line items of a receipt until they add up to `amount`, none
+/// when it's left with Esc.
+fn prompt_items(amount: rust_decimal::Decimal) -> Result<Vec<crate::receipt::LineItem>> {
+    let mut items = vec![];
+    loop {
+        let left = amount - crate::receipt::total(&items);
+        let line = inquire::Text::new(&format!(
+            "Item ({} left):",
+            crate::report::format_amount(crate::calc::to_f64(left))
+        ))
+        .with_validator(|input: &str| {
+            Ok(match input.trim() {
+                "" => inquire::validator::Validation::Valid,
+                line => match crate::receipt::parse_item(line) {
+                    Ok(_) => inquire::validator::Validation::Valid,
+                    Err(err) => inquire::validator::Validation::Invalid(err.into()),
+                },
+            })
+        })
+        .with_help_message("Name and price like `Milk 2.49`, empty when done, Esc to drop them")
+        .prompt_skippable()?;
+
+        match line.as_deref().map(str::trim) {
+            None => return Ok(vec![]),
+            Some("") => match crate::receipt::check_total(&items, amount) {
+                Ok(()) => return Ok(items),
+                Err(err) => eprintln!("{}", err),
+            },
+            Some(line) => items.push(crate::receipt::parse_item(line)?),
+        }
+    }
+}
+
+/// Whether `input` is an amount, or a split across known `categories` when
+/// splitting is allowed.
+fn check_amount(
+    input: &str,
+    categories: Option<&[String]>,
+    variables: &crate::calc::Variables,
+) -> Result<()> {
+    let err = match crate::calc::calc_with(input, variables) {
+        Ok(_) => return Ok(()),
+        Err(err) => err,
+    };
+    let Some(split) = crate::split::parse(input)? else {
+        return Err(err);
+    };
+    let categories = categories.ok_or_else(|| eyre!("A changed amount can't be split"))?;
+
+    match split.parts.iter().find(|part| {
+        !categories
+            .iter()
+            .any(|category| category.eq_ignore_ascii_case(&part.category))
+    }) {
+        Some(part) => Err(eyre!("Unknown category: {}", part.category)),
+        None => Ok(()),
+    }
+}
+
+/// The Currency select set to `currency`, adding it when it isn't an option.
+fn currency_value(
+    db_properties: &HashMap<String, notion::models::properties::PropertyConfiguration>,
+    property: &str,
+    currency: Option<String>,
+) -> Option<notion::models::properties::PropertyValue> {
+    let Some(notion::models::properties::PropertyConfiguration::Select { id, select }) =
+        db_properties.get(property)
+    else {
+        return None;
+    };
+
+    Some(notion::models::properties::PropertyValue::Select {
+        id: id.clone(),
+        select: Some(crate::prompt::tag_values(&select.options, &[currency?]).pop()?),
+    })
+}
+
+fn find_option<'a>(
+    options: &'a [notion::models::properties::SelectOption],
+    name: &str,
+) -> Option<&'a notion::models::properties::SelectOption> {
+    let name = name.to_lowercase();
+    options
+        .iter()
+        .find(|option| option.name.to_lowercase() == name)
+}
+
+fn find_page<'a>(
+    pages: &'a [notion::models::Page],
+    title: &str,
+) -> Option<&'a notion::models::Page> {
+    let title = title.to_lowercase();
+    pages
+        .iter()
+        .find(|page| page.title().is_some_and(|t| t.to_lowercase() == title))
+}
+
+fn page_property_to_string(page: &notion::models::Page, name: &str) -> Option<String> {
+    page.properties
+        .properties
+        .get(name)
+        .and_then(property_value_to_string)
+}
+
+fn local_time(date_time: &notion::chrono::DateTime<notion::chrono::Utc>) -> String {
+    date_time
+        .with_timezone(&notion::chrono::Local)
+        .format("%Y-%m-%d %H:%M")
+        .to_string()
+}
+
+fn date_value_to_string(date: &notion::models::properties::DateValue) -> String {
+    let to_string = |date: &notion::models::properties::DateOrDateTime| match date {
+        notion::models::properties::DateOrDateTime::Date(date) => date.to_string(),
+        notion::models::properties::DateOrDateTime::DateTime(date_time) => local_time(date_time),
+    };
+
+    match &date.end {
+        Some(end) => format!("{} → {}", to_string(&date.start), to_string(end)),
+        None => to_string(&date.start),
+    }
+}
+
+fn rich_text_to_string(text: &[notion::models::text::RichText]) -> String {
+    text.iter().map(|text| text.plain_text()).collect()
+}
+
+fn selected_names(values: &[notion::models::properties::SelectedValue]) -> String {
+    values
+        .iter()
+        .filter_map(|value| value.name.as_deref())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn formula_to_string(formula: &notion::models::properties::FormulaResultValue) -> Option<String> {
+    use notion::models::properties::FormulaResultValue;
+
+    match formula {
+        FormulaResultValue::String { string } => string.clone(),
+        FormulaResultValue::Number { number } => number.as_ref().map(|v| v.to_string()),
+        FormulaResultValue::Boolean { boolean } => boolean.map(|v| v.to_string()),
+        FormulaResultValue::Date { date } => date.as_ref().map(date_value_to_string),
+    }
+}
+
+fn rollup_to_string(rollup: &notion::models::properties::RollupValue) -> Option<String> {
+    use notion::models::properties::{RollupPropertyValue, RollupValue};
+
+    match rollup {
+        RollupValue::Number { number } => number.as_ref().map(|v| v.to_string()),
+        RollupValue::Date { date } => date.as_ref().map(local_time),
+        RollupValue::Array { array } => Some(
+            array
+                .iter()
+                .filter_map(|value| match value {
+                    RollupPropertyValue::Text { rich_text } => Some(rich_text_to_string(rich_text)),
+                    RollupPropertyValue::Number { number } => {
+                        number.as_ref().map(|v| v.to_string())
+                    }
+                    RollupPropertyValue::Select { select }
+                    | RollupPropertyValue::Status { status: select } => {
+                        select.as_ref().and_then(|select| select.name.clone())
+                    }
+                    RollupPropertyValue::Date { date } => date.as_ref().map(date_value_to_string),
+                    RollupPropertyValue::Formula { formula } => formula_to_string(formula),
+                    RollupPropertyValue::Checkbox { checkbox } => Some(checkbox.to_string()),
+                    RollupPropertyValue::Url { url } => url.clone(),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+    }
+}
+
+fn property_value_to_string(value: &notion::models::properties::PropertyValue) -> Option<String> {
+    use notion::models::properties::PropertyValue;
+
+    match value {
+        PropertyValue::Title { title, .. } => Some(rich_text_to_string(title)),
+        PropertyValue::Text { rich_text, .. } => Some(rich_text_to_string(rich_text)),
+        PropertyValue::Number { number, .. } => number.as_ref().map(|v| v.to_string()),
+        PropertyValue::Select { select, .. } | PropertyValue::Status { status: select, .. } => {
+            select.as_ref().and_then(|select| select.name.clone())
+        }
+        PropertyValue::MultiSelect { multi_select, .. } => {
+            multi_select.as_deref().map(selected_names)
+        }
+        PropertyValue::Date { date, .. } => date.as_ref().map(date_value_to_string),
+        PropertyValue::Checkbox { checkbox, .. } => Some(checkbox.to_string()),
+        PropertyValue::Formula { formula, .. } => formula_to_string(formula),
+        PropertyValue::Rollup { rollup, .. } => rollup.as_ref().and_then(rollup_to_string),
+        PropertyValue::Url { url, .. } => url.clone(),
+        PropertyValue::CreatedTime { created_time, .. } => Some(local_time(created_time)),
+        PropertyValue::Relation { relation, .. } => relation.as_ref().map(|relation| {
+            relation
+                .iter()
+                .map(|value| value.id.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        }),
+        _ => None,
+    }
+}
+
+#[test]
+fn property_value_to_string_test() {
+    let value = |json: serde_json::Value| -> notion::models::properties::PropertyValue {
+        serde_json::from_value(json).unwrap()
+    };
+
+    let cases = [
+        (
+            serde_json::json!({"id": "s", "type": "select", "select": {"name": "Food", "color": "default"}}),
+            Some("Food"),
+        ),
+        (
+            serde_json::json!({"id": "m", "type": "multi_select", "multi_select": [
+                {"name": "a", "color": "default"}, {"name": "b", "color": "default"}
+            ]}),
+            Some("a, b"),
+        ),
+        (
+            serde_json::json!({"id": "c", "type": "checkbox", "checkbox": true}),
+            Some("true"),
+        ),
+        (
+            serde_json::json!({"id": "f", "type": "formula", "formula": {"type": "number", "number": 2.5}}),
+            Some("2.5"),
+        ),
+        (
+            serde_json::json!({"id": "d", "type": "date", "date": {"start": "2024-05-01", "end": "2024-05-03"}}),
+            Some("2024-05-01 → 2024-05-03"),
+        ),
+        (
+            serde_json::json!({"id": "r", "type": "rollup", "rollup": {"type": "number", "number": 7}}),
+            Some("7"),
+        ),
+        (
+            serde_json::json!({"id": "u", "type": "url", "url": null}),
+            None,
+        ),
+        (
+            serde_json::json!({"id": "p", "type": "people", "people": []}),
+            None,
+        ),
+    ];
+
+    for (json, expected) in cases {
+        assert_eq!(property_value_to_string(&value(json)).as_deref(), expected);
+    }
+}
+
+/// Orders titles (given newest first) by how often they were used, ties going
+/// to the most recent one.
+fn rank_titles<'a>(titles: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut ranked: Vec<(String, usize)> = vec![];
+
+    for title in titles {
+        match ranked.iter_mut().find(|(t, _)| t.as_str() == title) {
+            Some((_, count)) => *count += 1,
+            None => ranked.push((title.to_string(), 1)),
+        }
+    }
+
+    // The sort is stable, so equally frequent titles keep their recency order.
+    ranked.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    ranked.into_iter().map(|(title, _)| title).collect()
+}
+
+#[test]
+fn rank_titles_test() {
+    let ranked = rank_titles(["Bread", "Coffee", "Milk", "Coffee", "Bread", "Coffee"]);
+    assert_eq!(ranked, vec!["Coffee", "Bread", "Milk"]);
+}
+
+#[derive(Clone)]
+struct TitleCompleter {
+    input: String,
+    prev_titles: Vec<String>,
+    output: Vec<String>,
+}
+
+impl TitleCompleter {
+    fn new(titles: Vec<&str>) -> Self {
+        Self {
+            prev_titles: titles.into_iter().map(|t| t.to_owned()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn update_input(&mut self, input: &str) -> Result<(), inquire::CustomUserError> {
+        if self.input == input {
+            return Ok(());
+        }
+
+        self.input = input.to_owned();
+        self.output.clear();
+
+        let matcher = fuzzy_matcher::skim::SkimMatcherV2::default().ignore_case();
+        let mut scored: Vec<(i64, &String)> = self
+            .prev_titles
+            .iter()
+            .filter_map(|item| matcher.fuzzy_match(item, input).map(|score| (score, item)))
+            .collect();
+
+        // Stable sort, so equally good matches stay in history order.
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        self.output
+            .extend(scored.into_iter().map(|(_, item)| item.to_string()));
+
+        Ok(())
+    }
+}
+
+#[test]
+fn title_completer_test() {
+    let mut completer =
+        TitleCompleter::new(vec!["Morning Coffee", "Cola", "Coffee beans", "Bread"]);
+
+    completer.update_input("coffee").unwrap();
+    assert_eq!(completer.output, vec!["Coffee beans", "Morning Coffee"]);
+
+    completer.update_input("cfe").unwrap();
+    assert_eq!(completer.output.len(), 2);
+
+    completer.update_input("").unwrap();
+    assert_eq!(completer.output.len(), 4);
+}
+
+impl Default for TitleCompleter {
+    fn default() -> Self {
+        Self {
+            input: "".to_string(),
+            output: vec![],
+            prev_titles: vec![],
+        }
+    }
+}
+
+impl Autocomplete for TitleCompleter {
+    fn get_suggestions(&mut self, input: &str) -> Result<Vec<String>, inquire::CustomUserError> {
+        self.update_input(input)?;
+
+        Ok(self.output.clone())
+    }
+
+    fn get_completion(
+        &mut self,
+        input: &str,
+        highlighted_suggestion: Option<String>,
+    ) -> Result<Replacement, inquire::CustomUserError> {
+        self.update_input(input)?;
+
+        Ok(match highlighted_suggestion {
+            Some(suggestion) => Replacement::Some(suggestion),
+            None => {
+                if self.output.len() == 1 {
+                    Replacement::Some(self.output[0].clone())
+                } else {
+                    Replacement::None
+                }
+            }
+        })
+    }
+}
+
+/// Suggests the earlier inputs starting with what's typed, newest first, like
+/// a shell's history.
+#[derive(Clone)]
+struct HistoryCompleter {
+    inputs: Vec<String>,
+}
+
+impl Autocomplete for HistoryCompleter {
+    fn get_suggestions(&mut self, input: &str) -> Result<Vec<String>, inquire::CustomUserError> {
+        let input = input.trim_start();
+        Ok(self
+            .inputs
+            .iter()
+            .filter(|item| item.starts_with(input) && item.as_str() != input)
+            .cloned()
+            .collect())
+    }
+
+    fn get_completion(
+        &mut self,
+        _input: &str,
+        highlighted_suggestion: Option<String>,
+    ) -> Result<Replacement, inquire::CustomUserError> {
+        Ok(highlighted_suggestion)
+    }
+}
+
+#[test]
+fn history_completer_test() {
+    let mut completer = HistoryCompleter {
+        inputs: vec!["12.50".to_string(), "lunch+2".to_string(), "12".to_string()],
+    };
+
+    assert_eq!(completer.get_suggestions("12").unwrap(), ["12.50"]);
+    assert_eq!(completer.get_suggestions("").unwrap().len(), 3);
+    assert!(completer.get_suggestions("7").unwrap().is_empty());
+}
+
+impl App {
+    pub fn new(global: &crate::cli::GlobalArgs) -> Result<Self> {
+        let settings = load_settings(global)?;
+        let mut backend: std::sync::Arc<dyn crate::backend::ExpenseBackend> = match global.offline {
+            true => std::sync::Arc::new(open_mirror(&settings)?),
+            false => open_backend(&settings)?,
+        };
+        if settings.read_only {
+            backend = std::sync::Arc::new(crate::backend::ReadOnlyBackend(backend));
+        }
+
+        let mut app = Self::with_backend(settings, backend)?;
+        app.offline = global.offline;
+        Ok(app)
+    }
+
+    pub fn with_backend(
+        settings: crate::settings::Settings,
+        backend: std::sync::Arc<dyn crate::backend::ExpenseBackend>,
+    ) -> Result<Self> {
+        let queue = crate::queue::Queue::new(&settings)?;
+        let prompt_history = queue.prompt_history();
+
+        Ok(Self {
+            settings,
+            backend,
+            queue,
+            categories_cache: None,
+            category_cache: crate::cache::CategoryCache::new()?,
+            trips_cache: None,
+            trip_cache: crate::cache::CategoryCache::named("trips")?,
+            refresh: false,
+            json: false,
+            offline: false,
+            last_date: None,
+            split: None,
+            title_history: vec![],
+            prompt_history,
+            history: vec![],
+            created: vec![],
+            drafting: false,
+            review_all: false,
+            uploads: vec![],
+        })
+    }
+
+    pub async fn run(cli: crate::cli::Cli) -> Result<()> {
+        let global = cli.global;
+
+        let result = match cli.command.unwrap_or(crate::cli::Command::Add {
+            line: None,
+            open: false,
+            from_receipt: None,
+            from_clipboard: false,
+            args: Default::default(),
+        }) {
+            crate::cli::Command::Add {
+                line,
+                open,
+                from_receipt,
+                from_clipboard,
+                args,
+            } => {
+                let today = notion::chrono::offset::Local::now().date_naive();
+                let args = match line {
+                    Some(line) => args.or(crate::quick::parse_line(&line, today)),
+                    None if from_clipboard => {
+                        let text = arboard::Clipboard::new()
+                            .and_then(|mut clipboard| clipboard.get_text())
+                            .map_err(|err| eyre!("Couldn't read the clipboard: {}", err))?;
+                        args.or(crate::quick::parse_copied(&text, today))
+                    }
+                    None => *args,
+                };
+                let mut app = Self::start(&global).await?;
+                // Copied text is read less reliably than a typed line.
+                app.review_all = from_clipboard;
+                app.settings.display.open_created |= open;
+                let args = match from_receipt {
+                    Some(path) => app.read_receipt(&path, args).await?,
+                    None => args,
+                };
+                app.add(args).await
+            }
+            crate::cli::Command::List(args) => Self::start(&global).await?.list(args).await,
+            crate::cli::Command::Report {
+                command,
+                format,
+                output,
+                email,
+            } => {
+                Self::start(&global)
+                    .await?
+                    .report(command, format, output, email)
+                    .await
+            }
+            crate::cli::Command::Batch => Self::start(&global).await?.batch().await,
+            crate::cli::Command::Import { command } => {
+                Self::start(&global).await?.import(command).await
+            }
+            crate::cli::Command::Ingest {
+                command: crate::cli::IngestCommand::Imap { days },
+            } => Self::start(&global).await?.ingest_imap(days).await,
+            crate::cli::Command::Recurring {
+                command: crate::cli::RecurringCommand::Run,
+            } => Self::start(&global).await?.run_recurring().await,
+            crate::cli::Command::Export { output, format } => {
+                Self::start(&global).await?.export(&output, format).await
+            }
+            crate::cli::Command::Edit => Self::start(&global).await?.edit().await,
+            crate::cli::Command::Undo => Self::start(&global).await?.undo().await,
+            crate::cli::Command::Again => Self::start(&global).await?.again().await,
+            crate::cli::Command::Dedupe => Self::start(&global).await?.dedupe().await,
+            crate::cli::Command::Refund { amount } => {
+                Self::start(&global).await?.refund(amount).await
+            }
+            crate::cli::Command::Check { fix } => Self::start(&global).await?.check(fix).await,
+            crate::cli::Command::Sync { full } => Self::new(&global)?.sync_mirror(full).await,
+            crate::cli::Command::Rollover { title, archive } => {
+                Self::start(&global).await?.rollover(title, archive).await
+            }
+            crate::cli::Command::Config { command: None } => Self::show_config(&global),
+            crate::cli::Command::Init { .. }
+            | crate::cli::Command::Config {
+                command: Some(crate::cli::ConfigCommand::SetKey),
+            } if global.read_only => Err(crate::settings::read_only_error()),
+            crate::cli::Command::Config {
+                command: Some(crate::cli::ConfigCommand::SetKey),
+            } => Self::set_key(&global),
+            crate::cli::Command::Config {
+                command: Some(crate::cli::ConfigCommand::Map { command }),
+            } => Self::edit_map(&global, command),
+            crate::cli::Command::Rules {
+                command: crate::cli::RulesCommand::Test { title, amount },
+            } => Self::test_rules(&global, &title, amount.as_deref()),
+            crate::cli::Command::Init { create_database } => {
+                Self::init(global.config.as_deref(), create_database).await
+            }
+            crate::cli::Command::Doctor => Self::doctor(&global).await,
+            #[cfg(unix)]
+            crate::cli::Command::Daemon { pipe } => {
+                let app = Self::start(&global).await?;
+                let pipe = match pipe {
+                    Some(pipe) => pipe,
+                    None => crate::settings::project_dirs()?.cache_dir().join("pipe"),
+                };
+                crate::daemon::run(app, &pipe).await
+            }
+            crate::cli::Command::Digest { email } => {
+                Self::start(&global).await?.digest(email).await
+            }
+            crate::cli::Command::Tui => crate::ui::run(Self::start(&global).await?).await,
+            crate::cli::Command::Completions { shell } => {
+                crate::completion::write_registration(shell, &mut std::io::stdout())
+            }
+            crate::cli::Command::Serve { listen } => {
+                let app = Self::start(&global).await?;
+                let listen = listen.unwrap_or(app.settings.server.listen.clone());
+                let token = app.settings.server.token.clone();
+                crate::server::serve(app, &listen, token).await
+            }
+        };
+
+        // Leaving a prompt with Esc or Ctrl+C just ends the command.
+        match result {
+            Err(err) if is_canceled(&err) => Ok(()),
+            result => result,
+        }
+    }
+
+    /// Creates the app and uploads whatever is left in the queue from previous runs.
+    #[tracing::instrument(skip_all)]
+    async fn start(global: &crate::cli::GlobalArgs) -> Result<Self> {
+        let mut app = Self::new(global)?;
+        app.refresh = global.refresh;
+        app.json = global.json;
+        if let Some(recent) = global.recent {
+            app.settings.display.recent_count = recent;
+        }
+
+        if !app.settings.read_only && !app.queue.pending()?.is_empty() {
+            app.sync().await?;
+        }
+
+        Ok(app)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn sync(&self) -> Result<()> {
+        let pending = self.queue.pending()?;
+        if pending.is_empty() {
+            println!("Nothing to sync");
+            return Ok(());
+        }
+
+        let mut uploaded = 0;
+        for entry in &pending {
+            match self.backend.create_entry(entry.properties.clone()).await {
+                Ok(page) => {
+                    self.queue.mark_uploaded(entry)?;
+                    self.append_items(&page.id, &entry.items).await;
+                    self.attach_receipt(&page, entry.attachment.as_deref())
+                        .await;
+                    uploaded += 1;
+                }
+                Err(err) => eprintln!("Couldn't upload entry from {}: {}", entry.created_at, err),
+            }
+        }
+
+        // Keeps stdout to the JSON when syncing before another command.
+        let message = format!("Synced {} of {} pending entries", uploaded, pending.len());
+        if self.json {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
+        }
+
+        Ok(())
+    }
+
+    /// Syncs the queue, then the local copy with Notion both ways.
+    async fn sync_mirror(&self, full: bool) -> Result<()> {
+        self.settings.check_writes()?;
+        if self.offline {
+            return Err(eyre!("Notion can't be synced with `--offline`"));
+        }
+        self.sync().await?;
+        if self.settings.storage != crate::settings::Storage::Notion {
+            return Ok(());
+        }
+
+        let summary = {
+            let _spinner = spinner("Updating the local copy…");
+            open_mirror(&self.settings)?
+                .sync(&*self.backend, &self.settings.properties.category, full)
+                .await?
+        };
+
+        if summary.pushed > 0 {
+            println!("Uploaded {} entries added offline", summary.pushed);
+        }
+        println!("Updated {} entries in the local copy", summary.pulled);
+
+        Ok(())
+    }
+
+    async fn get_database(&mut self) -> Result<notion::models::Database> {
+        let spinner = spinner("Loading the database…");
+        let db = self.load_database().await?;
+        spinner.finish_and_clear();
+        self.check_schema(&db)?;
+
+        Ok(db)
+    }
+
+    /// Offers to read the name, amount, date or category properties that are
+    /// gone from the ones of their type they were likely renamed to, and saves
+    /// the pick in the config.
+    fn check_schema(&mut self, db: &notion::models::Database) -> Result<()> {
+        use std::io::IsTerminal;
+
+        for drift in crate::doctor::schema_drift(db, &self.settings.properties) {
+            if drift.candidates.is_empty()
+                || !self.settings.is_writable()
+                || !std::io::stdin().is_terminal()
+            {
+                eprintln!(
+                    "The database has no `{}` property, rename it under `properties` in the config",
+                    drift.name
+                );
+                continue;
+            }
+
+            let keep = format!("Keep looking for `{}`", drift.name);
+            let mut options = drift.candidates.clone();
+            options.push(keep.clone());
+            let picked = inquire::Select::new(
+                &format!(
+                    "The database has no `{}` property, which one is it now?",
+                    drift.name
+                ),
+                options,
+            )
+            .with_help_message("Only the name, amount, date and category are checked")
+            .prompt_skippable()?;
+
+            if let Some(picked) = picked.filter(|picked| *picked != keep) {
+                self.settings.rename_property(drift.setting, &picked)?;
+                self.status(format!(
+                    "Using `{}` as the {} from now on",
+                    picked, drift.setting
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    pub(crate) async fn load_database(&self) -> Result<notion::models::Database> {
+        let database_id = &self.settings.notion.database_id;
+
+        match self.backend.schema().await {
+            Ok(db) => {
+                self.queue.store_database(&db)?;
+                Ok(db)
+            }
+            Err(err) => {
+                tracing::warn!(error = %err, "Couldn't load the schema, using the cached one");
+                self.queue.cached_database(database_id).ok_or(err)
+            }
+        }
+    }
+
+    async fn add(&mut self, args: crate::cli::AddArgs) -> Result<()> {
+        if !args.is_empty() {
+            let db = self.get_database().await?;
+            self.create_page(&db, &args).await?;
+            return Ok(());
+        }
+
+        let (db, recent) = self.prepare().await?;
+        if !recent.is_empty() && !self.json {
+            let count = recent.len().min(self.settings.display.recent_count.into());
+            self.print_entries(&recent[..count]);
+        }
+
+        let mut draft = None;
+        if let Some(saved) = self.queue.take_draft()? {
+            let today = notion::chrono::offset::Local::now().date_naive();
+            let resume = inquire::Confirm::new(&resume_message(&saved, today))
+                .with_default(true)
+                .prompt_skippable()?;
+            if resume == Some(true) {
+                draft = Some(saved.fields);
+            }
+        }
+        self.drafting = true;
+
+        // Uploads are awaited even when a prompt fails, so none is cut off midway.
+        let result = self.add_loop(&db, draft.unwrap_or_default()).await;
+        self.finish_uploads().await?;
+
+        if let Some(fields) = self
+            .history
+            .first()
+            .filter(|_| !self.created.is_empty() && !self.json)
+        {
+            if let Err(err) = self.print_month_summary(fields).await {
+                eprintln!("Couldn't load this month's entries: {}", err);
+            }
+        }
+
+        result
+    }
+
+    /// `args` with the merchant, total and date of the receipt at `path` filled
+    /// in where they aren't given, and the receipt to attach.
+    async fn read_receipt(
+        &mut self,
+        path: &std::path::Path,
+        args: crate::cli::AddArgs,
+    ) -> Result<crate::cli::AddArgs> {
+        let path = self
+            .checked_attachment(Some(path.to_path_buf()))?
+            .unwrap_or_default();
+
+        let reading = spinner("Reading the receipt…");
+        let text = crate::ocr::recognize(&self.settings.ocr, &path).await;
+        reading.finish_and_clear();
+
+        let today = notion::chrono::offset::Local::now().date_naive();
+        let receipt = crate::ocr::parse(&text?, today);
+        if receipt == crate::ocr::Receipt::default() {
+            self.status("Couldn't read anything off the receipt".to_string());
+        }
+        self.review_all = true;
+
+        Ok(args.or(crate::cli::AddArgs {
+            name: receipt.merchant,
+            amount: receipt.total.map(|total| format!("{:.2}", total)),
+            date: receipt.date,
+            attach: Some(path),
+            ..Default::default()
+        }))
+    }
+
+    async fn add_loop(
+        &mut self,
+        db: &notion::models::Database,
+        mut args: crate::cli::AddArgs,
+    ) -> Result<()> {
+        loop {
+            match self.prompt_entry(db, &args).await {
+                Ok(Some(entries)) => {
+                    for entry in entries {
+                        self.spawn_upload(entry)?;
+                    }
+                }
+                Ok(None) => self.queue.clear_draft()?,
+                Err(err) => self.offer_draft(err)?,
+            }
+            args = crate::cli::AddArgs::default();
+
+            loop {
+                let mut actions = vec![NextAction::AddMore];
+                if !self.created.is_empty() || !self.uploads.is_empty() {
+                    actions.push(NextAction::Undo);
+                }
+                actions.push(NextAction::Done);
+
+                match inquire::Select::new("Next:", actions).prompt() {
+                    Ok(NextAction::AddMore) => break,
+                    Ok(NextAction::Undo) => self.undo_created().await?,
+                    _ => return Ok(()),
+                }
+            }
+        }
+    }
+
+    /// Offers to keep what was entered before a prompt was canceled, or returns
+    /// `err` when it failed otherwise.
+    fn offer_draft(&self, err: eyre::Report) -> Result<()> {
+        let partial = match with_partial(err, &crate::cli::AddArgs::default()).downcast() {
+            Ok(Canceled(partial)) => partial,
+            Err(err) => return Err(err),
+        };
+        if partial.is_empty() {
+            return self.queue.clear_draft();
+        }
+
+        // A second Ctrl+C means no.
+        let save = inquire::Confirm::new("Save the entry as a draft?")
+            .with_default(true)
+            .prompt_skippable();
+        if matches!(save, Ok(Some(true))) {
+            self.queue.store_draft(&partial)?;
+            println!("Saved, it's offered again on the next add");
+        } else {
+            self.queue.clear_draft()?;
+        }
+
+        Ok(())
+    }
+
+    /// Keeps the prompt history for the next sessions.
+    fn store_prompt_history(&self) {
+        if let Err(err) = self.queue.store_prompt_history(&self.prompt_history) {
+            eprintln!("Couldn't save the prompt history: {}", err);
+        }
+    }
+
+    /// Keeps the fields in `properties` as the draft while drafting.
+    fn save_draft(&self, properties: &HashMap<String, notion::models::properties::PropertyValue>) {
+        if !self.drafting {
+            return;
+        }
+
+        let fields = entry_fields(
+            properties,
+            &self.settings.properties,
+            self.categories_cache.as_deref().unwrap_or_default(),
+        );
+        if let Err(err) = self.queue.store_draft(&fields) {
+            eprintln!("Couldn't save the draft: {}", err);
+        }
+    }
+
+    /// Queues `entry` and uploads it on a separate task, so the next one can be
+    /// prompted for meanwhile.
+    fn spawn_upload(&mut self, entry: crate::queue::Entry) -> Result<()> {
+        tracing::debug!(created_at = %entry.created_at, "Uploading in the background");
+        self.queue.push(&entry)?;
+        self.queue.clear_draft()?;
+        self.history.insert(
+            0,
+            entry_fields(
+                &entry.properties.properties,
+                &self.settings.properties,
+                self.categories_cache.as_deref().unwrap_or_default(),
+            ),
+        );
+
+        let backend = self.backend.clone();
+        self.uploads.push(tokio::spawn(async move {
+            let result = backend.create_entry(entry.properties.clone()).await;
+            (entry, result)
+        }));
+
+        Ok(())
+    }
+
+    /// Waits for the background uploads and reports the ones that failed, which
+    /// stay queued for the next sync.
+    #[tracing::instrument(skip_all)]
+    async fn finish_uploads(&mut self) -> Result<()> {
+        let mut failed = vec![];
+        for upload in std::mem::take(&mut self.uploads) {
+            match upload.await? {
+                (entry, Ok(page)) => {
+                    self.queue.mark_uploaded(&entry)?;
+                    self.append_items(&page.id, &entry.items).await;
+                    self.attach_receipt(&page, entry.attachment.as_deref())
+                        .await;
+                    self.show_created(&page);
+                    self.notify_created(&page).await;
+                    self.created.push(page.id);
+                }
+                (entry, Err(err)) => failed.push((entry, err)),
+            }
+        }
+
+        if !failed.is_empty() {
+            eprintln!(
+                "Couldn't upload {} entries, they are queued for the next sync:",
+                failed.len()
+            );
+            for (entry, err) in failed {
+                let fields = entry_fields(
+                    &entry.properties.properties,
+                    &self.settings.properties,
+                    self.categories_cache.as_deref().unwrap_or_default(),
+                );
+                eprintln!(
+                    "  {}: {}",
+                    entry_summary(&fields, &self.settings.display),
+                    err
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Loads the schema, the categories and the recent entries for a session of
+    /// prompts. The recent entries are empty when they couldn't be loaded.
+    #[tracing::instrument(skip_all)]
+    pub(crate) async fn prepare(
+        &mut self,
+    ) -> Result<(notion::models::Database, Vec<notion::models::Page>)> {
+        // The categories database is only known from the schema, so the one cached
+        // by the previous run lets all three load at once.
+        let names = self.settings.properties.clone();
+        let categories_id = self
+            .queue
+            .cached_database(&self.settings.notion.database_id)
+            .and_then(|db| relation_database_id(&db, &names.category));
+
+        let spinner = spinner("Loading the database and recent entries…");
+        let (db, recent, categories) = tokio::join!(
+            self.load_database(),
+            self.backend.recent_entries(
+                self.settings.recent_sorts(),
+                self.settings.display.recent_count.max(100)
+            ),
+            async {
+                match &categories_id {
+                    Some(database_id) => {
+                        self.fetch_pages(&self.category_cache, database_id, "categories")
+                            .await
+                    }
+                    None => None,
+                }
+            },
+        );
+
+        let db = db?;
+        spinner.suspend(|| self.check_schema(&db))?;
+        let names = self.settings.properties.clone();
+        if categories_id.is_some() && categories_id == relation_database_id(&db, &names.category) {
+            self.categories_cache = categories;
+        }
+        // Without a cached schema the categories are fetched now, so the recent
+        // entries can name them.
+        self.categories(&db).await;
+        spinner.finish_and_clear();
+
+        let (recent, history): (Vec<notion::models::Page>, Vec<crate::cli::AddArgs>) = match recent
+        {
+            Ok(pages) => {
+                let categories = self.categories_cache.as_deref().unwrap_or_default();
+                let history = pages
+                    .iter()
+                    .map(|page| entry_fields(&page.properties.properties, &names, categories))
+                    .collect();
+                (pages, history)
+            }
+            Err(err) => {
+                eprintln!("Couldn't load recent entries: {}", err);
+                let history = self
+                    .queue
+                    .entries()?
+                    .iter()
+                    .rev()
+                    .map(|entry| {
+                        entry_fields(
+                            &entry.properties.properties,
+                            &names,
+                            self.categories_cache.as_deref().unwrap_or_default(),
+                        )
+                    })
+                    .collect();
+                (vec![], history)
+            }
+        };
+        self.title_history =
+            rank_titles(history.iter().filter_map(|fields| fields.name.as_deref()));
+        self.history = history;
+
+        Ok((db, recent))
+    }
+
+    /// Fills the history with the newest entries, unless they can't be loaded.
+    async fn load_history(&mut self) {
+        let recent = self
+            .backend
+            .recent_entries(crate::backend::newest_created(), 100)
+            .await;
+        match recent {
+            Ok(pages) => {
+                let categories = self.categories_cache.as_deref().unwrap_or_default();
+                self.history = pages
+                    .iter()
+                    .map(|page| {
+                        entry_fields(
+                            &page.properties.properties,
+                            &self.settings.properties,
+                            categories,
+                        )
+                    })
+                    .collect();
+            }
+            Err(err) => tracing::warn!(error = %err, "Couldn't load the recent entries"),
+        }
+    }
+
+    async fn batch(&mut self) -> Result<()> {
+        let (db, _) = self.prepare().await?;
+        self.drafting = true;
+
+        let mut entries = vec![];
+        let mut rows = vec![];
+        loop {
+            let properties = match self
+                .create_page_properties(&db.properties, &crate::cli::AddArgs::default(), None)
+                .await
+            {
+                Ok(properties) => properties,
+                Err(err) => {
+                    self.offer_draft(err)?;
+                    if rows.is_empty() {
+                        return Ok(());
+                    }
+                    break;
+                }
+            };
+            let fields = entry_fields(
+                &properties,
+                &self.settings.properties,
+                self.categories_cache.as_deref().unwrap_or_default(),
+            );
+            self.history.insert(0, fields.clone());
+            rows.push(fields);
+            entries.push(crate::queue::Entry::new(notion::models::Properties {
+                properties,
+            }));
+
+            let more = inquire::Confirm::new("Another one?")
+                .with_default(true)
+                .prompt()?;
+            if !more {
+                break;
+            }
+        }
+
+        // The rows are kept in memory from here on.
+        self.queue.clear_draft()?;
+        println!(
+            "{}",
+            crate::report::fields_table(&rows, &self.settings.display)
+        );
+
+        let confirmed = inquire::Confirm::new(&format!("Upload {} entries?", entries.len()))
+            .with_default(true)
+            .prompt()?;
+        if !confirmed {
+            return Ok(());
+        }
+
+        let uploaded = self.upload(&entries).await?;
+        println!("Uploaded {} of {} entries", uploaded, entries.len());
+
+        Ok(())
+    }
+
+    async fn import(&mut self, command: crate::cli::ImportCommand) -> Result<()> {
+        match command {
+            crate::cli::ImportCommand::Csv { path } => self.import_csv(&path).await,
+            crate::cli::ImportCommand::Bank {
+                link: Some(institution),
+                ..
+            } => self.link_bank(&institution).await,
+            crate::cli::ImportCommand::Bank { days, link: None } => self.import_bank(days).await,
+        }
+    }
+
+    async fn import_csv(&mut self, path: &std::path::Path) -> Result<()> {
+        let rows = crate::import::read_csv(std::fs::File::open(path)?, &self.settings.csv)?;
+        self.import_rows(rows, false, crate::import::Row::matches)
+            .await
+    }
+
+    fn bank_settings(&self) -> Result<crate::settings::BankSettings> {
+        self.settings
+            .bank
+            .clone()
+            .ok_or_else(|| eyre!("Set up the `bank` section to import from a bank"))
+    }
+
+    /// Connects a bank and prints the accounts to add to `bank.accounts`.
+    async fn link_bank(&self, institution: &str) -> Result<()> {
+        let client = crate::bank::Client::login(&self.bank_settings()?).await?;
+        let requisition = client.link(institution).await?;
+
+        println!("Connect the bank at {}", requisition.link);
+        if let Err(err) = open::that(&requisition.link) {
+            eprintln!("Couldn't open the link: {}", err);
+        }
+        inquire::Confirm::new("Connected?")
+            .with_default(true)
+            .prompt()?;
+
+        let accounts = client.requisition(&requisition.id).await?.accounts;
+        if accounts.is_empty() {
+            return Err(eyre!("The bank didn't share any accounts"));
+        }
+        println!("Add these to `bank.accounts`:");
+        for account in accounts {
+            println!("  - {}", account);
+        }
+
+        Ok(())
+    }
+
+    /// Offers the payments of the last `days` not recorded yet.
+    async fn import_bank(&mut self, days: Option<u32>) -> Result<()> {
+        let settings = self.bank_settings()?;
+        if settings.accounts.is_empty() {
+            return Err(eyre!(
+                "Add accounts to `bank.accounts`, `import bank --link` lists them"
+            ));
+        }
+
+        let client = crate::bank::Client::login(&settings).await?;
+        let from = notion::chrono::Local::now().date_naive()
+            - notion::chrono::Duration::days(days.unwrap_or(settings.days).into());
+        let mut rows = vec![];
+        for account in &settings.accounts {
+            rows.extend(crate::bank::rows(
+                &client.transactions(account, from).await?,
+            )?);
+        }
+        rows.sort_by_key(|row| row.date);
+
+        self.import_rows(rows, true, crate::bank::is_recorded).await
+    }
+
+    /// Reads the receipts mailed in the last `days` and offers each one.
+    async fn ingest_imap(&mut self, days: Option<u32>) -> Result<()> {
+        let settings =
+            self.settings.imap.clone().ok_or_else(|| {
+                eyre!("Set up the `imap` section to read receipts from a mailbox")
+            })?;
+        let rules = settings
+            .rules
+            .iter()
+            .map(crate::ingest::Rule::new)
+            .collect::<Result<Vec<_>>>()?;
+        let today = notion::chrono::Local::now().date_naive();
+        let since = today - notion::chrono::Duration::days(days.unwrap_or(settings.days).into());
+
+        let mut session = crate::imap::Session::connect(&settings).await?;
+        session.select(&settings.folder).await?;
+        let uids = session.search_since(since).await?;
+        let headers = session.fetch(&uids, "HEADER").await?;
+        let uids: Vec<u32> = headers
+            .iter()
+            .filter(|(_, header)| {
+                let mail = crate::ingest::Mail::parse(header);
+                crate::ingest::is_receipt(&mail.subject, &settings.subjects)
+            })
+            .map(|(uid, _)| *uid)
+            .collect();
+        let mails = session.fetch(&uids, "").await?;
+        session.logout().await?;
+
+        let mut rows = vec![];
+        for (_, raw) in mails {
+            let mail = crate::ingest::Mail::parse(&raw);
+            match crate::ingest::extract(&mail, &rules, today) {
+                Some(row) => rows.push(row),
+                None => self.status(format!("No amount found in \"{}\"", mail.subject)),
+            }
+        }
+
+        self.import_rows(rows, true, crate::import::Row::matches)
+            .await
+    }
+
+    /// Creates entries from `rows`, leaving out the ones `matches` finds
+    /// recorded. With `confirm_each` every row is asked about first.
+    async fn import_rows(
+        &mut self,
+        rows: Vec<crate::import::Row>,
+        confirm_each: bool,
+        matches: fn(
+            &crate::import::Row,
+            &notion::models::Page,
+            &crate::settings::PropertyNames,
+        ) -> bool,
+    ) -> Result<()> {
+        let (Some(from), Some(to)) = (
+            rows.iter().map(|row| row.date).min(),
+            rows.iter().map(|row| row.date).max(),
+        ) else {
+            println!("Nothing to import");
+            return Ok(());
+        };
+
+        let names = self.settings.properties.clone();
+        let existing = self
+            .backend
+            .query_entries(crate::backend::Query {
+                filter: Some(crate::backend::Filter::date_range(
+                    &names.date,
+                    from - notion::chrono::Duration::days(crate::bank::BOOKING_DAYS),
+                    to.succ_opt().unwrap_or(to),
+                )),
+                ..Default::default()
+            })
+            .await?;
+
+        let (duplicates, rows): (Vec<_>, Vec<_>) = rows
+            .into_iter()
+            .partition(|row| existing.iter().any(|page| matches(row, page, &names)));
+        if !duplicates.is_empty() {
+            println!(
+                "Skipping {} entries that are already in Notion",
+                duplicates.len()
+            );
+        }
+
+        let db = self.get_database().await?;
+
+        let mut entries = vec![];
+        for row in rows {
+            let mut args = crate::cli::AddArgs {
+                name: Some(row.name),
+                amount: Some(self.amount_text(row.amount)),
+                date: Some(row.date),
+                category: None,
+                entry_type: None,
+                notes: None,
+                tags: vec![],
+                payer: None,
+                reimbursable: false,
+                project: None,
+                trip: None,
+                attach: None,
+            };
+            self.apply_rules(&mut args)?;
+            println!("{}", entry_summary(&args, &self.settings.display));
+            if confirm_each
+                && !inquire::Confirm::new("Add it?")
+                    .with_default(true)
+                    .prompt()?
+            {
+                continue;
+            }
+
+            let properties = self
+                .create_page_properties(&db.properties, &args, None)
+                .await?;
+            entries.push(crate::queue::Entry::new(notion::models::Properties {
+                properties,
+            }));
+        }
+
+        if entries.is_empty() {
+            println!("Nothing to import");
+            return Ok(());
+        }
+
+        let confirmed = inquire::Confirm::new(&format!("Create {} entries?", entries.len()))
+            .with_default(true)
+            .prompt()?;
+        if !confirmed {
+            return Ok(());
+        }
+
+        let uploaded = self.upload(&entries).await?;
+        println!("Imported {} of {} entries", uploaded, entries.len());
+
+        Ok(())
+    }
+
+    /// Queues `entries` and creates them a few at a time, returning how many
+    /// were uploaded.
+    #[tracing::instrument(skip_all)]
+    async fn upload(&self, entries: &[crate::queue::Entry]) -> Result<usize> {
+        use futures::StreamExt;
+
+        for entry in entries {
+            self.queue.push(entry)?;
+        }
+
+        let progress = indicatif::ProgressBar::new(entries.len() as u64);
+        let mut results = futures::stream::iter(entries)
+            .map(|entry| async move {
+                (
+                    entry,
+                    self.backend.create_entry(entry.properties.clone()).await,
+                )
+            })
+            .buffer_unordered(4);
+
+        let mut uploaded = 0;
+        while let Some((entry, result)) = results.next().await {
+            match result {
+                Ok(_) => {
+                    self.queue.mark_uploaded(entry)?;
+                    uploaded += 1;
+                }
+                Err(err) => progress
+                    .suspend(|| eprintln!("Couldn't upload an entry, it is queued: {}", err)),
+            }
+            progress.inc(1);
+        }
+        progress.finish_and_clear();
+
+        Ok(uploaded)
+    }
+
+    async fn run_recurring(&mut self) -> Result<()> {
+        let today = notion::chrono::offset::Local::now().date_naive();
+        let names = self.settings.properties.clone();
+        let existing = self
+            .backend
+            .query_entries(crate::report::month_query(&names.date, today))
+            .await?;
+
+        let recurring = self.settings.recurring.clone();
+        let due = crate::recurring::due(&recurring, today, &existing);
+        if due.is_empty() {
+            println!("Nothing is due");
+            return Ok(());
+        }
+
+        let db = self.get_database().await?;
+
+        let mut entries = vec![];
+        for (expense, date) in due {
+            let args = crate::cli::AddArgs {
+                name: Some(expense.name.clone()),
+                amount: Some(self.amount_text(expense.amount)),
+                date: Some(date),
+                category: Some(expense.category.clone()),
+                ..Default::default()
+            };
+            println!("{}", entry_summary(&args, &self.settings.display));
+
+            let properties = self
+                .create_page_properties(&db.properties, &args, None)
+                .await?;
+            entries.push(crate::queue::Entry::new(notion::models::Properties {
+                properties,
+            }));
+        }
+
+        let confirmed = inquire::Confirm::new(&format!("Create {} entries?", entries.len()))
+            .with_default(true)
+            .prompt()?;
+        if !confirmed {
+            return Ok(());
+        }
+
+        let uploaded = self.upload(&entries).await?;
+        println!("Booked {} of {} entries", uploaded, entries.len());
+
+        Ok(())
+    }
+
+    async fn export(
+        &mut self,
+        output: &std::path::Path,
+        format: crate::cli::ExportFormat,
+    ) -> Result<()> {
+        let db = self.get_database().await?;
+        let pages = self
+            .backend
+            .query_entries(crate::backend::Query::default())
+            .await?;
+
+        let mut relation_names = HashMap::new();
+        for config in db.properties.values() {
+            if let notion::models::properties::PropertyConfiguration::Relation {
+                relation, ..
+            } = config
+            {
+                for page in self.backend.categories(&relation.database_id).await? {
+                    let title = page.title().unwrap_or("Untitled".to_string());
+                    relation_names.insert(page.id, title);
+                }
+            }
+        }
+
+        let rows = crate::export::rows(&pages, &relation_names);
+        let file = std::io::BufWriter::new(std::fs::File::create(output)?);
+
+        match format {
+            crate::cli::ExportFormat::Csv => {
+                let names = &self.settings.properties;
+                let mut columns: Vec<String> = db
+                    .properties
+                    .keys()
+                    .filter(|name| !names.contains(name))
+                    .cloned()
+                    .collect();
+                columns.sort();
+
+                let known = [&names.date, &names.name, &names.amount, &names.category];
+                for name in known.into_iter().rev() {
+                    if db.properties.contains_key(name) {
+                        columns.insert(0, name.clone());
+                    }
+                }
+
+                crate::export::write_csv(file, &columns, &rows)?
+            }
+            crate::cli::ExportFormat::Json => crate::export::write_json(file, &rows)?,
+            crate::cli::ExportFormat::Ledger => crate::export::write_ledger(
+                file,
+                &pages,
+                &relation_names,
+                &self.settings.properties,
+                &self.settings.ledger,
+            )?,
+        }
+
+        println!("Exported {} entries to {}", rows.len(), output.display());
+
+        Ok(())
+    }
+
+    async fn edit(&mut self) -> Result<()> {
+        let db = self.get_database().await?;
+
+        let Some(page) = self
+            .backend
+            .recent_entries(self.settings.recent_sorts(), 1)
+            .await?
+            .into_iter()
+            .next()
+        else {
+            println!("Nothing to edit");
+            return Ok(());
+        };
+
+        println!("Editing {}", self.format_entry(&page));
+
+        let properties = self
+            .create_page_properties(&db.properties, &crate::cli::AddArgs::default(), Some(&page))
+            .await?;
+
+        self.backend
+            .update_entry(&page.id, notion::models::Properties { properties })
+            .await?;
+        println!("Updated");
+
+        Ok(())
+    }
+
+    /// Archives the last page created in this session.
+    async fn undo_created(&mut self) -> Result<()> {
+        self.finish_uploads().await?;
+        if let Some(page_id) = self.created.pop() {
+            self.backend.archive_entry(&page_id).await?;
+            println!("Archived the last entry");
+        }
+
+        Ok(())
+    }
+
+    async fn undo(&self) -> Result<()> {
+        let query = crate::backend::database_sorting(crate::backend::newest_created(), 1);
+
+        let Some(page) = self.backend.query_entries(query).await?.into_iter().next() else {
+            println!("Nothing to undo");
+            return Ok(());
+        };
+
+        let confirmed =
+            inquire::Confirm::new(&format!("Archive \"{}\"?", self.format_entry(&page)))
+                .with_default(false)
+                .prompt()?;
+
+        if confirmed {
+            self.backend.archive_entry(&page.id).await?;
+            println!("Archived");
+        }
+
+        Ok(())
+    }
+
+    /// Creates a copy of the most recently created entry with its title, amount
+    /// and category, dated today.
+    async fn again(&mut self) -> Result<()> {
+        let db = self.get_database().await?;
+        let names = self.settings.properties.clone();
+        let query = crate::backend::database_sorting(crate::backend::newest_created(), 1);
+
+        let Some(page) = self.backend.query_entries(query).await?.into_iter().next() else {
+            println!("Nothing to add again");
+            return Ok(());
+        };
+
+        let confirmed = inquire::Confirm::new(&format!(
+            "Add \"{}\" again today?",
+            self.format_entry(&page)
+        ))
+        .with_default(true)
+        .prompt()?;
+        if !confirmed {
+            return Ok(());
+        }
+
+        let mut properties: HashMap<_, _> = [
+            &names.name,
+            &names.amount,
+            &names.category,
+            &names.entry_type,
+            &names.currency,
+        ]
+        .into_iter()
+        .filter_map(|name| {
+            let value = page.properties.properties.get(name)?;
+            Some((name.clone(), value.clone()))
+        })
+        .collect();
+        if let Some(notion::models::properties::PropertyConfiguration::Date { id }) =
+            db.properties.get(&names.date)
+        {
+            let today = notion::chrono::offset::Local::now().date_naive();
+            properties.insert(
+                names.date.clone(),
+                notion::models::properties::PropertyValue::Date {
+                    id: id.clone(),
+                    date: Some(notion::models::properties::DateValue {
+                        start: notion::models::properties::DateOrDateTime::Date(today),
+                        end: None,
+                        time_zone: None,
+                    }),
+                },
+            );
+        }
+
+        let entry = crate::queue::Entry::new(notion::models::Properties { properties });
+        self.queue.push(&entry)?;
+        let page = self
+            .backend
+            .create_entry(entry.properties.clone())
+            .await
+            .wrap_err("Couldn't upload the entry, it is queued for the next sync")?;
+        self.queue.mark_uploaded(&entry)?;
+
+        self.show_created(&page);
+        self.notify_created(&page).await;
+        self.status(format!("Added {}", self.format_entry(&page)));
+
+        Ok(())
+    }
+
+    /// Creates a refund of a picked recent expense: the amount taken off in the
+    /// same category, linked back to the expense.
+    async fn refund(&mut self, amount: Option<String>) -> Result<()> {
+        let db = self.get_database().await?;
+        let names = self.settings.properties.clone();
+
+        let expenses: Vec<notion::models::Page> = self
+            .backend
+            .recent_entries(self.settings.recent_sorts(), 20)
+            .await?
+            .into_iter()
+            .filter(|page| {
+                crate::report::page_amount(page, &names.amount).is_some_and(|amount| amount > 0.0)
+            })
+            .collect();
+        if expenses.is_empty() {
+            println!("No recent expenses to refund");
+            return Ok(());
+        }
+
+        let labels: Vec<String> = expenses
+            .iter()
+            .map(|page| self.format_entry(page))
+            .collect();
+        let picked = inquire::Select::new("Refund which?", labels).raw_prompt()?;
+        let expense = &expenses[picked.index];
+        let spent = crate::report::page_amount(expense, &names.amount).unwrap_or_default();
+
+        let most = rust_decimal::Decimal::try_from(spent).unwrap_or_default();
+        let check = move |amount: rust_decimal::Decimal| match amount.is_sign_positive()
+            && !amount.is_zero()
+            && amount <= most
+        {
+            true => Ok(amount),
+            false => Err(eyre!("Expected more than 0 and at most {:.2}", spent)),
+        };
+        let read = self.amount_reader();
+        let amount = match amount {
+            Some(amount) => check(crate::calc::calc(&read(&amount).0)?)?,
+            None => {
+                let validated = read.clone();
+                let amount = inquire::Text::new("Refunded amount:")
+                    .with_initial_value(&spent.to_string())
+                    .with_validator(move |input: &str| {
+                        Ok(
+                            match crate::calc::calc(&validated(input).0).and_then(check) {
+                                Ok(_) => inquire::validator::Validation::Valid,
+                                Err(err) => inquire::validator::Validation::Invalid(err.into()),
+                            },
+                        )
+                    })
+                    .prompt()?;
+                crate::calc::calc(&read(&amount).0)?
+            }
+        };
+
+        let title = expense.title().unwrap_or("Untitled".to_string());
+        let mut properties: HashMap<_, _> = [&names.category, &names.entry_type]
+            .into_iter()
+            .filter_map(|name| {
+                let value = expense.properties.properties.get(name)?;
+                Some((name.clone(), value.clone()))
+            })
+            .collect();
+        if let Some(notion::models::properties::PropertyConfiguration::Title { id }) =
+            db.properties.get(&names.name)
+        {
+            properties.insert(
+                names.name.clone(),
+                notion::models::properties::PropertyValue::Title {
+                    id: id.clone(),
+                    title: crate::prompt::rich_text(&format!("Refund: {}", title)),
+                },
+            );
+        }
+        if let Some(notion::models::properties::PropertyConfiguration::Number { id, .. }) =
+            db.properties.get(&names.amount)
+        {
+            properties.insert(
+                names.amount.clone(),
+                notion::models::properties::PropertyValue::Number {
+                    id: id.clone(),
+                    number: crate::calc::number(-amount, self.settings.decimal_places),
+                },
+            );
+        }
+        if let Some(notion::models::properties::PropertyConfiguration::Date { id }) =
+            db.properties.get(&names.date)
+        {
+            let today = notion::chrono::offset::Local::now().date_naive();
+            properties.insert(
+                names.date.clone(),
+                notion::models::properties::PropertyValue::Date {
+                    id: id.clone(),
+                    date: Some(notion::models::properties::DateValue {
+                        start: notion::models::properties::DateOrDateTime::Date(today),
+                        end: None,
+                        time_zone: None,
+                    }),
+                },
+            );
+        }
+        // Without a relation to link it with, the expense is named in the notes.
+        match (
+            db.properties.get(&names.refund_of),
+            db.properties.get(&names.notes),
+        ) {
+            (Some(notion::models::properties::PropertyConfiguration::Relation { id, .. }), _) => {
+                properties.insert(
+                    names.refund_of.clone(),
+                    notion::models::properties::PropertyValue::Relation {
+                        id: id.clone(),
+                        relation: Some(vec![notion::models::properties::RelationValue {
+                            id: expense.id.clone(),
+                        }]),
+                    },
+                );
+            }
+            (_, Some(notion::models::properties::PropertyConfiguration::Text { id })) => {
+                properties.insert(
+                    names.notes.clone(),
+                    notion::models::properties::PropertyValue::Text {
+                        id: id.clone(),
+                        rich_text: crate::prompt::rich_text(&format!(
+                            "Refund of {}",
+                            self.format_entry(expense)
+                        )),
+                    },
+                );
+            }
+            _ => {}
+        }
+
+        let entry = crate::queue::Entry::new(notion::models::Properties { properties });
+        self.queue.push(&entry)?;
+        let page = self
+            .backend
+            .create_entry(entry.properties.clone())
+            .await
+            .wrap_err("Couldn't upload the refund, it is queued for the next sync")?;
+        self.queue.mark_uploaded(&entry)?;
+
+        self.show_created(&page);
+        self.status(format!(
+            "Refunded {} of {}",
+            crate::report::format_amount(crate::calc::to_f64(amount)),
+            title
+        ));
+
+        Ok(())
+    }
+
+    /// Goes through the suspected duplicates and archives the picked ones, all
+    /// but the first created of each group by default.
+    async fn dedupe(&mut self) -> Result<()> {
+        let searching = spinner("Looking for duplicates…");
+        let entries = self
+            .backend
+            .query_entries(crate::backend::Query::default())
+            .await;
+        searching.finish_and_clear();
+        let entries = entries?;
+        let groups = crate::dedupe::groups(&entries, &self.settings.properties);
+
+        if self.json {
+            let groups: Vec<Vec<_>> = groups
+                .iter()
+                .map(|group| group.iter().map(|page| self.page_json(page)).collect())
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&groups)?);
+            return Ok(());
+        }
+        if groups.is_empty() {
+            println!("No duplicates found");
+            return Ok(());
+        }
+
+        let mut archived = 0;
+        for (index, group) in groups.iter().enumerate() {
+            let labels: Vec<String> = group.iter().map(|page| self.format_entry(page)).collect();
+            let message = format!("Archive which? ({} of {})", index + 1, groups.len());
+            let Some(picked) = inquire::MultiSelect::new(&message, labels)
+                .with_default(&(1..group.len()).collect::<Vec<_>>())
+                .with_help_message("Esc to keep them all")
+                .raw_prompt_skippable()?
+            else {
+                continue;
+            };
+
+            for choice in picked {
+                self.backend.archive_entry(&group[choice.index].id).await?;
+                archived += 1;
+            }
+        }
+        println!("Archived {} entries", archived);
+
+        Ok(())
+    }
+
+    async fn check(&mut self, fix: bool) -> Result<()> {
+        let db = self.get_database().await?;
+        let checking = spinner("Checking the entries…");
+        let entries = self
+            .backend
+            .query_entries(crate::backend::Query::default())
+            .await;
+        let categories = self.category_names(&db.properties).await;
+        checking.finish_and_clear();
+        let entries = entries?;
+
+        let names =
+            crate::report::category_names(self.categories_cache.as_deref().unwrap_or_default());
+        let problems = crate::check::Problems::new(
+            &entries,
+            &names,
+            categories.as_deref(),
+            &self.settings.properties,
+        );
+
+        if self.json {
+            let mut json = serde_json::Map::new();
+            for (heading, pages) in problems.sections() {
+                let key = heading.to_lowercase().replace(' ', "_");
+                let pages: Vec<_> = pages.iter().map(|page| self.page_json(page)).collect();
+                json.insert(key, pages.into());
+            }
+            json.insert(
+                "unused_categories".to_string(),
+                problems.unused_categories.clone().into(),
+            );
+            println!("{}", serde_json::to_string_pretty(&json)?);
+        } else if problems.is_empty() {
+            println!("Checked {} entries, found nothing wrong", entries.len());
+        } else {
+            for (heading, pages) in problems.sections() {
+                if !pages.is_empty() {
+                    println!("{} ({}):", heading, pages.len());
+                    let pages: Vec<_> = pages.iter().map(|&page| page.clone()).collect();
+                    self.print_entries(&pages);
+                }
+            }
+            if !problems.unused_categories.is_empty() {
+                println!(
+                    "Categories without entries: {}",
+                    problems.unused_categories.join(", ")
+                );
+            }
+        }
+
+        if !fix {
+            return Ok(());
+        }
+        let mut fixed = 0;
+        for page in &problems.missing_category {
+            println!("{}", self.format_entry(page));
+            let value = match self
+                .category_property(&db.properties, None, None, None)
+                .await
+            {
+                Ok(Some((value, _))) => value,
+                Ok(None) => break,
+                Err(err) if is_canceled(&err) => break,
+                Err(err) => return Err(err),
+            };
+
+            let properties = HashMap::from([(self.settings.properties.category.clone(), value)]);
+            self.backend
+                .update_entry(&page.id, notion::models::Properties { properties })
+                .await?;
+            fixed += 1;
+        }
+        println!("Set the category of {} entries", fixed);
+
+        Ok(())
+    }
+
+    async fn list(&mut self, args: crate::cli::ListArgs) -> Result<()> {
+        let db = self.get_database().await?;
+        let pages = self.entries(&db, args).await?;
+
+        if self.json {
+            let pages: Vec<_> = pages.iter().map(|page| self.page_json(page)).collect();
+            println!("{}", serde_json::to_string_pretty(&pages)?);
+            return Ok(());
+        }
+
+        let categories = self.categories_cache.as_deref().unwrap_or_default();
+        println!(
+            "{}",
+            crate::report::entries_table(
+                &pages,
+                categories,
+                &self.settings.properties,
+                &self.settings.display
+            )
+        );
+
+        Ok(())
+    }
+
+    /// The entries `args` selects, sorted like the recent entries. The
+    /// categories are loaded too, to name them.
+    pub(crate) async fn entries(
+        &mut self,
+        db: &notion::models::Database,
+        args: crate::cli::ListArgs,
+    ) -> Result<Vec<notion::models::Page>> {
+        use crate::backend::Filter;
+        use notion::models::search::{
+            DateCondition, PropertyCondition, RelationCondition, SelectCondition, TextCondition,
+        };
+
+        let names = self.settings.properties.clone();
+
+        let mut filters = vec![];
+        if let Some(from) = args.from {
+            filters.push(Filter::property(
+                &names.date,
+                PropertyCondition::Date(DateCondition::OnOrAfter(crate::backend::midnight(from))),
+            ));
+        }
+        if let Some(to) = args.to {
+            filters.push(Filter::property(
+                &names.date,
+                PropertyCondition::Date(DateCondition::OnOrBefore(crate::backend::midnight(to))),
+            ));
+        }
+        if let Some(category) = &args.category {
+            let condition = match db.properties.get(&names.category) {
+                Some(notion::models::properties::PropertyConfiguration::Select {
+                    select, ..
+                }) => find_option(&select.options, category).map(|option| {
+                    PropertyCondition::Select(SelectCondition::Equals(option.name.clone()))
+                }),
+                _ => find_page(self.categories(db).await, category).map(|page| {
+                    PropertyCondition::Relation(RelationCondition::Contains(page.id.clone()))
+                }),
+            };
+            let condition = condition.ok_or_else(|| eyre!("Unknown category: {}", category))?;
+            filters.push(Filter::property(&names.category, condition));
+        }
+        if let Some(text) = args.contains {
+            filters.push(Filter::property(
+                &names.name,
+                PropertyCondition::RichText(TextCondition::Contains(text)),
+            ));
+        }
+
+        let mut query = crate::backend::database_sorting(
+            self.settings.recent_sorts(),
+            args.limit.unwrap_or(self.settings.display.recent_count),
+        );
+        if !filters.is_empty() {
+            query.filter = Some(Filter::And { and: filters });
+        }
+
+        let pages = self.backend.query_entries(query).await?;
+        self.categories(db).await;
+
+        Ok(pages)
+    }
+
+    /// Prints where a created `page` opens, and opens it and copies the link
+    /// when `display.open_created` is set.
+    fn show_created(&self, page: &notion::models::Page) {
+        if self.json {
+            println!("{}", self.page_json(page));
+        }
+        // Local entries have nowhere to open.
+        if !self.opens_in_notion() {
+            return;
+        }
+
+        let url = crate::export::page_url(&page.id);
+        if !self.json {
+            println!("Created {}", url);
+        }
+        if !self.settings.display.open_created {
+            return;
+        }
+        if let Err(err) = open::that(&url) {
+            eprintln!("Couldn't open the entry: {}", err);
+        }
+        if let Err(err) =
+            arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(url))
+        {
+            eprintln!("Couldn't copy the link: {}", err);
+        }
+    }
+
+    /// Whether new entries are Notion pages, rather than local ones.
+    fn opens_in_notion(&self) -> bool {
+        self.settings.storage == crate::settings::Storage::Notion && !self.offline
+    }
+
+    /// `page` as JSON, with the categories named.
+    pub(crate) fn page_json(&self, page: &notion::models::Page) -> serde_json::Value {
+        let relation_names = self
+            .categories_cache
+            .iter()
+            .flatten()
+            .map(|category| {
+                let title = category.title().unwrap_or("Untitled".to_string());
+                (category.id.clone(), title)
+            })
+            .collect();
+
+        let mut json = crate::export::page_json(page, &relation_names);
+        if !self.opens_in_notion() {
+            json["url"] = serde_json::Value::Null;
+        }
+        json
+    }
+
+    fn print_entries(&self, pages: &[notion::models::Page]) {
+        println!(
+            "{}",
+            crate::report::entries_table(
+                pages,
+                self.categories_cache.as_deref().unwrap_or_default(),
+                &self.settings.properties,
+                &self.settings.display
+            )
+        );
+    }
+
+    fn format_entry(&self, page: &notion::models::Page) -> String {
+        let names = &self.settings.properties;
+        let date = crate::report::page_date(page, &names.date)
+            .map(|date| self.settings.display.format_date(date))
+            .unwrap_or_default();
+        let amount = page_property_to_string(page, &names.amount).unwrap_or_default();
+
+        format!(
+            "{} {} {}",
+            date,
+            page.title().unwrap_or("Untitled".to_string()),
+            amount
+        )
+    }
+
+    /// Titles for autocompletion: the ranked history followed by the predefined ones.
+    fn known_titles(&self) -> Vec<&str> {
+        let mut titles: Vec<&str> = self
+            .prompt_history
+            .names
+            .iter()
+            .map(String::as_str)
+            .collect();
+
+        for title in self
+            .title_history
+            .iter()
+            .map(String::as_str)
+            .chain(self.settings.list())
+        {
+            if !titles.iter().any(|t| t.eq_ignore_ascii_case(title)) {
+                titles.push(title);
+            }
+        }
+
+        titles
+    }
+
+    async fn report(
+        &mut self,
+        command: Option<crate::cli::ReportCommand>,
+        format: crate::cli::ReportFormat,
+        output: Option<std::path::PathBuf>,
+        email: bool,
+    ) -> Result<()> {
+        use notion::chrono::Datelike;
+
+        let today = notion::chrono::offset::Local::now().date_naive();
+        let markdown = format == crate::cli::ReportFormat::Markdown;
+        let json = |json: serde_json::Value| serde_json::to_string_pretty(&json);
+        let email = email.then(|| self.email_settings()).transpose()?;
+
+        let command = command.unwrap_or(crate::cli::ReportCommand::Month {
+            month: None,
+            to_notion: false,
+        });
+        let subject = match &command {
+            crate::cli::ReportCommand::Month { month, .. } => {
+                format!("Expenses in {}", month.unwrap_or(today).format("%B %Y"))
+            }
+            crate::cli::ReportCommand::Subscriptions => "Subscriptions".to_string(),
+            crate::cli::ReportCommand::Year { year } => {
+                format!("Expenses in {}", year.unwrap_or(today.year()))
+            }
+            crate::cli::ReportCommand::Compare => {
+                "Expenses against last month and week".to_string()
+            }
+            crate::cli::ReportCommand::Trend { months, .. } => {
+                format!("Expenses over the last {} months", months)
+            }
+            crate::cli::ReportCommand::Reimbursable => "Outstanding reimbursements".to_string(),
+            crate::cli::ReportCommand::Trip { name } => format!("Expenses of {}", name),
+            crate::cli::ReportCommand::Settle { month } => month
+                .unwrap_or(today)
+                .format("Settlement for %B %Y")
+                .to_string(),
+        };
+        let mut to_notion = None;
+        let rendered = match command {
+            crate::cli::ReportCommand::Month {
+                month,
+                to_notion: notion,
+            } => {
+                let db = self.get_database().await?;
+                let report = self.month_report(&db, month.unwrap_or(today)).await?;
+                let forecast = report.forecast(today, &self.settings.budgets);
+                if notion {
+                    to_notion = Some((report.title(), report.blocks(forecast.as_ref())));
+                }
+
+                if self.json {
+                    let mut value = report.json();
+                    if let Some(forecast) = &forecast {
+                        value["forecast"] = forecast.json();
+                    }
+                    json(value)?
+                } else if markdown {
+                    report.markdown(forecast.as_ref())
+                } else {
+                    let mut text = report.to_string();
+
+                    let width = crossterm::terminal::size().map_or(80, |(width, _)| width);
+                    let chart = report.chart(width.into());
+                    if !chart.is_empty() {
+                        text.push_str(&format!("\n\n{}", chart));
+                    }
+                    if let Some(forecast) = &forecast {
+                        text.push_str(&format!("\n\n{}", forecast));
+                    }
+                    text
+                }
+            }
+            crate::cli::ReportCommand::Subscriptions => {
+                let names = &self.settings.properties;
+                let entries = self
+                    .backend
+                    .query_entries(crate::report::SubscriptionReport::query(&names.date, today))
+                    .await?;
+
+                let report = crate::report::SubscriptionReport::new(&entries, names);
+                match (self.json, markdown) {
+                    (true, _) => json(report.json())?,
+                    (false, true) => report.markdown(),
+                    (false, false) => report.to_string(),
+                }
+            }
+            crate::cli::ReportCommand::Year { year } => {
+                let db = self.get_database().await?;
+                let names = self.settings.properties.clone();
+                let year = year.unwrap_or(today.year());
+                let entries = self
+                    .backend
+                    .query_entries(crate::report::YearReport::query(&names.date, year))
+                    .await?;
+                let categories = self.categories(&db).await;
+
+                let report =
+                    crate::report::YearReport::new(year, today, &entries, categories, &names);
+                match (self.json, markdown) {
+                    (true, _) => json(report.json())?,
+                    (false, true) => report.markdown(),
+                    (false, false) => report.to_string(),
+                }
+            }
+            crate::cli::ReportCommand::Compare => {
+                let db = self.get_database().await?;
+                let names = self.settings.properties.clone();
+                let (months, weeks) = (
+                    crate::report::Comparison::months(today),
+                    crate::report::Comparison::weeks(today, self.settings.display.week_start),
+                );
+                let query = crate::backend::Query {
+                    filter: Some(crate::backend::Filter::date_range(
+                        &names.date,
+                        months.0 .0,
+                        months.1 .1.max(weeks.1 .1),
+                    )),
+                    ..Default::default()
+                };
+                let entries = self.backend.query_entries(query).await?;
+                let categories = self.categories(&db).await;
+
+                let month = crate::report::Comparison::new(
+                    months,
+                    (
+                        months.0 .0.format("%B %Y").to_string(),
+                        months.1 .0.format("%B %Y").to_string(),
+                    ),
+                    &entries,
+                    categories,
+                    &names,
+                );
+                let week = crate::report::Comparison::new(
+                    weeks,
+                    (
+                        weeks.0 .0.format("Week of %b %-d").to_string(),
+                        weeks.1 .0.format("Week of %b %-d").to_string(),
+                    ),
+                    &entries,
+                    categories,
+                    &names,
+                );
+                match (self.json, markdown) {
+                    (true, _) => {
+                        json(serde_json::json!({ "month": month.json(), "week": week.json() }))?
+                    }
+                    (false, true) => format!("{}\n\n{}", month.markdown(), week.markdown()),
+                    (false, false) => format!("{}\n{}", month.table(), week.table()),
+                }
+            }
+            crate::cli::ReportCommand::Trend { months, category } => {
+                let db = self.get_database().await?;
+                let names = self.settings.properties.clone();
+                let entries = self
+                    .backend
+                    .query_entries(crate::report::months_query(&names.date, today, months))
+                    .await?;
+                let categories = self.categories(&db).await;
+
+                let report = crate::report::TrendReport::new(
+                    today, months, &entries, categories, &category, &names,
+                );
+                match (self.json, markdown) {
+                    (true, _) => json(report.json())?,
+                    (false, true) => report.markdown(),
+                    (false, false) => report.to_string(),
+                }
+            }
+            crate::cli::ReportCommand::Trip { name } => {
+                let db = self.get_database().await?;
+                let names = self.settings.properties.clone();
+                if relation_database_id(&db, &names.trip).is_none() {
+                    return Err(eyre!("The database has no {} relation", names.trip));
+                }
+                let trip = find_page(self.trips(&db).await, &name)
+                    .cloned()
+                    .ok_or_else(|| eyre!("Unknown trip: {}", name))?;
+                let entries = self
+                    .backend
+                    .query_entries(crate::report::TripReport::query(&trip.id, &names))
+                    .await?;
+
+                let report =
+                    crate::report::TripReport::new(&trip.title().unwrap_or(name), &entries, &names);
+                match (self.json, markdown) {
+                    (true, _) => json(report.json())?,
+                    (false, true) => report.markdown(),
+                    (false, false) => report.to_string(),
+                }
+            }
+            crate::cli::ReportCommand::Reimbursable => {
+                let db = self.get_database().await?;
+                let names = &self.settings.properties;
+                let entries = self
+                    .backend
+                    .query_entries(crate::report::ReimbursableReport::query(names))
+                    .await?;
+
+                // Statuses have their options grouped, Complete being the last group.
+                let complete: Vec<String> = match db.properties.get(&names.reimbursed) {
+                    Some(notion::models::properties::PropertyConfiguration::Status {
+                        status,
+                        ..
+                    }) => status
+                        .groups
+                        .iter()
+                        .filter(|group| group.name == "Complete")
+                        .flat_map(|group| &group.option_ids)
+                        .filter_map(|id| status.options.iter().find(|option| &option.id == id))
+                        .map(|option| option.name.clone())
+                        .collect(),
+                    _ => vec![],
+                };
+
+                let report = crate::report::ReimbursableReport::new(&entries, &complete, names);
+                match (self.json, markdown) {
+                    (true, _) => json(report.json())?,
+                    (false, true) => report.markdown(),
+                    (false, false) => report.to_string(),
+                }
+            }
+            crate::cli::ReportCommand::Settle { month } => {
+                let names = &self.settings.properties;
+                let month = month.unwrap_or(today);
+                let entries = self
+                    .backend
+                    .query_entries(crate::report::month_query(&names.date, month))
+                    .await?;
+
+                let settlement =
+                    crate::report::Settlement::new(month, &entries, &self.settings.shares, names);
+                match (self.json, markdown) {
+                    (true, _) => json(settlement.json())?,
+                    (false, true) => settlement.markdown(),
+                    (false, false) => settlement.to_string(),
+                }
+            }
+        };
+
+        match &output {
+            Some(path) => {
+                std::fs::write(path, format!("{}\n", rendered))?;
+                println!("Wrote the report to {}", path.display());
+            }
+            None => println!("{}", rendered),
+        }
+
+        if let Some(email) = &email {
+            crate::email::send(email, &subject, &rendered).await?;
+            self.status(format!("Emailed the report to {}", email.to.join(", ")));
+        }
+        if let Some((title, blocks)) = to_notion {
+            self.report_to_notion(title, blocks).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Appends a report to the `notion.reports` page, or adds it to that
+    /// database as a page titled `title`.
+    async fn report_to_notion(
+        &self,
+        title: String,
+        mut blocks: Vec<serde_json::Value>,
+    ) -> Result<()> {
+        self.settings.check_writes()?;
+        if self.settings.sandbox {
+            return Err(eyre!("Reports aren't added to Notion with `--sandbox`"));
+        }
+        let target = self.settings.notion.reports.clone().ok_or_else(|| {
+            eyre!("Set `notion.reports` to the page or database to add the reports to")
+        })?;
+
+        match target {
+            crate::settings::ReportsTarget::Page(page_id) => {
+                blocks.insert(0, crate::report::heading_block(&title));
+                self.backend.append_content(&page_id, blocks).await?;
+            }
+            crate::settings::ReportsTarget::Database(database_id) => {
+                self.backend
+                    .create_document(&database_id, &title, blocks)
+                    .await?;
+            }
+        }
+
+        self.status(format!("Added the {} report to Notion", title));
+
+        Ok(())
+    }
+
+    /// Prints what was done, to stderr when stdout is kept to the JSON.
+    fn status(&self, message: String) {
+        if self.json {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
+        }
+    }
+
+    fn email_settings(&self) -> Result<crate::settings::EmailSettings> {
+        self.settings
+            .email
+            .clone()
+            .ok_or_else(|| eyre!("Set up the `email` section to send emails"))
+    }
+
+    /// Totals of the month `month` falls in.
+    pub(crate) async fn month_report(
+        &mut self,
+        db: &notion::models::Database,
+        month: notion::chrono::NaiveDate,
+    ) -> Result<crate::report::MonthReport> {
+        Ok(self.month_overview(db, month).await?.1)
+    }
+
+    /// The entries of the month `month` falls in, newest first, and their totals.
+    pub(crate) async fn month_overview(
+        &mut self,
+        db: &notion::models::Database,
+        month: notion::chrono::NaiveDate,
+    ) -> Result<(Vec<crate::cli::AddArgs>, crate::report::MonthReport)> {
+        let names = self.settings.properties.clone();
+        let mut entries = self
+            .backend
+            .query_entries(crate::report::month_query(&names.date, month))
+            .await?;
+        entries
+            .sort_by_key(|entry| std::cmp::Reverse(crate::report::page_date(entry, &names.date)));
+        let categories = self.categories(db).await;
+
+        let report = crate::report::MonthReport::new(month, &entries, categories, &names);
+        let category_names = crate::report::category_names(categories);
+        let rows = entries
+            .iter()
+            .map(|entry| crate::report::page_fields(entry, &category_names, &names))
+            .collect();
+
+        Ok((rows, report))
+    }
+
+    /// Pages of the Category relation, fetched once per session.
+    async fn categories(&mut self, db: &notion::models::Database) -> &[notion::models::Page] {
+        if let Some(database_id) = relation_database_id(db, &self.settings.properties.category) {
+            self.load_categories(&database_id).await;
+        }
+
+        self.categories_cache.as_deref().unwrap_or_default()
+    }
+
+    async fn load_categories(&mut self, database_id: &notion::ids::DatabaseId) {
+        if self.categories_cache.is_none() {
+            self.categories_cache = self
+                .fetch_pages(&self.category_cache, database_id, "categories")
+                .await;
+        }
+    }
+
+    /// The trip pages, none when the database has no Trip relation.
+    async fn trips(&mut self, db: &notion::models::Database) -> &[notion::models::Page] {
+        if let Some(database_id) = relation_database_id(db, &self.settings.properties.trip) {
+            if self.trips_cache.is_none() {
+                self.trips_cache = self
+                    .fetch_pages(&self.trip_cache, &database_id, "trips")
+                    .await;
+            }
+        }
+
+        self.trips_cache.as_deref().unwrap_or_default()
+    }
+
+    /// Asks whether `title` should get `category` preselected from now on, and
+    /// saves it in the config when confirmed.
+    fn offer_mapping(&mut self, title: &str, category: &str) -> Result<()> {
+        if !self.settings.is_writable() {
+            return Ok(());
+        }
+
+        let confirmed = inquire::Confirm::new(&format!("Remember {} for '{}'?", category, title))
+            .with_default(true)
+            .prompt_skippable()?;
+
+        if confirmed == Some(true) {
+            if let Err(err) = self.settings.remember(title, category) {
+                eprintln!("Couldn't save the mapping: {}", err);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Prompts for the name of a new category page and adds it to the cache.
+    async fn create_category(
+        &mut self,
+        database_id: &notion::ids::DatabaseId,
+    ) -> Result<notion::ids::PageId> {
+        let name = inquire::Text::new("New category:")
+            .with_validator(inquire::required!())
+            .prompt()?;
+        let page = self
+            .backend
+            .create_category(database_id, name.trim())
+            .await?;
+        let page_id = page.id.clone();
+
+        let pages = self.categories_cache.get_or_insert_with(Vec::new);
+        pages.push(page);
+        if let Err(err) = self.category_cache.store(database_id, pages) {
+            eprintln!("Couldn't cache the categories: {}", err);
+        }
+
+        Ok(page_id)
+    }
+
+    /// The pages of the related database `database_id`, from `cache` unless
+    /// it's stale. `what` names them in the logs.
+    #[tracing::instrument(skip_all)]
+    async fn fetch_pages(
+        &self,
+        cache: &crate::cache::CategoryCache,
+        database_id: &notion::ids::DatabaseId,
+        what: &str,
+    ) -> Option<Vec<notion::models::Page>> {
+        let ttl = notion::chrono::Duration::minutes(self.settings.cache_ttl);
+        if !self.refresh {
+            if let Some(pages) = cache.load(database_id, ttl) {
+                tracing::debug!(count = pages.len(), what, "Pages from the cache");
+                return Some(pages);
+            }
+        }
+
+        let pages = match self.backend.categories(database_id).await {
+            Ok(pages) => pages,
+            Err(err) => {
+                tracing::warn!(error = %err, "Couldn't load the {}", what);
+                return None;
+            }
+        };
+        if let Err(err) = cache.store(database_id, &pages) {
+            eprintln!("Couldn't cache the {}: {}", what, err);
+        }
+
+        Some(pages)
+    }
+
+    /// Copies the properties of the expense database to a new one for the next
+    /// year and makes that the expense database.
+    async fn rollover(&mut self, title: Option<String>, archive: bool) -> Result<()> {
+        if self.settings.storage != crate::settings::Storage::Notion {
+            return Err(eyre!("Only Notion databases can be rolled over"));
+        }
+        self.settings.check_config_writes()?;
+        if !self.settings.is_writable() {
+            return Err(eyre!(
+                "There is no config file to switch to the new database"
+            ));
+        }
+
+        let old = self.get_database().await?;
+        let today = notion::chrono::offset::Local::now().date_naive();
+        let title = title.unwrap_or_else(|| next_year_title(&old.title_plain_text(), today));
+
+        let (new, skipped) = self.backend.copy_database(&title).await?;
+        for name in skipped {
+            eprintln!(
+                "Couldn't copy the status property `{}`, add it by hand",
+                name
+            );
+        }
+        let name = title_year(&title).map_or(title.clone(), |year| year.to_string());
+        self.settings.switch_database(&name, &new.id)?;
+        self.status(format!("Created {}, new entries go there", title));
+
+        if archive {
+            self.backend.archive_database(&old.id).await?;
+            self.status(format!("Moved {} to the trash", old.title_plain_text()));
+        }
+
+        Ok(())
+    }
+
+    /// Setup wizard that picks or creates the expense database and writes the
+    /// config.
+    async fn init(config: Option<&std::path::Path>, create_database: bool) -> Result<()> {
+        let path = crate::settings::Settings::config_path(config)?;
+        if crate::settings::Settings::exists(&path)? {
+            let overwrite = inquire::Confirm::new("Replace the existing config?")
+                .with_default(false)
+                .prompt()?;
+            if !overwrite {
+                return Ok(());
+            }
+        }
+
+        let api_key = inquire::Password::new("Notion integration token:")
+            .without_confirmation()
+            .with_display_toggle_enabled()
+            .with_help_message("Create one at https://www.notion.so/my-integrations")
+            .prompt()?;
+        let retry = crate::settings::RetrySettings::default();
+
+        if create_database {
+            let database = Self::create_expense_database(&api_key, &retry).await?;
+            println!("Created {}", database.title_plain_text());
+            return Self::write_init_config(&path, api_key, database.id, retry);
+        }
+
+        let databases = crate::backend::search_databases(&api_key, &retry).await?;
+        if databases.is_empty() {
+            return Err(eyre!(
+                "No databases are shared with the integration, add it to the expense database first"
+            ));
+        }
+
+        struct Database(notion::models::Database);
+
+        impl std::fmt::Display for Database {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}", self.0.title_plain_text())
+            }
+        }
+
+        let database = inquire::Select::new(
+            "Expense database:",
+            databases.into_iter().map(Database).collect(),
+        )
+        .prompt()?
+        .0;
+
+        let notion = crate::settings::NotionSettings {
+            api_key: api_key.clone(),
+            database_id: database.id.clone(),
+            retry: retry.clone(),
+            ..Default::default()
+        };
+        let schema = crate::backend::NotionBackend::new(&notion)?
+            .schema()
+            .await?;
+        let names = crate::settings::PropertyNames::default();
+        for name in [&names.name, &names.amount, &names.date, &names.category] {
+            if !schema.properties.contains_key(name) {
+                eprintln!(
+                    "The database has no `{}` property, rename it under `properties` in the config",
+                    name
+                );
+            }
+        }
+
+        Self::write_init_config(&path, api_key, database.id, retry)
+    }
+
+    /// Creates the expense database and its categories in a page picked from
+    /// the ones shared with the integration.
+    async fn create_expense_database(
+        api_key: &str,
+        retry: &crate::settings::RetrySettings,
+    ) -> Result<notion::models::Database> {
+        struct Page(notion::models::Page);
+
+        impl std::fmt::Display for Page {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}", self.0.title().unwrap_or("Untitled".to_string()))
+            }
+        }
+
+        let pages = crate::backend::search_pages(api_key, retry).await?;
+        if pages.is_empty() {
+            return Err(eyre!(
+                "No pages are shared with the integration, add it to the page for the databases first"
+            ));
+        }
+        let parent = inquire::Select::new(
+            "Page to create the databases in:",
+            pages.into_iter().map(Page).collect(),
+        )
+        .prompt()?
+        .0;
+
+        let notion = crate::settings::NotionSettings {
+            api_key: api_key.to_string(),
+            retry: retry.clone(),
+            ..Default::default()
+        };
+        let _spinner = spinner("Creating the databases…");
+        crate::backend::NotionBackend::new(&notion)?
+            .create_expense_database(
+                &parent.id,
+                &crate::settings::PropertyNames::default(),
+                STARTER_CATEGORIES,
+            )
+            .await
+    }
+
+    /// Writes a config for the expense database `database_id`, offering to
+    /// keep the API key in the keychain instead.
+    fn write_init_config(
+        path: &std::path::Path,
+        api_key: String,
+        database_id: notion::ids::DatabaseId,
+        retry: crate::settings::RetrySettings,
+    ) -> Result<()> {
+        let mut notion = crate::settings::NotionSettings {
+            api_key,
+            database_id,
+            retry,
+            ..Default::default()
+        };
+
+        let use_keychain = inquire::Confirm::new("Store the API key in the system keychain?")
+            .with_default(true)
+            .prompt()?;
+        if use_keychain {
+            crate::settings::store_keychain_api_key(None, &notion.api_key)?;
+            notion.api_key.clear();
+        }
+
+        crate::settings::Settings::init(path, &notion)?;
+        println!("Wrote {}", path.display());
+
+        Ok(())
+    }
+
+    async fn doctor(global: &crate::cli::GlobalArgs) -> Result<()> {
+        let mut failures = 0;
+        let mut check = |result: Result<String>| match result {
+            Ok(message) => println!("[ok]    {}", message),
+            Err(err) => {
+                failures += 1;
+                match crate::doctor::advice(&err) {
+                    Some(advice) => println!("[error] {:#}: {}", err, advice),
+                    None => println!("[error] {:#}", err),
+                }
+            }
+        };
+
+        let settings = match load_settings(global) {
+            Ok(settings) => settings,
+            Err(err) => {
+                check(Err(err.wrap_err("Couldn't read the config")));
+                return Err(eyre!("The config needs fixing first"));
+            }
+        };
+        check(Ok(format!(
+            "Config {}",
+            crate::settings::Settings::config_path(global.config.as_deref())?.display()
+        )));
+
+        let backend = open_backend(&settings)?;
+        let db = match backend.schema().await {
+            Ok(db) => db,
+            Err(err) => {
+                check(Err(err.wrap_err("Couldn't load the expense database")));
+                return Err(eyre!("Notion needs fixing first"));
+            }
+        };
+        check(Ok(format!("Database \"{}\"", db.title_plain_text())));
+
+        let names = &settings.properties;
+        let problems = crate::doctor::schema_problems(&db, names);
+        if problems.is_empty() {
+            check(Ok("Properties".to_string()));
+        }
+        for problem in problems {
+            check(Err(eyre!(problem)));
+        }
+
+        if let Some(database_id) = relation_database_id(&db, &names.category) {
+            check(
+                backend
+                    .categories(&database_id)
+                    .await
+                    .map(|pages| format!("{} categories", pages.len()))
+                    .wrap_err("Couldn't load the category database"),
+            );
+        }
+
+        match failures {
+            0 => Ok(()),
+            failures => Err(eyre!("{} checks failed", failures)),
+        }
+    }
+
+    fn set_key(global: &crate::cli::GlobalArgs) -> Result<()> {
+        let api_key = inquire::Password::new("Notion integration token:")
+            .without_confirmation()
+            .with_display_toggle_enabled()
+            .prompt()?;
+
+        crate::settings::store_keychain_api_key(global.profile.as_deref(), api_key.trim())?;
+        println!("Stored the API key in the system keychain, `notion.api_key` can be removed from the config");
+
+        Ok(())
+    }
+
+    fn show_config(global: &crate::cli::GlobalArgs) -> Result<()> {
+        println!(
+            "{}",
+            crate::settings::Settings::config_path(global.config.as_deref())?.display()
+        );
+
+        print_map(&load_settings(global)?);
+
+        Ok(())
+    }
+
+    fn edit_map(global: &crate::cli::GlobalArgs, command: crate::cli::MapCommand) -> Result<()> {
+        let mut settings = load_settings(global)?;
+
+        match command {
+            crate::cli::MapCommand::Add { title, category } => {
+                settings.remember(&title, &category)?;
+                println!("{}: {}", title, category);
+            }
+            crate::cli::MapCommand::List => print_map(&settings),
+            crate::cli::MapCommand::Remove { title } => {
+                if !settings.forget(&title)? {
+                    return Err(eyre!("`{}` isn't a predefined expense", title));
+                }
+                println!("Removed {}", title);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn test_rules(
+        global: &crate::cli::GlobalArgs,
+        title: &str,
+        amount: Option<&str>,
+    ) -> Result<()> {
+        let settings = load_settings(global)?;
+        let rules = crate::rules::compile(&settings.rules)?;
+        if rules.is_empty() {
+            println!("There are no rules in the `rules` section");
+            return Ok(());
+        }
+        let amount = amount.map(crate::calc::calc).transpose()?;
+
+        let mut fired = None;
+        for (i, rule) in rules.iter().enumerate() {
+            let outcome = match rule.check(title, amount) {
+                _ if fired.is_some() => "not tried",
+                crate::rules::Outcome::Fires => {
+                    fired = Some((i, rule.rule.category.as_str()));
+                    "fires"
+                }
+                crate::rules::Outcome::NoMatch => "doesn't match",
+                crate::rules::Outcome::OutOfRange => "matches, the amount is out of range",
+            };
+            println!("{}. {}: {}", i + 1, rule, outcome);
+        }
+
+        match fired {
+            Some((i, category)) => println!("{} by rule {}", category, i + 1),
+            None => println!("No rule fires, the category is asked for"),
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn create_page(
+        &mut self,
+        db: &notion::models::Database,
+        args: &crate::cli::AddArgs,
+    ) -> Result<()> {
+        let Some(entries) = self.prompt_entry(db, args).await? else {
+            return Ok(());
+        };
+
+        let mut saved = None;
+        for entry in entries {
+            self.queue.push(&entry)?;
+
+            let saving = spinner("Saving the entry…");
+            let result = self.backend.create_entry(entry.properties.clone()).await;
+            saving.finish_and_clear();
+
+            match result {
+                Ok(page) => {
+                    self.queue.mark_uploaded(&entry)?;
+                    self.append_items(&page.id, &entry.items).await;
+                    self.attach_receipt(&page, entry.attachment.as_deref())
+                        .await;
+
+                    let fields = entry_fields(
+                        &entry.properties.properties,
+                        &self.settings.properties,
+                        self.categories_cache.as_deref().unwrap_or_default(),
+                    );
+                    self.history.insert(0, fields.clone());
+                    self.show_created(&page);
+                    self.notify_created(&page).await;
+                    saved = Some(fields);
+                }
+                Err(err) => eprintln!(
+                    "Couldn't upload the entry, it is queued for the next sync: {}",
+                    err
+                ),
+            }
+        }
+
+        if let Some(fields) = saved.filter(|_| !self.json) {
+            if let Err(err) = self.print_month_summary(&fields).await {
+                eprintln!("Couldn't load this month's entries: {}", err);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Creates an entry from `args` without prompting, for `serve` and `daemon`.
+    /// The name and amount are required. The category defaults to the one
+    /// mapped to the name or used for it last, the date to today.
+    #[tracing::instrument(skip_all)]
+    pub(crate) async fn add_entry(
+        &mut self,
+        db: &notion::models::Database,
+        mut args: crate::cli::AddArgs,
+    ) -> Result<notion::models::Page> {
+        args.date
+            .get_or_insert_with(|| notion::chrono::offset::Local::now().date_naive());
+        self.apply_rules(&mut args)?;
+        if args.category.is_none() {
+            args.category = args.name.as_ref().and_then(|name| {
+                self.settings
+                    .get(name)
+                    .or_else(|| frequent_category(&self.history, name))
+                    .cloned()
+            });
+        }
+        if !args.is_complete() {
+            return Err(eyre!("The name, amount and category are required"));
+        }
+
+        let properties = self
+            .create_page_properties(&db.properties, &args, None)
+            .await?;
+        if self.split.take().is_some() {
+            return Err(eyre!("Split amounts can only be entered with `add`"));
+        }
+        let mut entry = crate::queue::Entry::new(notion::models::Properties { properties });
+        entry.attachment = self.checked_attachment(args.attach.take())?;
+        self.queue.push(&entry)?;
+
+        let page = self
+            .backend
+            .create_entry(entry.properties.clone())
+            .await
+            .wrap_err("Couldn't upload the entry, it is queued for the next sync")?;
+        self.queue.mark_uploaded(&entry)?;
+        self.attach_receipt(&page, entry.attachment.as_deref())
+            .await;
+        self.history.insert(0, args);
+        self.notify_created(&page).await;
+
+        Ok(page)
+    }
+
+    /// Prompts for an entry and lets it be reviewed, `None` when it's discarded.
+    /// A split amount gives an entry for each part.
+    async fn prompt_entry(
+        &mut self,
+        db: &notion::models::Database,
+        args: &crate::cli::AddArgs,
+    ) -> Result<Option<Vec<crate::queue::Entry>>> {
+        use std::io::IsTerminal;
+
+        self.split = None;
+        let mut args = args.clone();
+        self.apply_rules(&mut args)?;
+        let args = &args;
+        let mut items = vec![];
+        let mut attachment = self.checked_attachment(args.attach.clone())?;
+        let mut properties = self
+            .create_page_properties(&db.properties, args, None)
+            .await?;
+        // A templated title only asks for the date, which confirms the entry.
+        let templated = entry_fields(&properties, &self.settings.properties, &[])
+            .name
+            .is_some_and(|name| self.settings.template(&name).is_some());
+
+        let complete = self.is_complete(&args.clone().or(self.settings.prompts.defaults.clone()));
+        if !(complete || templated) || std::mem::take(&mut self.review_all) {
+            loop {
+                let mut fields = entry_fields(
+                    &properties,
+                    &self.settings.properties,
+                    self.categories_cache.as_deref().unwrap_or_default(),
+                );
+
+                let mut actions = vec![ReviewAction::Save];
+                if fields.name.is_some() {
+                    actions.push(ReviewAction::EditName);
+                }
+                if fields.amount.is_some() {
+                    actions.push(ReviewAction::EditAmount);
+                }
+                if fields.date.is_some() {
+                    actions.push(ReviewAction::EditDate);
+                }
+                if fields.category.is_some() {
+                    actions.push(ReviewAction::EditCategory);
+                }
+                if fields.entry_type.is_some() {
+                    actions.push(ReviewAction::EditType);
+                }
+                // The items go in the page content, which only Notion pages have.
+                let amount = fields.amount.as_deref().map(crate::calc::calc);
+                if matches!(amount, Some(Ok(_))) && self.split.is_none() && self.opens_in_notion() {
+                    actions.push(ReviewAction::Itemize);
+                }
+                if self.settings.storage == crate::settings::Storage::Notion {
+                    actions.push(ReviewAction::Attach);
+                }
+                actions.push(ReviewAction::Discard);
+
+                let mut summary = entry_summary(&fields, &self.settings.display);
+                if let Some(split) = &self.split {
+                    summary = format!("{} = {}", summary, split);
+                }
+                if !items.is_empty() {
+                    summary = format!("{} · {} items", summary, items.len());
+                }
+                if let Some(path) = &attachment {
+                    summary = format!("{} · {}", summary, crate::attachment::file_name(path));
+                }
+                let action = inquire::Select::new(&summary, actions)
+                    .prompt()
+                    .map_err(|err| with_partial(err.into(), &fields))?;
+                // Canceling an edit keeps the value that was being edited.
+                let reviewed = fields.clone();
+                match action {
+                    ReviewAction::Save => match amount {
+                        Some(Ok(amount)) if !items.is_empty() => {
+                            match crate::receipt::check_total(&items, amount) {
+                                Ok(()) => break,
+                                Err(err) => {
+                                    eprintln!("{}", err);
+                                    continue;
+                                }
+                            }
+                        }
+                        _ => break,
+                    },
+                    ReviewAction::Itemize => {
+                        if let Some(Ok(amount)) = amount {
+                            items =
+                                prompt_items(amount).map_err(|err| with_partial(err, &reviewed))?;
+                        }
+                        continue;
+                    }
+                    ReviewAction::Attach => {
+                        attachment = prompt_attachment()
+                            .map_err(|err| with_partial(err, &reviewed))?
+                            .or(attachment);
+                        continue;
+                    }
+                    ReviewAction::Discard => return Ok(None),
+                    ReviewAction::EditName => fields.name = None,
+                    ReviewAction::EditAmount => fields.amount = None,
+                    ReviewAction::EditDate => fields.date = None,
+                    ReviewAction::EditCategory => fields.category = None,
+                    ReviewAction::EditType => {
+                        properties.extend(
+                            self.entry_type_property(&db.properties, None, fields.entry_type)
+                                .map_err(|err| with_partial(err, &reviewed))?,
+                        );
+                        continue;
+                    }
+                }
+
+                let edited = self
+                    .create_page_properties(&db.properties, &fields, None)
+                    .await
+                    .map_err(|err| with_partial(err, &reviewed))?;
+                properties.extend(edited);
+            }
+        }
+
+        let mut fields = entry_fields(
+            &properties,
+            &self.settings.properties,
+            self.categories_cache.as_deref().unwrap_or_default(),
+        );
+        // A split's parts are checked by their categories when they're made.
+        if std::io::stdin().is_terminal() && self.split.is_none() {
+            // An entry added with flags doesn't load the averages' entries up front.
+            if self.history.is_empty() && self.settings.large_amount.factor.is_some() {
+                self.load_history().await;
+            }
+            while let Some(amount) = fields
+                .amount
+                .as_deref()
+                .and_then(|a| a.parse::<rust_decimal::Decimal>().ok())
+            {
+                let large = is_unusually_large(
+                    amount,
+                    fields.category.as_deref(),
+                    &self.history,
+                    &self.settings.large_amount,
+                );
+                if !large {
+                    break;
+                }
+
+                let confirmed = inquire::Confirm::new(&format!(
+                    "Amount {} looks unusually large — confirm?",
+                    crate::report::format_amount(crate::calc::to_f64(amount))
+                ))
+                .with_default(false)
+                .with_help_message("No to enter it again")
+                .prompt()
+                .map_err(|err| with_partial(err.into(), &fields))?;
+                if confirmed {
+                    break;
+                }
+
+                fields.amount = None;
+                let edited = self
+                    .create_page_properties(&db.properties, &fields, None)
+                    .await?;
+                properties.extend(edited);
+                fields = entry_fields(
+                    &properties,
+                    &self.settings.properties,
+                    self.categories_cache.as_deref().unwrap_or_default(),
+                );
+            }
+        }
+
+        if std::io::stdin().is_terminal() && self.has_duplicate(&fields).await {
+            let create = inquire::Confirm::new("A matching entry already exists — create anyway?")
+                .with_default(false)
+                .prompt_skippable()
+                .map_err(|err| with_partial(err.into(), &fields))?;
+            if create != Some(true) {
+                return Ok(None);
+            }
+        }
+
+        match self.split.take() {
+            Some(split) => {
+                let mut entries = self
+                    .split_entries(&db.properties, properties, &split)
+                    .await?;
+                // The receipt is of the whole amount, it goes with the first part.
+                entries[0].attachment = attachment;
+                Ok(Some(entries))
+            }
+            None => {
+                let mut entry = crate::queue::Entry::new(notion::models::Properties { properties });
+                entry.items = items;
+                entry.attachment = attachment;
+                Ok(Some(vec![entry]))
+            }
+        }
+    }
+
+    /// Reads a typed amount by the locale, returning its currency apart.
+    fn amount_reader(&self) -> impl Fn(&str) -> (String, Option<String>) + Clone + 'static {
+        let format = crate::calc::NumberFormat::for_locale(&self.settings.locale);
+        let currencies = self.settings.currencies.clone();
+        move |input| crate::calc::split_currency(&format.normalize(input), &currencies)
+    }
+
+    /// `amount` rounded to `decimal_places`, written the way `amount_reader`
+    /// reads it.
+    fn amount_text(&self, amount: rust_decimal::Decimal) -> String {
+        crate::calc::NumberFormat::for_locale(&self.settings.locale)
+            .format(crate::calc::round(amount, self.settings.decimal_places))
+    }
+
+    /// The names typed amounts can use, `_` being the previous entry's amount.
+    fn amount_variables(&self) -> crate::calc::Variables {
+        let previous = self
+            .history
+            .first()
+            .and_then(|fields| fields.amount.as_deref()?.parse().ok());
+
+        self.settings
+            .variables
+            .iter()
+            .map(|(name, value)| (name.to_lowercase(), *value))
+            .chain(previous.map(|amount| ("_".to_string(), amount)))
+            .collect()
+    }
+
+    /// The category of the first rule firing for an entry titled `title`.
+    fn rule_category(
+        &self,
+        title: &str,
+        amount: Option<rust_decimal::Decimal>,
+    ) -> Result<Option<String>> {
+        let rules = crate::rules::compile(&self.settings.rules)?;
+        Ok(crate::rules::category(&rules, title, amount).map(String::from))
+    }
+
+    /// Fills in the category of `args` from the rules, unless it has one.
+    fn apply_rules(&self, args: &mut crate::cli::AddArgs) -> Result<()> {
+        if let (None, Some(name)) = (&args.category, &args.name) {
+            let amount = args
+                .amount
+                .as_deref()
+                .and_then(|amount| crate::calc::calc_with(amount, &self.amount_variables()).ok());
+            args.category = self.rule_category(name, amount)?;
+        }
+        Ok(())
+    }
+
+    /// Whether nothing is left to prompt for in `args`, with the categories
+    /// coming from the split amount when there is one.
+    fn is_complete(&self, args: &crate::cli::AddArgs) -> bool {
+        match self.split {
+            Some(_) => args.name.is_some() && args.amount.is_some() && args.date.is_some(),
+            None => args.is_complete(),
+        }
+    }
+
+    /// An entry for each part of `split`, with its amount and category, and the
+    /// split noted.
+    async fn split_entries(
+        &mut self,
+        db_properties: &HashMap<String, notion::models::properties::PropertyConfiguration>,
+        properties: HashMap<String, notion::models::properties::PropertyValue>,
+        split: &crate::split::Split,
+    ) -> Result<Vec<crate::queue::Entry>> {
+        let names = self.settings.properties.clone();
+        let fields = entry_fields(
+            &properties,
+            &names,
+            self.categories_cache.as_deref().unwrap_or_default(),
+        );
+        let note = format!(
+            "Split of {:.2}: {}",
+            crate::calc::round(split.total, 2),
+            split
+        );
+        let notes = match fields.notes {
+            Some(notes) => format!("{}\n{}", notes, note),
+            None => note,
+        };
+
+        let mut entries = vec![];
+        for part in &split.parts {
+            let mut properties = properties.clone();
+            if let Some(notion::models::properties::PropertyConfiguration::Number { id, .. }) =
+                db_properties.get(&names.amount)
+            {
+                properties.insert(
+                    names.amount.clone(),
+                    notion::models::properties::PropertyValue::Number {
+                        id: id.clone(),
+                        number: crate::calc::number(part.amount, self.settings.decimal_places),
+                    },
+                );
+            }
+            if let Some((value, _)) = self
+                .category_property(db_properties, Some(&part.category), None, None)
+                .await?
+            {
+                properties.insert(names.category.clone(), value);
+            }
+            if let Some(notion::models::properties::PropertyConfiguration::Text { id }) =
+                db_properties.get(&names.notes)
+            {
+                properties.insert(
+                    names.notes.clone(),
+                    notion::models::properties::PropertyValue::Text {
+                        id: id.clone(),
+                        rich_text: crate::prompt::rich_text(&notes),
+                    },
+                );
+            }
+
+            entries.push(crate::queue::Entry::new(notion::models::Properties {
+                properties,
+            }));
+        }
+
+        Ok(entries)
+    }
+
+    /// Whether an entry with the date, title and amount of `fields` exists.
+    /// Entries are created anyway when that can't be checked.
+    async fn has_duplicate(&self, fields: &crate::cli::AddArgs) -> bool {
+        let Some(date) = fields.date else {
+            return false;
+        };
+        let names = &self.settings.properties;
+        let query = crate::backend::Query {
+            filter: Some(crate::backend::Filter::date_range(
+                &names.date,
+                date,
+                date.succ_opt().unwrap_or(date),
+            )),
+            ..Default::default()
+        };
+
+        match self.backend.query_entries(query).await {
+            Ok(entries) => {
+                let categories = crate::report::category_names(
+                    self.categories_cache.as_deref().unwrap_or_default(),
+                );
+                entries.iter().any(|entry| {
+                    is_duplicate(
+                        fields,
+                        &crate::report::page_fields(entry, &categories, names),
+                    )
+                })
+            }
+            Err(err) => {
+                tracing::warn!(%err, "Couldn't look for a matching entry");
+                false
+            }
+        }
+    }
+
+    /// Names of the categories to pick from, `None` when the database has no
+    /// category property.
+    async fn category_names(
+        &mut self,
+        db_properties: &HashMap<String, notion::models::properties::PropertyConfiguration>,
+    ) -> Option<Vec<String>> {
+        match db_properties.get(&self.settings.properties.category)? {
+            notion::models::properties::PropertyConfiguration::Relation { relation, .. } => {
+                self.load_categories(&relation.database_id).await;
+                Some(
+                    self.categories_cache
+                        .iter()
+                        .flatten()
+                        .map(|page| page.title().unwrap_or("Untitled".to_string()))
+                        .collect(),
+                )
+            }
+            notion::models::properties::PropertyConfiguration::Select { select, .. } => Some(
+                select
+                    .options
+                    .iter()
+                    .map(|option| option.name.clone())
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// The category property set to `category`, or to the one picked with the
+    /// category of `current` or `preselect` highlighted, with its name.
+    async fn category_property(
+        &mut self,
+        db_properties: &HashMap<String, notion::models::properties::PropertyConfiguration>,
+        category: Option<&String>,
+        current: Option<&notion::models::Page>,
+        mut preselect: Option<String>,
+    ) -> Result<Option<(notion::models::properties::PropertyValue, Option<String>)>> {
+        let property = self.settings.properties.category.clone();
+
+        if let Some(notion::models::properties::PropertyConfiguration::Relation { id, relation }) =
+            db_properties.get(&property)
+        {
+            self.load_categories(&relation.database_id).await;
+
+            let Some(pages) = &self.categories_cache else {
+                return Ok(None);
+            };
+            if let Some(page) = current {
+                let ids = crate::report::page_relation_ids(page, &property);
+                preselect = pages
+                    .iter()
+                    .find(|category| ids.contains(&category.id))
+                    .and_then(|category| category.title());
+            }
+
+            let page_id = match category {
+                Some(category) => Some(
+                    find_page(pages, category)
+                        .map(|page| page.id.clone())
+                        .ok_or_else(|| eyre!("Unknown category: {}", category))?,
+                ),
+                None => select_page(pages, preselect.as_ref())?,
+            };
+            let page_id = match page_id {
+                Some(page_id) => page_id,
+                None => self.create_category(&relation.database_id).await?,
+            };
+            let name = self
+                .categories_cache
+                .iter()
+                .flatten()
+                .find(|page| page.id == page_id)
+                .and_then(|page| page.title());
+
+            return Ok(Some((
+                notion::models::properties::PropertyValue::Relation {
+                    id: id.clone(),
+                    relation: Some(vec![notion::models::properties::RelationValue {
+                        id: page_id,
+                    }]),
+                },
+                name,
+            )));
+        }
+
+        if let Some(notion::models::properties::PropertyConfiguration::Select { id, select }) =
+            db_properties.get(&property)
+        {
+            let option = match category {
+                Some(category) => find_option(&select.options, category)
+                    .cloned()
+                    .ok_or_else(|| eyre!("Unknown category: {}", category))?,
+                None => select_option("Category:", &select.options, preselect.as_ref())?,
+            };
+            let name = Some(option.name.clone());
+
+            return Ok(Some((
+                notion::models::properties::PropertyValue::Select {
+                    id: id.clone(),
+                    select: Some(notion::models::properties::SelectedValue {
+                        id: Some(option.id),
+                        name: Some(option.name),
+                        color: option.color,
+                    }),
+                },
+                name,
+            )));
+        }
+
+        Ok(None)
+    }
+
+    /// The Type property set to `entry_type`, or to the option picked in a prompt
+    /// starting at `preselect`, or to Expense when there's neither.
+    fn entry_type_property(
+        &self,
+        db_properties: &HashMap<String, notion::models::properties::PropertyConfiguration>,
+        entry_type: Option<&String>,
+        preselect: Option<String>,
+    ) -> Result<Option<(String, notion::models::properties::PropertyValue)>> {
+        let property = &self.settings.properties.entry_type;
+        let Some(notion::models::properties::PropertyConfiguration::Select { id, select }) =
+            db_properties.get(property)
+        else {
+            return Ok(None);
+        };
+
+        let option = match (entry_type, preselect) {
+            (Some(entry_type), _) => find_option(&select.options, entry_type)
+                .cloned()
+                .ok_or_else(|| eyre!("Unknown type: {}", entry_type))?,
+            (None, Some(preselect)) => select_option("Type:", &select.options, Some(&preselect))?,
+            (None, None) => {
+                match find_option(&select.options, crate::report::EntryType::Expense.name()) {
+                    Some(option) => option.clone(),
+                    None => return Ok(None),
+                }
+            }
+        };
+
+        Ok(Some((
+            property.clone(),
+            notion::models::properties::PropertyValue::Select {
+                id: id.clone(),
+                select: Some(notion::models::properties::SelectedValue {
+                    id: Some(option.id),
+                    name: Some(option.name),
+                    color: option.color,
+                }),
+            },
+        )))
+    }
+
+    /// The Trip relation set to the page titled `args.trip`, or to the one picked
+    /// in a prompt starting at the current trip.
+    async fn trip_property(
+        &mut self,
+        db_properties: &HashMap<String, notion::models::properties::PropertyConfiguration>,
+        args: &crate::cli::AddArgs,
+        current: Option<&notion::models::Page>,
+        prompted: bool,
+    ) -> Result<Option<(String, notion::models::properties::PropertyValue)>> {
+        let property = self.settings.properties.trip.clone();
+        let Some(notion::models::properties::PropertyConfiguration::Relation { id, relation }) =
+            db_properties.get(&property)
+        else {
+            return Ok(None);
+        };
+        if self.trips_cache.is_none() {
+            self.trips_cache = self
+                .fetch_pages(&self.trip_cache, &relation.database_id, "trips")
+                .await;
+        }
+        let trips = self.trips_cache.as_deref().unwrap_or_default();
+
+        let page_id = match &args.trip {
+            Some(trip) => Some(
+                find_page(trips, trip)
+                    .map(|page| page.id.clone())
+                    .ok_or_else(|| eyre!("Unknown trip: {}", trip))?,
+            ),
+            None if prompted && !trips.is_empty() => {
+                let preselect = current.and_then(|page| {
+                    let ids = crate::report::page_relation_ids(page, &property);
+                    trips
+                        .iter()
+                        .find(|trip| ids.contains(&trip.id))
+                        .and_then(|trip| trip.title())
+                });
+                select_trip(trips, preselect.as_ref())?
+            }
+            None => return Ok(None),
+        };
+
+        // Edited entries get an empty relation when "No trip" is picked.
+        Ok(Some((
+            property,
+            notion::models::properties::PropertyValue::Relation {
+                id: id.clone(),
+                relation: Some(
+                    page_id
+                        .into_iter()
+                        .map(|id| notion::models::properties::RelationValue { id })
+                        .collect(),
+                ),
+            },
+        )))
+    }
+
+    /// The Payer property set to `args.payer`, or to the option picked in a
+    /// prompt starting at the current or last payer. People properties are only
+    /// read, since picking workspace users would need another request.
+    fn payer_property(
+        &self,
+        db_properties: &HashMap<String, notion::models::properties::PropertyConfiguration>,
+        args: &crate::cli::AddArgs,
+        current: Option<&notion::models::Page>,
+        prompted: bool,
+    ) -> Result<Option<(String, notion::models::properties::PropertyValue)>> {
+        let property = &self.settings.properties.payer;
+        let Some(notion::models::properties::PropertyConfiguration::Select { id, select }) =
+            db_properties.get(property)
+        else {
+            return Ok(None);
+        };
+
+        let option = match &args.payer {
+            Some(payer) => find_option(&select.options, payer)
+                .cloned()
+                .ok_or_else(|| eyre!("Unknown payer: {}", payer))?,
+            None if prompted && !select.options.is_empty() => {
+                let preselect = match current {
+                    Some(page) => crate::report::page_payer(page, property),
+                    None => self.history.first().and_then(|last| last.payer.clone()),
+                };
+                select_option("Payer:", &select.options, preselect.as_ref())?
+            }
+            None => return Ok(None),
+        };
+
+        Ok(Some((
+            property.clone(),
+            notion::models::properties::PropertyValue::Select {
+                id: id.clone(),
+                select: Some(notion::models::properties::SelectedValue {
+                    id: Some(option.id),
+                    name: Some(option.name),
+                    color: option.color,
+                }),
+            },
+        )))
+    }
+
+    /// The Reimbursable checkbox and, for reimbursable entries, the Project and,
+    /// when editing, whether it was reimbursed.
+    fn reimbursement_properties(
+        &self,
+        db_properties: &HashMap<String, notion::models::properties::PropertyConfiguration>,
+        args: &crate::cli::AddArgs,
+        current: Option<&notion::models::Page>,
+        prompted: bool,
+    ) -> Result<Vec<(String, notion::models::properties::PropertyValue)>> {
+        use notion::models::properties::{PropertyConfiguration, PropertyValue};
+
+        let names = &self.settings.properties;
+        let Some(PropertyConfiguration::Checkbox { id }) = db_properties.get(&names.reimbursable)
+        else {
+            return Ok(vec![]);
+        };
+        let fields = current.map(|page| entry_fields(&page.properties.properties, names, &[]));
+
+        let reimbursable = if args.reimbursable || args.project.is_some() {
+            true
+        } else if prompted {
+            inquire::Confirm::new("Reimbursable?")
+                .with_default(fields.as_ref().is_some_and(|fields| fields.reimbursable))
+                .prompt()?
+        } else {
+            return Ok(vec![]);
+        };
+        let mut properties = vec![(
+            names.reimbursable.clone(),
+            PropertyValue::Checkbox {
+                id: id.clone(),
+                checkbox: reimbursable,
+            },
+        )];
+        if !reimbursable {
+            return Ok(properties);
+        }
+
+        if let Some(PropertyConfiguration::Select { id, select }) =
+            db_properties.get(&names.project)
+        {
+            let project = match &args.project {
+                Some(project) => {
+                    crate::prompt::tag_values(&select.options, std::slice::from_ref(project)).pop()
+                }
+                None if prompted && !select.options.is_empty() => {
+                    let preselect = fields
+                        .as_ref()
+                        .and_then(|fields| fields.project.clone())
+                        .or_else(|| self.history.iter().find_map(|last| last.project.clone()));
+                    let option = select_option("Project:", &select.options, preselect.as_ref())?;
+                    Some(crate::prompt::selected_value(&option))
+                }
+                None => None,
+            };
+            if let Some(project) = project {
+                properties.push((
+                    names.project.clone(),
+                    PropertyValue::Select {
+                        id: id.clone(),
+                        select: Some(project),
+                    },
+                ));
+            }
+        }
+
+        let Some(page) = current else {
+            return Ok(properties);
+        };
+        match db_properties.get(&names.reimbursed) {
+            Some(PropertyConfiguration::Checkbox { id }) => {
+                let reimbursed = inquire::Confirm::new("Reimbursed?")
+                    .with_default(crate::report::page_checkbox(page, &names.reimbursed))
+                    .prompt()?;
+                properties.push((
+                    names.reimbursed.clone(),
+                    PropertyValue::Checkbox {
+                        id: id.clone(),
+                        checkbox: reimbursed,
+                    },
+                ));
+            }
+            Some(PropertyConfiguration::Status { id, status }) => {
+                let current = match page.properties.properties.get(&names.reimbursed) {
+                    Some(PropertyValue::Status {
+                        status: Some(status),
+                        ..
+                    }) => status.name.clone(),
+                    _ => None,
+                };
+                let option = select_option("Reimbursed:", &status.options, current.as_ref())?;
+                properties.push((
+                    names.reimbursed.clone(),
+                    PropertyValue::Status {
+                        id: id.clone(),
+                        status: Some(crate::prompt::selected_value(&option)),
+                    },
+                ));
+            }
+            _ => {}
+        }
 
         Ok(properties)
     }
+
+    /// `path` once it's checked to be a receipt that can be attached.
+    fn checked_attachment(
+        &self,
+        path: Option<std::path::PathBuf>,
+    ) -> Result<Option<std::path::PathBuf>> {
+        let Some(path) = path else {
+            return Ok(None);
+        };
+        if self.settings.storage != crate::settings::Storage::Notion {
+            return Err(eyre!("Receipts can only be attached to Notion pages"));
+        }
+        if self.offline {
+            return Err(eyre!("Receipts can't be attached with `--offline`"));
+        }
+        crate::attachment::check(&path)?;
+
+        // Queued entries may be synced from another directory.
+        Ok(Some(std::path::absolute(&path)?))
+    }
+
+    /// Uploads the receipt at `path` to the Receipt property of `page`, or to
+    /// its content when it has none, reporting but otherwise ignoring failures
+    /// since the entry is saved.
+    async fn attach_receipt(&self, page: &notion::models::Page, path: Option<&std::path::Path>) {
+        let Some(path) = path else {
+            return;
+        };
+
+        let property = &self.settings.properties.receipt;
+        if let Err(err) = self.backend.attach_receipt(page, property, path).await {
+            eprintln!("Couldn't attach {}: {}", path.display(), err);
+        }
+    }
+
+    /// Adds the line items of a receipt to the content of the page `page_id`,
+    /// reporting but otherwise ignoring failures since the entry is saved.
+    async fn append_items(
+        &self,
+        page_id: &notion::ids::PageId,
+        items: &[crate::receipt::LineItem],
+    ) {
+        if items.is_empty() {
+            return;
+        }
+
+        let blocks = crate::receipt::blocks(items);
+        if let Err(err) = self.backend.append_content(page_id, blocks).await {
+            eprintln!("Couldn't add the line items: {}", err);
+        }
+    }
+
+    /// Sends the `notify` messages about a created `page`, reporting but otherwise
+    /// ignoring failures.
+    async fn notify_created(&self, page: &notion::models::Page) {
+        let Some(notify) = &self.settings.notify else {
+            return;
+        };
+
+        if let Err(err) = self.send_notifications(notify, page).await {
+            eprintln!("Couldn't send the notification: {}", err);
+        }
+    }
+
+    /// Sends last week's digest as a notification, and by email if `email`.
+    async fn digest(&mut self, email: bool) -> Result<()> {
+        let notify = self.settings.notify.clone();
+        let email = email.then(|| self.email_settings()).transpose()?;
+        if notify.is_none() && email.is_none() {
+            return Err(eyre!(
+                "Set `notify.url` to where the digest should be sent, or pass --email"
+            ));
+        }
+
+        let db = self.get_database().await?;
+        let names = self.settings.properties.clone();
+        let today = notion::chrono::offset::Local::now().date_naive();
+        let (week, _) = crate::report::Comparison::weeks(today, self.settings.display.week_start);
+
+        let query = crate::backend::Query {
+            filter: Some(crate::backend::Filter::date_range(
+                &names.date,
+                week.0,
+                week.1,
+            )),
+            ..Default::default()
+        };
+        let entries = self.backend.query_entries(query).await?;
+        let categories = self.categories(&db).await;
+        let digest = crate::report::Digest::new(week, &entries, categories, &names);
+
+        if let Some(notify) = &notify {
+            crate::notify::send(notify, crate::notify::Event::Digest(&digest)).await?;
+        }
+        if let Some(email) = &email {
+            let subject = format!("Expenses: {}", digest.title());
+            crate::email::send(email, &subject, &digest.to_string()).await?;
+        }
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&digest.json())?);
+        } else {
+            println!("{}", digest);
+        }
+
+        Ok(())
+    }
+
+    async fn send_notifications(
+        &self,
+        notify: &crate::settings::NotifySettings,
+        page: &notion::models::Page,
+    ) -> Result<()> {
+        let names = &self.settings.properties;
+        let categories = self.categories_cache.as_deref().unwrap_or_default();
+        let fields = entry_fields(&page.properties.properties, names, categories);
+        crate::notify::send(notify, crate::notify::Event::Created(&fields)).await?;
+
+        let (Some(category), Some(date), Some(amount)) = (
+            &fields.category,
+            fields.date,
+            crate::report::page_amount(page, &names.amount),
+        ) else {
+            return Ok(());
+        };
+        let Some(limit) = self.settings.budget(category) else {
+            return Ok(());
+        };
+
+        // The new entry may not be in the results yet, so it's added separately.
+        let mut entries = self
+            .backend
+            .query_entries(crate::report::month_query(&names.date, date))
+            .await?;
+        entries.retain(|entry| entry.id != page.id);
+        let spent = crate::report::MonthReport::new(date, &entries, categories, names)
+            .category_total(category);
+
+        if crate::notify::crosses_budget(spent, amount, limit, notify.budget_threshold) {
+            let event = crate::notify::Event::Budget {
+                category,
+                spent: spent + amount,
+                limit,
+            };
+            crate::notify::send(notify, event).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Prints the month-to-date spending and what's left of the monthly budget
+    /// of the entry's category.
+    async fn print_month_summary(&self, fields: &crate::cli::AddArgs) -> Result<()> {
+        let names = &self.settings.properties;
+        let categories = self.categories_cache.as_deref().unwrap_or_default();
+        let today = notion::chrono::offset::Local::now().date_naive();
+
+        let entries = self
+            .backend
+            .query_entries(crate::report::month_query(&names.date, today))
+            .await?;
+        let report = crate::report::MonthReport::new(today, &entries, categories, names);
+
+        println!(
+            "Spent this month: {}",
+            crate::report::format_amount(report.total())
+        );
+
+        let (Some(category), Some(date)) = (&fields.category, fields.date) else {
+            return Ok(());
+        };
+        let Some(limit) = self.settings.budget(category) else {
+            return Ok(());
+        };
+
+        let report = if crate::report::month_start(date) == crate::report::month_start(today) {
+            report
+        } else {
+            let entries = self
+                .backend
+                .query_entries(crate::report::month_query(&names.date, date))
+                .await?;
+            crate::report::MonthReport::new(date, &entries, categories, names)
+        };
+
+        println!(
+            "{}",
+            budget_line(category, report.category_total(category), limit)
+        );
+
+        Ok(())
+    }
+
+    /// Prompts for the values of a new entry, or for the new values of `current`
+    /// with its values pre-filled. A canceled prompt fails with the values
+    /// entered until then.
+    async fn create_page_properties(
+        &mut self,
+        db_properties: &HashMap<String, notion::models::properties::PropertyConfiguration>,
+        args: &crate::cli::AddArgs,
+        current: Option<&notion::models::Page>,
+    ) -> Result<HashMap<String, notion::models::properties::PropertyValue>> {
+        let mut properties = HashMap::new();
+
+        match self
+            .prompt_properties(db_properties, args, current, &mut properties)
+            .await
+        {
+            Ok(()) => Ok(properties),
+            Err(err) => Err(with_partial(
+                err,
+                &entry_fields(
+                    &properties,
+                    &self.settings.properties,
+                    self.categories_cache.as_deref().unwrap_or_default(),
+                ),
+            )),
+        }
+    }
+
+    async fn prompt_properties(
+        &mut self,
+        db_properties: &HashMap<String, notion::models::properties::PropertyConfiguration>,
+        args: &crate::cli::AddArgs,
+        current: Option<&notion::models::Page>,
+        properties: &mut HashMap<String, notion::models::properties::PropertyValue>,
+    ) -> Result<()> {
+        let names = self.settings.properties.clone();
+        let mut preselect =
+            current.and_then(|page| crate::report::page_select_name(page, &names.category));
+        // A new title whose category is picked below, offered to be remembered.
+        let mut unmapped_title = None;
+        let mut title = None;
+        let mut entered_amount = None;
+
+        // Flags leave the optional fields out, the defaults under `prompts` don't.
+        let mut interactive = args.is_empty() || current.is_some();
+
+        // A templated title fills in the rest, so only the date is asked for.
+        let mut filled = None;
+        for field in self.settings.prompts.sequence() {
+            let given = filled.as_ref().unwrap_or(args);
+            // Defaults come last, after the template and the rules.
+            let args = &match current {
+                Some(_) => given.clone(),
+                None => given.clone().or(self.settings.prompts.defaults.clone()),
+            };
+            let skipped = self.settings.prompts.skip.contains(&field);
+            let prompted = interactive && !skipped;
+            match field {
+                crate::settings::PromptField::Name => {
+                    if let Some(notion::models::properties::PropertyConfiguration::Title { id }) =
+                        db_properties.get(&names.name)
+                    {
+                        let name = match &args.name {
+                            Some(name) => name.clone(),
+                            None if skipped => continue,
+                            None => {
+                                let initial = current.and_then(|page| page.title());
+                                let mut prompt = inquire::Text::new("Name:")
+                                    .with_autocomplete(TitleCompleter::new(self.known_titles()));
+                                if let Some(initial) = &initial {
+                                    prompt = prompt.with_initial_value(initial);
+                                }
+
+                                let name = prompt.prompt()?;
+                                self.prompt_history.add_name(&name);
+                                self.store_prompt_history();
+                                name
+                            }
+                        };
+
+                        title = Some(name.clone());
+                        let rich_title = crate::prompt::rich_text(&name);
+
+                        if current.is_none() {
+                            preselect = self.settings.get(name.as_ref()).cloned();
+                            if preselect.is_none() && args.category.is_none() {
+                                unmapped_title = Some(name.clone());
+                            }
+                            preselect = preselect
+                                .or_else(|| frequent_category(&self.history, &name).cloned());
+                        }
+
+                        self.title_history.retain(|title| title != &name);
+                        self.title_history.insert(0, name.clone());
+
+                        properties.insert(
+                            names.name.clone(),
+                            notion::models::properties::PropertyValue::Title {
+                                id: id.clone(),
+                                title: rich_title,
+                            },
+                        );
+                    }
+                }
+                crate::settings::PromptField::Amount => {
+                    if let Some(notion::models::properties::PropertyConfiguration::Number {
+                        id,
+                        ..
+                    }) = db_properties.get(&names.amount)
+                    {
+                        // An entry added with flags doesn't load the previous one up front.
+                        if self.history.is_empty()
+                            && args.amount.as_ref().is_some_and(|a| a.contains('_'))
+                        {
+                            self.load_history().await;
+                        }
+                        let variables = self.amount_variables();
+                        let read = self.amount_reader();
+                        let amount = match &args.amount {
+                            Some(amount) => amount.clone(),
+                            None if skipped => continue,
+                            None => {
+                                self.split = None;
+                                // Edited entries stay a single entry.
+                                let categories = match current {
+                                    Some(_) => None,
+                                    None => Some(
+                                        self.category_names(db_properties)
+                                            .await
+                                            .unwrap_or_default(),
+                                    ),
+                                };
+                                let format =
+                                    crate::calc::NumberFormat::for_locale(&self.settings.locale);
+                                let initial = current
+                                    .and_then(|page| page_amount_text(page, &names.amount, format))
+                                    .or_else(|| {
+                                        last_amount(&self.history, title.as_ref()?).cloned()
+                                    });
+                                let validated = read.clone();
+                                let known = variables.clone();
+                                let mut prompt = inquire::Text::new("Amount:").with_validator(
+                                    move |input: &str| {
+                                        let input = validated(input).0;
+                                        Ok(
+                                            match check_amount(
+                                                &input,
+                                                categories.as_deref(),
+                                                &known,
+                                            ) {
+                                                Ok(()) => inquire::validator::Validation::Valid,
+                                                Err(err) => {
+                                                    inquire::validator::Validation::Invalid(
+                                                        err.into(),
+                                                    )
+                                                }
+                                            },
+                                        )
+                                    },
+                                );
+                                if current.is_none() {
+                                    prompt = prompt.with_help_message(
+                                        "Split like `30 Groceries + 13.20 Household`",
+                                    );
+                                }
+                                if let Some(initial) = &initial {
+                                    prompt = prompt.with_initial_value(initial);
+                                }
+
+                                let amount = prompt
+                                    .with_autocomplete(HistoryCompleter {
+                                        inputs: self.prompt_history.amounts.clone(),
+                                    })
+                                    .prompt()?;
+                                self.prompt_history.add_amount(&amount);
+                                self.store_prompt_history();
+                                amount
+                            }
+                        };
+                        let (amount, currency) = read(&amount);
+                        if let Some(value) =
+                            currency_value(db_properties, &names.currency, currency)
+                        {
+                            properties.insert(names.currency.clone(), value);
+                        }
+                        let amount = match crate::calc::calc_with(&amount, &variables) {
+                            Ok(amount) => amount,
+                            Err(err) => match crate::split::parse(&amount)? {
+                                Some(split) => {
+                                    let total = split.total;
+                                    self.split = Some(split);
+                                    total
+                                }
+                                None => return Err(err),
+                            },
+                        };
+                        entered_amount = Some(amount);
+
+                        properties.insert(
+                            names.amount.clone(),
+                            notion::models::properties::PropertyValue::Number {
+                                id: id.clone(),
+                                number: crate::calc::number(amount, self.settings.decimal_places),
+                            },
+                        );
+                    }
+                }
+                crate::settings::PromptField::Date => {
+                    if let Some(notion::models::properties::PropertyConfiguration::Date { id }) =
+                        db_properties.get(&names.date)
+                    {
+                        let now = notion::chrono::offset::Local::now().date_naive();
+                        let default_date = current
+                            .and_then(|page| crate::report::page_date(page, &names.date))
+                            .or(self.last_date)
+                            .unwrap_or(now);
+                        let window = &self.settings.date;
+                        let min_date = now
+                            .checked_sub_days(notion::chrono::Days::new(window.min_days_back))
+                            .unwrap_or(notion::chrono::NaiveDate::MIN)
+                            .min(default_date);
+
+                        let date = match args.date {
+                            Some(date) => date,
+                            None if skipped => continue,
+                            None => {
+                                let mut prompt = inquire::DateSelect::new("Date:")
+                                    .with_default(default_date)
+                                    .with_min_date(min_date)
+                                    .with_week_start(self.settings.display.week_start);
+                                if !window.allow_future {
+                                    prompt = prompt.with_max_date(now.max(default_date));
+                                }
+                                prompt.prompt()?
+                            }
+                        };
+
+                        // Entries that already have a time keep one, whatever the setting.
+                        let current_time = current
+                            .and_then(|page| page.properties.properties.get(&names.date))
+                            .and_then(|value| match value {
+                                notion::models::properties::PropertyValue::Date {
+                                    date:
+                                        Some(notion::models::properties::DateValue {
+                                            start:
+                                                notion::models::properties::DateOrDateTime::DateTime(time),
+                                            ..
+                                        }),
+                                    ..
+                                } => Some(time.with_timezone(&notion::chrono::Local).time()),
+                                _ => None,
+                            });
+
+                        let time = if args.date.is_none()
+                            && (window.with_time || current_time.is_some())
+                        {
+                            inquire::CustomType::<notion::chrono::NaiveTime>::new("Time:")
+                                .with_default(
+                                    current_time.unwrap_or(notion::chrono::Local::now().time()),
+                                )
+                                .with_parser(&|input| {
+                                    notion::chrono::NaiveTime::parse_from_str(input, "%H:%M")
+                                        .map_err(|_| ())
+                                })
+                                .with_formatter(&|time| time.format("%H:%M").to_string())
+                                .with_default_value_formatter(&|time| {
+                                    time.format("%H:%M").to_string()
+                                })
+                                .with_error_message("Expected a time like 14:30")
+                                .with_help_message("Esc to leave the time out")
+                                .prompt_skippable()?
+                        } else {
+                            None
+                        };
+
+                        let start = match time.and_then(|time| {
+                            date.and_time(time)
+                                .and_local_timezone(notion::chrono::Local)
+                                .earliest()
+                        }) {
+                            Some(date_time) => {
+                                notion::models::properties::DateOrDateTime::DateTime(
+                                    date_time.with_timezone(&notion::chrono::Utc),
+                                )
+                            }
+                            None => notion::models::properties::DateOrDateTime::Date(date),
+                        };
+
+                        properties.insert(
+                            names.date.clone(),
+                            notion::models::properties::PropertyValue::Date {
+                                id: id.clone(),
+                                date: Some(notion::models::properties::DateValue {
+                                    start,
+                                    end: None,
+                                    time_zone: None,
+                                }),
+                            },
+                        );
+
+                        self.last_date = Some(date);
+                    }
+                }
+                crate::settings::PromptField::Category => {
+                    // A rule picks the category of a new entry without asking.
+                    let ruled = match (&title, current, &given.category) {
+                        (Some(title), None, None) => self.rule_category(title, entered_amount)?,
+                        _ => None,
+                    };
+                    if ruled.is_some() {
+                        unmapped_title = None;
+                    }
+                    let category = given
+                        .category
+                        .as_ref()
+                        .or(ruled.as_ref())
+                        .or(args.category.as_ref());
+
+                    // The parts of a split amount bring their categories.
+                    let category = match self.split {
+                        Some(_) => None,
+                        None if skipped && category.is_none() => None,
+                        None => {
+                            self.category_property(
+                                db_properties,
+                                category,
+                                current,
+                                preselect.take(),
+                            )
+                            .await?
+                        }
+                    };
+                    if let Some((value, picked)) = category {
+                        properties.insert(names.category.clone(), value);
+                        if let (Some(title), Some(picked)) = (unmapped_title.take(), picked) {
+                            self.offer_mapping(&title, &picked)?;
+                        }
+                    }
+                }
+                crate::settings::PromptField::Trip => {
+                    if let Some((name, value)) = self
+                        .trip_property(db_properties, args, current, prompted)
+                        .await?
+                    {
+                        properties.insert(name, value);
+                    }
+                }
+                crate::settings::PromptField::Type => {
+                    let preselect_type = (args.entry_type.is_none()
+                        && !skipped
+                        && !self.is_complete(args))
+                    .then(|| {
+                        current
+                            .and_then(|page| {
+                                crate::report::page_select_name(page, &names.entry_type)
+                            })
+                            .unwrap_or(crate::report::EntryType::Expense.name().to_string())
+                    });
+                    if let Some((name, value)) = self.entry_type_property(
+                        db_properties,
+                        args.entry_type.as_ref(),
+                        preselect_type,
+                    )? {
+                        properties.insert(name, value);
+                    }
+                }
+                crate::settings::PromptField::Payer => {
+                    if let Some((name, value)) =
+                        self.payer_property(db_properties, args, current, prompted)?
+                    {
+                        properties.insert(name, value);
+                    }
+                }
+                crate::settings::PromptField::Reimbursable => {
+                    properties.extend(self.reimbursement_properties(
+                        db_properties,
+                        args,
+                        current,
+                        prompted,
+                    )?);
+                }
+                crate::settings::PromptField::Notes => {
+                    if let Some(notion::models::properties::PropertyConfiguration::Text { id }) =
+                        db_properties.get(&names.notes)
+                    {
+                        let notes = match &args.notes {
+                            Some(notes) => Some(notes.clone()),
+                            None if prompted => {
+                                let initial = current
+                                    .and_then(|page| page_property_to_string(page, &names.notes));
+                                crate::prompt::notes("Notes:", initial.as_deref())?
+                            }
+                            None => None,
+                        };
+
+                        // Edited entries get an empty value so removed notes are cleared.
+                        if notes.is_some() || current.is_some() {
+                            properties.insert(
+                                names.notes.clone(),
+                                notion::models::properties::PropertyValue::Text {
+                                    id: id.clone(),
+                                    rich_text: notes
+                                        .as_deref()
+                                        .map(crate::prompt::rich_text)
+                                        .unwrap_or_default(),
+                                },
+                            );
+                        }
+                    }
+                }
+                crate::settings::PromptField::Tags => {
+                    if let Some(notion::models::properties::PropertyConfiguration::MultiSelect {
+                        id,
+                        multi_select,
+                    }) = db_properties.get(&names.tags)
+                    {
+                        let tags = if !args.tags.is_empty() {
+                            Some(crate::prompt::tag_values(&multi_select.options, &args.tags))
+                        } else if prompted {
+                            let selected = current
+                                .map(|page| {
+                                    entry_fields(&page.properties.properties, &names, &[]).tags
+                                })
+                                .unwrap_or_default();
+                            crate::prompt::tags("Tags:", &multi_select.options, &selected)?
+                        } else {
+                            None
+                        };
+
+                        if let Some(tags) = tags {
+                            properties.insert(
+                                names.tags.clone(),
+                                notion::models::properties::PropertyValue::MultiSelect {
+                                    id: id.clone(),
+                                    multi_select: Some(tags),
+                                },
+                            );
+                        }
+                    }
+                }
+                crate::settings::PromptField::Other => {
+                    if prompted && current.is_none() {
+                        let mut extra: Vec<(&String, _)> = db_properties
+                            .iter()
+                            .filter(|(name, _)| !names.contains(name))
+                            .collect();
+                        extra.sort_by_key(|(name, _)| name.as_str());
+
+                        for (name, config) in extra {
+                            if let Some(value) = crate::prompt::prompt_property(name, config)? {
+                                properties.insert(name.clone(), value);
+                            }
+                        }
+                    }
+                }
+            }
+
+            self.save_draft(properties);
+
+            let template = title
+                .as_deref()
+                .and_then(|title| self.settings.template(title));
+            if let (Some(template), None, crate::settings::PromptField::Name) =
+                (template, current, field)
+            {
+                filled = Some(template_args(
+                    given,
+                    template,
+                    self.settings.get(&template.title),
+                    crate::calc::NumberFormat::for_locale(&self.settings.locale),
+                ));
+                interactive = false;
+            }
+        }
+
+        Ok(())
+    }
 }
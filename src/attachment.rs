@@ -0,0 +1,120 @@
+use eyre::{eyre, Result};
+
+/// The largest file Notion takes in a single part.
+const MAX_SIZE: u64 = 20 * 1024 * 1024;
+
+/// The content type of the receipt at `path`, by its extension.
+pub fn content_type(path: &std::path::Path) -> Result<&'static str> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+
+    Ok(match extension.as_str() {
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "heic" => "image/heic",
+        "pdf" => "application/pdf",
+        _ => {
+            return Err(eyre!(
+                "Can't attach {}, only images and PDFs are supported",
+                path.display()
+            ))
+        }
+    })
+}
+
+/// Fails unless `path` is a file Notion would take.
+pub fn check(path: &std::path::Path) -> Result<()> {
+    content_type(path)?;
+    let metadata =
+        std::fs::metadata(path).map_err(|err| eyre!("Can't attach {}: {}", path.display(), err))?;
+    if !metadata.is_file() {
+        return Err(eyre!("Can't attach {}, it isn't a file", path.display()));
+    }
+    if metadata.len() > MAX_SIZE {
+        return Err(eyre!(
+            "Can't attach {}, files can be up to 20 MB",
+            path.display()
+        ));
+    }
+
+    Ok(())
+}
+
+/// A path typed or dropped into a prompt, without the quotes terminals add and
+/// with `~` expanded.
+pub fn typed_path(input: &str) -> std::path::PathBuf {
+    let input = input.trim().trim_matches(['\'', '"']);
+
+    match (input.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => std::path::Path::new(&home).join(rest),
+        _ => input.into(),
+    }
+}
+
+pub fn file_name(path: &std::path::Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or("receipt".to_string())
+}
+
+/// A `multipart/form-data` body with `content` as its `file` field.
+pub fn multipart(boundary: &str, name: &str, content_type: &str, content: &[u8]) -> Vec<u8> {
+    let mut body = format!(
+        "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+        boundary,
+        name.replace(['"', '\r', '\n'], "_"),
+        content_type
+    )
+    .into_bytes();
+    body.extend_from_slice(content);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+
+    body
+}
+
+/// An image block, or a PDF one, showing the file upload `id`.
+pub fn block(id: &str, content_type: &str) -> serde_json::Value {
+    let kind = match content_type {
+        "application/pdf" => "pdf",
+        _ => "image",
+    };
+
+    serde_json::json!({
+        "object": "block",
+        "type": kind,
+        kind: {"type": "file_upload", "file_upload": {"id": id}},
+    })
+}
+
+#[test]
+fn attachment_test() {
+    assert_eq!(
+        content_type(std::path::Path::new("scans/Receipt.JPG")).unwrap(),
+        "image/jpeg"
+    );
+    assert!(content_type(std::path::Path::new("notes.txt")).is_err());
+    assert_eq!(
+        typed_path(" '/tmp/receipt 1.png' "),
+        std::path::PathBuf::from("/tmp/receipt 1.png")
+    );
+
+    assert_eq!(
+        multipart("b", "a\"b.png", "image/png", b"PNG"),
+        b"--b\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a_b.png\"\r\n\
+          Content-Type: image/png\r\n\r\nPNG\r\n--b--\r\n"
+    );
+
+    assert_eq!(
+        block("upload", "application/pdf"),
+        serde_json::json!({
+            "object": "block",
+            "type": "pdf",
+            "pdf": {"type": "file_upload", "file_upload": {"id": "upload"}},
+        })
+    );
+}
@@ -0,0 +1,1087 @@
+use eyre::Result;
+use serde::Serialize;
+
+const NOTION_API_VERSION: &str = "2022-02-22";
+/// File uploads need a newer version than the rest.
+const FILE_UPLOAD_API_VERSION: &str = "2022-06-28";
+/// The most results Notion returns per request.
+const MAX_PAGE_SIZE: usize = 100;
+
+/// A database filter. Unlike `notion::models::search::FilterCondition` it can be
+/// combined with `and`/`or`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum Filter {
+    Property(notion::models::search::FilterCondition),
+    And {
+        and: Vec<Filter>,
+    },
+    Or {
+        or: Vec<Filter>,
+    },
+    /// Compares when entries were last edited, see [`Filter::edited_since`].
+    LastEdited {
+        timestamp: notion::models::search::DatabaseSortTimestamp,
+        last_edited_time: notion::models::search::DateCondition,
+    },
+}
+
+impl Filter {
+    pub fn property(
+        property: impl Into<String>,
+        condition: notion::models::search::PropertyCondition,
+    ) -> Self {
+        Self::Property(notion::models::search::FilterCondition {
+            property: property.into(),
+            condition,
+        })
+    }
+
+    /// Entries with `property` on or after `from` and before `to`.
+    pub fn date_range(
+        property: impl Into<String>,
+        from: notion::chrono::NaiveDate,
+        to: notion::chrono::NaiveDate,
+    ) -> Self {
+        use notion::models::search::{DateCondition, PropertyCondition};
+
+        let property = property.into();
+
+        Self::And {
+            and: vec![
+                Self::property(
+                    property.clone(),
+                    PropertyCondition::Date(DateCondition::OnOrAfter(midnight(from))),
+                ),
+                Self::property(
+                    property,
+                    PropertyCondition::Date(DateCondition::Before(midnight(to))),
+                ),
+            ],
+        }
+    }
+
+    /// Entries edited at or after `time`.
+    pub fn edited_since(time: notion::chrono::DateTime<notion::chrono::Utc>) -> Self {
+        Self::LastEdited {
+            timestamp: notion::models::search::DatabaseSortTimestamp::LastEditedTime,
+            last_edited_time: notion::models::search::DateCondition::OnOrAfter(time),
+        }
+    }
+}
+
+/// Start of `date` as the timestamp Notion date filters expect.
+pub fn midnight(date: notion::chrono::NaiveDate) -> notion::chrono::DateTime<notion::chrono::Utc> {
+    date.and_hms_opt(0, 0, 0).unwrap().and_utc()
+}
+
+/// Body of a database query, see <https://developers.notion.com/reference/post-database-query>.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Query {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sorts: Option<Vec<notion::models::search::DatabaseSort>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<Filter>,
+    #[serde(flatten)]
+    pub paging: Option<notion::models::paging::Paging>,
+}
+
+/// Newest first by `property`.
+pub fn descending(property: impl Into<String>) -> Vec<notion::models::search::DatabaseSort> {
+    vec![notion::models::search::DatabaseSort {
+        property: Some(property.into()),
+        timestamp: None,
+        direction: notion::models::search::SortDirection::Descending,
+    }]
+}
+
+/// Most recently created first.
+pub fn newest_created() -> Vec<notion::models::search::DatabaseSort> {
+    vec![notion::models::search::DatabaseSort {
+        property: None,
+        timestamp: Some(notion::models::search::DatabaseSortTimestamp::CreatedTime),
+        direction: notion::models::search::SortDirection::Descending,
+    }]
+}
+
+pub fn database_sorting(sorts: Vec<notion::models::search::DatabaseSort>, page_size: u8) -> Query {
+    Query {
+        sorts: Some(sorts),
+        paging: Some(notion::models::paging::Paging {
+            start_cursor: None,
+            page_size: Some(page_size),
+        }),
+        filter: None,
+    }
+}
+
+/// Storage for the expense entries.
+///
+/// Entries and categories are exchanged as Notion models, since the prompts are
+/// driven by the database schema.
+#[async_trait::async_trait]
+pub trait ExpenseBackend: Send + Sync {
+    /// Schema of the expense database.
+    async fn schema(&self) -> Result<notion::models::Database>;
+
+    /// Entries matching `query`. Without a page size every match is returned.
+    async fn query_entries(&self, query: Query) -> Result<Vec<notion::models::Page>>;
+
+    /// Pages of the database a Category relation points to.
+    async fn categories(
+        &self,
+        database_id: &notion::ids::DatabaseId,
+    ) -> Result<Vec<notion::models::Page>>;
+
+    async fn create_entry(
+        &self,
+        properties: notion::models::Properties,
+    ) -> Result<notion::models::Page>;
+
+    /// Adds a page titled `name` to the database a Category relation points to.
+    async fn create_category(
+        &self,
+        database_id: &notion::ids::DatabaseId,
+        name: &str,
+    ) -> Result<notion::models::Page>;
+
+    /// Overwrites the given properties of an existing entry.
+    async fn update_entry(
+        &self,
+        page_id: &notion::ids::PageId,
+        properties: notion::models::Properties,
+    ) -> Result<notion::models::Page>;
+
+    /// Moves an entry to the trash.
+    async fn archive_entry(&self, page_id: &notion::ids::PageId) -> Result<()>;
+
+    /// Adds `blocks` to the content of the entry `page_id`.
+    async fn append_content(
+        &self,
+        _page_id: &notion::ids::PageId,
+        _blocks: Vec<serde_json::Value>,
+    ) -> Result<()> {
+        Err(eyre::eyre!("Only Notion pages have content to add to"))
+    }
+
+    /// Adds a page titled `title` with `blocks` as its content to the
+    /// database `database_id`.
+    async fn create_document(
+        &self,
+        _database_id: &notion::ids::DatabaseId,
+        _title: &str,
+        _blocks: Vec<serde_json::Value>,
+    ) -> Result<()> {
+        Err(eyre::eyre!(
+            "Only Notion databases can have pages added to them"
+        ))
+    }
+
+    /// Uploads the receipt at `path` to the files property `property` of
+    /// `page`, or to its content when it has none.
+    async fn attach_receipt(
+        &self,
+        _page: &notion::models::Page,
+        _property: &str,
+        _path: &std::path::Path,
+    ) -> Result<()> {
+        Err(eyre::eyre!("Receipts can only be attached to Notion pages"))
+    }
+
+    /// Creates an empty database titled `title` with the properties of the
+    /// expense database, in the same page. Also returns the properties left out
+    /// since the API can't create them.
+    async fn copy_database(&self, _title: &str) -> Result<(notion::models::Database, Vec<String>)> {
+        Err(eyre::eyre!("Only Notion databases can be rolled over"))
+    }
+
+    /// Moves the database `database_id` to the trash, with its pages.
+    async fn archive_database(&self, _database_id: &notion::ids::DatabaseId) -> Result<()> {
+        Err(eyre::eyre!(
+            "Only Notion databases can be moved to the trash"
+        ))
+    }
+
+    /// The last `count` entries by `date_property`, newest first.
+    async fn recent_entries(
+        &self,
+        sorts: Vec<notion::models::search::DatabaseSort>,
+        count: u8,
+    ) -> Result<Vec<notion::models::Page>> {
+        self.query_entries(database_sorting(sorts, count)).await
+    }
+}
+
+/// Another backend with every change refused, for `--read-only`.
+pub struct ReadOnlyBackend(pub std::sync::Arc<dyn ExpenseBackend>);
+
+#[async_trait::async_trait]
+impl ExpenseBackend for ReadOnlyBackend {
+    async fn schema(&self) -> Result<notion::models::Database> {
+        self.0.schema().await
+    }
+
+    async fn query_entries(&self, query: Query) -> Result<Vec<notion::models::Page>> {
+        self.0.query_entries(query).await
+    }
+
+    async fn categories(
+        &self,
+        database_id: &notion::ids::DatabaseId,
+    ) -> Result<Vec<notion::models::Page>> {
+        self.0.categories(database_id).await
+    }
+
+    async fn create_entry(&self, _: notion::models::Properties) -> Result<notion::models::Page> {
+        Err(crate::settings::read_only_error())
+    }
+
+    async fn create_category(
+        &self,
+        _: &notion::ids::DatabaseId,
+        _: &str,
+    ) -> Result<notion::models::Page> {
+        Err(crate::settings::read_only_error())
+    }
+
+    async fn update_entry(
+        &self,
+        _: &notion::ids::PageId,
+        _: notion::models::Properties,
+    ) -> Result<notion::models::Page> {
+        Err(crate::settings::read_only_error())
+    }
+
+    async fn archive_entry(&self, _: &notion::ids::PageId) -> Result<()> {
+        Err(crate::settings::read_only_error())
+    }
+
+    async fn append_content(
+        &self,
+        _: &notion::ids::PageId,
+        _: Vec<serde_json::Value>,
+    ) -> Result<()> {
+        Err(crate::settings::read_only_error())
+    }
+
+    async fn create_document(
+        &self,
+        _: &notion::ids::DatabaseId,
+        _: &str,
+        _: Vec<serde_json::Value>,
+    ) -> Result<()> {
+        Err(crate::settings::read_only_error())
+    }
+
+    async fn attach_receipt(
+        &self,
+        _: &notion::models::Page,
+        _: &str,
+        _: &std::path::Path,
+    ) -> Result<()> {
+        Err(crate::settings::read_only_error())
+    }
+
+    async fn copy_database(&self, _: &str) -> Result<(notion::models::Database, Vec<String>)> {
+        Err(crate::settings::read_only_error())
+    }
+
+    async fn archive_database(&self, _: &notion::ids::DatabaseId) -> Result<()> {
+        Err(crate::settings::read_only_error())
+    }
+}
+
+pub struct NotionBackend {
+    client: reqwest::Client,
+    database_id: notion::ids::DatabaseId,
+    retry: crate::settings::RetrySettings,
+}
+
+/// The delay a 429 response asks for, see <https://developers.notion.com/reference/request-limits>.
+fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<std::time::Duration> {
+    headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(std::time::Duration::from_secs)
+}
+
+/// Errors that may go away when the request is sent again.
+fn is_transient(err: &reqwest::Error) -> bool {
+    err.is_timeout() || err.is_connect() || err.is_request()
+}
+
+fn http_client(api_key: &str) -> Result<reqwest::Client> {
+    let mut auth_value = reqwest::header::HeaderValue::from_str(&format!("Bearer {}", api_key))?;
+    auth_value.set_sensitive(true);
+
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert(
+        "Notion-Version",
+        reqwest::header::HeaderValue::from_static(NOTION_API_VERSION),
+    );
+    headers.insert(reqwest::header::AUTHORIZATION, auth_value);
+
+    Ok(reqwest::Client::builder()
+        .default_headers(headers)
+        .build()?)
+}
+
+/// Sends a request, retrying on rate limits, server errors and network
+/// failures with exponential backoff.
+async fn send(
+    retry: &crate::settings::RetrySettings,
+    request: reqwest::RequestBuilder,
+) -> Result<notion::models::Object> {
+    let object: notion::models::Object = serde_json::from_str(&send_body(retry, request).await?)?;
+
+    match object {
+        notion::models::Object::Error { error } => Err(notion::Error::ApiError { error }.into()),
+        object => Ok(object),
+    }
+}
+
+/// Like `send`, for responses the Notion models can't parse, such as blocks
+/// the crate doesn't know.
+async fn send_json(
+    retry: &crate::settings::RetrySettings,
+    request: reqwest::RequestBuilder,
+) -> Result<serde_json::Value> {
+    let value: serde_json::Value = serde_json::from_str(&send_body(retry, request).await?)?;
+
+    match value["object"].as_str() {
+        Some("error") => match serde_json::from_value(value)? {
+            notion::models::Object::Error { error } => {
+                Err(notion::Error::ApiError { error }.into())
+            }
+            response => Err(notion::Error::UnexpectedResponse { response }.into()),
+        },
+        _ => Ok(value),
+    }
+}
+
+async fn send_body(
+    retry: &crate::settings::RetrySettings,
+    request: reqwest::RequestBuilder,
+) -> Result<String> {
+    let mut delay = std::time::Duration::from_millis(retry.delay_ms);
+    let mut attempt = 1;
+
+    let response = loop {
+        let retryable = attempt < retry.attempts;
+        let (client, built) = request
+            .try_clone()
+            .ok_or_else(|| eyre::eyre!("Request body can't be resent"))?
+            .build_split();
+        let built = built?;
+        let (method, url) = (built.method().clone(), built.url().clone());
+
+        let started = std::time::Instant::now();
+        let result = client.execute(built).await;
+        match &result {
+            Ok(response) => tracing::debug!(
+                %method,
+                %url,
+                status = %response.status(),
+                elapsed = ?started.elapsed(),
+                attempt,
+                "Notion request"
+            ),
+            Err(err) => tracing::debug!(
+                %method,
+                %url,
+                error = %err,
+                elapsed = ?started.elapsed(),
+                attempt,
+                "Notion request failed"
+            ),
+        }
+
+        let wait = match result {
+            Ok(response)
+                if retryable
+                    && (response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS
+                        || response.status().is_server_error()) =>
+            {
+                retry_after(response.headers()).unwrap_or(delay)
+            }
+            Err(err) if retryable && is_transient(&err) => delay,
+            result => break result?,
+        };
+
+        tracing::warn!(%method, %url, ?wait, attempt, "Retrying the Notion request");
+        tokio::time::sleep(wait).await;
+        delay *= 2;
+        attempt += 1;
+    };
+
+    let body = response.text().await?;
+    tracing::trace!(%body, "Notion response");
+
+    Ok(body)
+}
+
+/// The objects of the kind `object` shared with the integration `api_key`
+/// belongs to.
+async fn search(
+    api_key: &str,
+    retry: &crate::settings::RetrySettings,
+    object: &str,
+) -> Result<Vec<notion::models::Object>> {
+    let request = http_client(api_key)?
+        .post("https://api.notion.com/v1/search")
+        .json(&serde_json::json!({
+            "filter": {"property": "object", "value": object},
+            "page_size": 100,
+        }));
+
+    match send(retry, request).await? {
+        notion::models::Object::List { list } => Ok(list.results),
+        response => Err(notion::Error::UnexpectedResponse { response }.into()),
+    }
+}
+
+/// Databases shared with the integration `api_key` belongs to.
+pub async fn search_databases(
+    api_key: &str,
+    retry: &crate::settings::RetrySettings,
+) -> Result<Vec<notion::models::Database>> {
+    search(api_key, retry, "database")
+        .await?
+        .into_iter()
+        .map(|object| match object {
+            notion::models::Object::Database { database } => Ok(database),
+            response => Err(notion::Error::UnexpectedResponse { response }.into()),
+        })
+        .collect()
+}
+
+/// Pages shared with the integration `api_key` belongs to, leaving out the
+/// ones in databases.
+pub async fn search_pages(
+    api_key: &str,
+    retry: &crate::settings::RetrySettings,
+) -> Result<Vec<notion::models::Page>> {
+    let mut pages = vec![];
+    for object in search(api_key, retry, "page").await? {
+        match object {
+            notion::models::Object::Page { page } => {
+                if !matches!(page.parent, notion::models::Parent::Database { .. }) {
+                    pages.push(page);
+                }
+            }
+            response => return Err(notion::Error::UnexpectedResponse { response }.into()),
+        }
+    }
+
+    Ok(pages)
+}
+
+/// The properties of a retrieved database, as creating one takes them: without
+/// the ids, and without status properties, which the API can't create.
+fn copied_properties(properties: &serde_json::Value) -> (serde_json::Value, Vec<String>) {
+    let mut copied = serde_json::Map::new();
+    let mut skipped = vec![];
+
+    for (name, property) in properties.as_object().into_iter().flatten() {
+        let Some(kind) = property["type"].as_str() else {
+            continue;
+        };
+        let config = &property[kind];
+        let config = match kind {
+            "status" => {
+                skipped.push(name.clone());
+                continue;
+            }
+            "select" | "multi_select" => {
+                let options: Vec<_> = config["options"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(|option| serde_json::json!({ "name": option["name"], "color": option["color"] }))
+                    .collect();
+                serde_json::json!({ "options": options })
+            }
+            "relation" => serde_json::json!({ "database_id": config["database_id"] }),
+            "rollup" => serde_json::json!({
+                "relation_property_name": config["relation_property_name"],
+                "rollup_property_name": config["rollup_property_name"],
+                "function": config["function"],
+            }),
+            _ => config.clone(),
+        };
+        copied.insert(name.clone(), serde_json::json!({ kind: config }));
+    }
+
+    (copied.into(), skipped)
+}
+
+impl NotionBackend {
+    pub fn new(settings: &crate::settings::NotionSettings) -> Result<Self> {
+        Ok(Self {
+            client: http_client(&settings.api_key)?,
+            database_id: settings.database_id.clone(),
+            retry: settings.retry.clone(),
+        })
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<notion::models::Object> {
+        send(&self.retry, request).await
+    }
+
+    /// Adds `blocks` to the end of the page `page_id`.
+    pub async fn append_blocks(
+        &self,
+        page_id: &notion::ids::PageId,
+        blocks: Vec<serde_json::Value>,
+    ) -> Result<()> {
+        let request = self
+            .client
+            .patch(format!(
+                "https://api.notion.com/v1/blocks/{}/children",
+                page_id
+            ))
+            .json(&serde_json::json!({ "children": blocks }));
+
+        send_json(&self.retry, request).await?;
+
+        Ok(())
+    }
+
+    /// Uploads the file at `path` and returns the id to attach it by.
+    pub async fn upload_file(&self, path: &std::path::Path) -> Result<String> {
+        let content_type = crate::attachment::content_type(path)?;
+        let name = crate::attachment::file_name(path);
+        let content = tokio::fs::read(path).await?;
+
+        let request = self
+            .client
+            .post("https://api.notion.com/v1/file_uploads")
+            .header("Notion-Version", FILE_UPLOAD_API_VERSION)
+            .json(&serde_json::json!({ "filename": name, "content_type": content_type }));
+        let upload = send_json(&self.retry, request).await?;
+        let id = upload["id"]
+            .as_str()
+            .ok_or_else(|| eyre::eyre!("Notion didn't return a file upload id"))?
+            .to_string();
+
+        let boundary = uuid::Uuid::new_v4().simple().to_string();
+        let request = self
+            .client
+            .post(format!(
+                "https://api.notion.com/v1/file_uploads/{}/send",
+                id
+            ))
+            .header("Notion-Version", FILE_UPLOAD_API_VERSION)
+            .header(
+                reqwest::header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={}", boundary),
+            )
+            .body(crate::attachment::multipart(
+                &boundary,
+                &name,
+                content_type,
+                &content,
+            ));
+        send_json(&self.retry, request).await?;
+
+        Ok(id)
+    }
+
+    /// Sets the files property `property` of the page `page_id` to the
+    /// uploaded file `upload_id`.
+    pub async fn attach_file(
+        &self,
+        page_id: &notion::ids::PageId,
+        property: &str,
+        upload_id: &str,
+        name: &str,
+    ) -> Result<()> {
+        let request = self
+            .client
+            .patch(format!("https://api.notion.com/v1/pages/{}", page_id))
+            .header("Notion-Version", FILE_UPLOAD_API_VERSION)
+            .json(&serde_json::json!({
+                "properties": {
+                    property: {"files": [{
+                        "type": "file_upload",
+                        "file_upload": {"id": upload_id},
+                        "name": name,
+                    }]},
+                },
+            }));
+
+        send_json(&self.retry, request).await?;
+
+        Ok(())
+    }
+
+    /// Adds a block showing the uploaded file `upload_id` to the end of the
+    /// page `page_id`.
+    pub async fn embed_file(
+        &self,
+        page_id: &notion::ids::PageId,
+        upload_id: &str,
+        content_type: &str,
+    ) -> Result<()> {
+        let request = self
+            .client
+            .patch(format!(
+                "https://api.notion.com/v1/blocks/{}/children",
+                page_id
+            ))
+            .header("Notion-Version", FILE_UPLOAD_API_VERSION)
+            .json(&serde_json::json!({
+                "children": [crate::attachment::block(upload_id, content_type)],
+            }));
+
+        send_json(&self.retry, request).await?;
+
+        Ok(())
+    }
+
+    /// Creates a database titled `title` in the page `parent`.
+    async fn create_database(
+        &self,
+        parent: &notion::ids::PageId,
+        title: &str,
+        properties: serde_json::Value,
+    ) -> Result<notion::models::Database> {
+        let request = self
+            .client
+            .post("https://api.notion.com/v1/databases")
+            .json(&serde_json::json!({
+                "parent": { "page_id": parent },
+                "title": crate::report::rich_text(title),
+                "properties": properties,
+            }));
+
+        match self.send(request).await? {
+            notion::models::Object::Database { database } => Ok(database),
+            response => Err(notion::Error::UnexpectedResponse { response }.into()),
+        }
+    }
+
+    /// Creates an expense database with the properties `names` in the page
+    /// `parent`, next to a Categories database holding `categories`.
+    pub async fn create_expense_database(
+        &self,
+        parent: &notion::ids::PageId,
+        names: &crate::settings::PropertyNames,
+        categories: &[&str],
+    ) -> Result<notion::models::Database> {
+        let categories_db = self
+            .create_database(
+                parent,
+                "Categories",
+                serde_json::json!({ "Name": { "title": {} } }),
+            )
+            .await?;
+        let Some(notion::models::properties::PropertyConfiguration::Title { id }) =
+            categories_db.properties.get("Name")
+        else {
+            return Err(eyre::eyre!("The categories database has no title property"));
+        };
+        for category in categories {
+            let properties = notion::models::Properties {
+                properties: std::collections::HashMap::from([(
+                    "Name".to_string(),
+                    notion::models::properties::PropertyValue::Title {
+                        id: id.clone(),
+                        title: crate::prompt::rich_text(category),
+                    },
+                )]),
+            };
+            self.create_page(&categories_db.id, properties).await?;
+        }
+
+        self.create_database(
+            parent,
+            "Expenses",
+            serde_json::json!({
+                &names.name: { "title": {} },
+                &names.amount: { "number": { "format": "number" } },
+                &names.date: { "date": {} },
+                &names.category: { "relation": { "database_id": categories_db.id } },
+            }),
+        )
+        .await
+    }
+
+    async fn database(
+        &self,
+        database_id: &notion::ids::DatabaseId,
+    ) -> Result<notion::models::Database> {
+        let request = self.client.get(format!(
+            "https://api.notion.com/v1/databases/{}",
+            database_id
+        ));
+
+        match self.send(request).await? {
+            notion::models::Object::Database { database } => Ok(database),
+            response => Err(notion::Error::UnexpectedResponse { response }.into()),
+        }
+    }
+
+    async fn create_page(
+        &self,
+        database_id: &notion::ids::DatabaseId,
+        properties: notion::models::Properties,
+    ) -> Result<notion::models::Page> {
+        let page = notion::models::PageCreateRequest {
+            parent: notion::models::Parent::Database {
+                database_id: database_id.clone(),
+            },
+            properties,
+        };
+
+        let request = self
+            .client
+            .post("https://api.notion.com/v1/pages")
+            .json(&page);
+
+        match self.send(request).await? {
+            notion::models::Object::Page { page } => Ok(page),
+            response => Err(notion::Error::UnexpectedResponse { response }.into()),
+        }
+    }
+
+    /// A single page of results along with the cursor of the next one.
+    async fn query_database_page(
+        &self,
+        database_id: &notion::ids::DatabaseId,
+        query: &Query,
+    ) -> Result<(
+        Vec<notion::models::Page>,
+        Option<notion::models::paging::PagingCursor>,
+    )> {
+        let request = self
+            .client
+            .post(format!(
+                "https://api.notion.com/v1/databases/{}/query",
+                database_id
+            ))
+            .json(query);
+
+        match self.send(request).await? {
+            notion::models::Object::List { list } => {
+                let pages = list
+                    .results
+                    .into_iter()
+                    .map(|object| match object {
+                        notion::models::Object::Page { page } => Ok(page),
+                        response => Err(notion::Error::UnexpectedResponse { response }.into()),
+                    })
+                    .collect::<Result<_>>()?;
+
+                Ok((pages, list.next_cursor.filter(|_| list.has_more)))
+            }
+            response => Err(notion::Error::UnexpectedResponse { response }.into()),
+        }
+    }
+
+    /// Follows the cursors until `page_size` pages are fetched, or all of them
+    /// when the query has no page size.
+    async fn query_database(
+        &self,
+        database_id: &notion::ids::DatabaseId,
+        query: &Query,
+    ) -> Result<Vec<notion::models::Page>> {
+        let limit = query
+            .paging
+            .as_ref()
+            .and_then(|paging| paging.page_size)
+            .map(usize::from);
+        let mut cursor = query
+            .paging
+            .as_ref()
+            .and_then(|paging| paging.start_cursor.clone());
+
+        let mut query = query.clone();
+        let mut pages = vec![];
+
+        loop {
+            let page_size = limit.map_or(MAX_PAGE_SIZE, |limit| {
+                (limit - pages.len()).min(MAX_PAGE_SIZE)
+            });
+            query.paging = Some(notion::models::paging::Paging {
+                start_cursor: cursor,
+                page_size: Some(page_size as u8),
+            });
+
+            let (mut batch, next_cursor) = self.query_database_page(database_id, &query).await?;
+            pages.append(&mut batch);
+
+            match next_cursor {
+                Some(next) if limit.is_none_or(|limit| pages.len() < limit) => cursor = Some(next),
+                _ => return Ok(pages),
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ExpenseBackend for NotionBackend {
+    async fn schema(&self) -> Result<notion::models::Database> {
+        self.database(&self.database_id).await
+    }
+
+    async fn query_entries(&self, query: Query) -> Result<Vec<notion::models::Page>> {
+        self.query_database(&self.database_id, &query).await
+    }
+
+    async fn categories(
+        &self,
+        database_id: &notion::ids::DatabaseId,
+    ) -> Result<Vec<notion::models::Page>> {
+        self.query_database(database_id, &Query::default()).await
+    }
+
+    async fn create_entry(
+        &self,
+        properties: notion::models::Properties,
+    ) -> Result<notion::models::Page> {
+        self.create_page(&self.database_id, properties).await
+    }
+
+    async fn create_category(
+        &self,
+        database_id: &notion::ids::DatabaseId,
+        name: &str,
+    ) -> Result<notion::models::Page> {
+        let db = self.database(database_id).await?;
+        let (property, id) = db
+            .properties
+            .iter()
+            .find_map(|(property, config)| match config {
+                notion::models::properties::PropertyConfiguration::Title { id } => {
+                    Some((property.clone(), id.clone()))
+                }
+                _ => None,
+            })
+            .ok_or_else(|| eyre::eyre!("The categories database has no title property"))?;
+
+        let properties = notion::models::Properties {
+            properties: std::collections::HashMap::from([(
+                property,
+                notion::models::properties::PropertyValue::Title {
+                    id,
+                    title: crate::prompt::rich_text(name),
+                },
+            )]),
+        };
+
+        self.create_page(database_id, properties).await
+    }
+
+    async fn update_entry(
+        &self,
+        page_id: &notion::ids::PageId,
+        properties: notion::models::Properties,
+    ) -> Result<notion::models::Page> {
+        let request = self
+            .client
+            .patch(format!("https://api.notion.com/v1/pages/{}", page_id))
+            .json(&serde_json::json!({ "properties": properties }));
+
+        match self.send(request).await? {
+            notion::models::Object::Page { page } => Ok(page),
+            response => Err(notion::Error::UnexpectedResponse { response }.into()),
+        }
+    }
+
+    async fn archive_entry(&self, page_id: &notion::ids::PageId) -> Result<()> {
+        let request = self
+            .client
+            .patch(format!("https://api.notion.com/v1/pages/{}", page_id))
+            .json(&serde_json::json!({ "archived": true }));
+
+        self.send(request).await?;
+
+        Ok(())
+    }
+
+    async fn append_content(
+        &self,
+        page_id: &notion::ids::PageId,
+        blocks: Vec<serde_json::Value>,
+    ) -> Result<()> {
+        self.append_blocks(page_id, blocks).await
+    }
+
+    async fn create_document(
+        &self,
+        database_id: &notion::ids::DatabaseId,
+        title: &str,
+        blocks: Vec<serde_json::Value>,
+    ) -> Result<()> {
+        let db = self.database(database_id).await?;
+        let property = db
+            .properties
+            .iter()
+            .find_map(|(property, config)| match config {
+                notion::models::properties::PropertyConfiguration::Title { .. } => {
+                    Some(property.clone())
+                }
+                _ => None,
+            })
+            .ok_or_else(|| eyre::eyre!("The reports database has no title property"))?;
+
+        let request =
+            self.client
+                .post("https://api.notion.com/v1/pages")
+                .json(&serde_json::json!({
+                    "parent": { "database_id": database_id },
+                    "properties": {
+                        property: { "title": crate::report::rich_text(title) },
+                    },
+                    "children": blocks,
+                }));
+
+        send_json(&self.retry, request).await?;
+
+        Ok(())
+    }
+
+    async fn attach_receipt(
+        &self,
+        page: &notion::models::Page,
+        property: &str,
+        path: &std::path::Path,
+    ) -> Result<()> {
+        let id = self.upload_file(path).await?;
+        match page.properties.properties.get(property) {
+            Some(notion::models::properties::PropertyValue::Files { .. }) => {
+                self.attach_file(&page.id, property, &id, &crate::attachment::file_name(path))
+                    .await
+            }
+            _ => {
+                self.embed_file(&page.id, &id, crate::attachment::content_type(path)?)
+                    .await
+            }
+        }
+    }
+
+    async fn copy_database(&self, title: &str) -> Result<(notion::models::Database, Vec<String>)> {
+        let request = self.client.get(format!(
+            "https://api.notion.com/v1/databases/{}",
+            self.database_id
+        ));
+        let db = send_json(&self.retry, request).await?;
+        let parent = match db["parent"]["type"].as_str() {
+            Some("page_id") => serde_json::json!({ "page_id": db["parent"]["page_id"] }),
+            _ => {
+                return Err(eyre::eyre!(
+                    "Only a database inside a page can be copied, move it into one first"
+                ))
+            }
+        };
+        let (properties, skipped) = copied_properties(&db["properties"]);
+
+        let request = self
+            .client
+            .post("https://api.notion.com/v1/databases")
+            .json(&serde_json::json!({
+                "parent": parent,
+                "title": crate::report::rich_text(title),
+                "is_inline": db["is_inline"],
+                "properties": properties,
+            }));
+
+        match self.send(request).await? {
+            notion::models::Object::Database { database } => Ok((database, skipped)),
+            response => Err(notion::Error::UnexpectedResponse { response }.into()),
+        }
+    }
+
+    async fn archive_database(&self, database_id: &notion::ids::DatabaseId) -> Result<()> {
+        let request = self
+            .client
+            .delete(format!("https://api.notion.com/v1/blocks/{}", database_id));
+
+        send_json(&self.retry, request).await?;
+
+        Ok(())
+    }
+}
+
+#[test]
+fn retry_after_test() {
+    let mut headers = reqwest::header::HeaderMap::new();
+    assert_eq!(retry_after(&headers), None);
+
+    headers.insert(
+        reqwest::header::RETRY_AFTER,
+        reqwest::header::HeaderValue::from_static("3"),
+    );
+    assert_eq!(
+        retry_after(&headers),
+        Some(std::time::Duration::from_secs(3))
+    );
+}
+
+#[test]
+fn filter_serialization_test() {
+    let from = notion::chrono::NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+    let to = notion::chrono::NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+
+    let json = serde_json::to_value(Filter::date_range("Date", from, to)).unwrap();
+
+    assert_eq!(
+        json,
+        serde_json::json!({"and": [
+            {"property": "Date", "date": {"on_or_after": "2024-05-01T00:00:00Z"}},
+            {"property": "Date", "date": {"before": "2024-06-01T00:00:00Z"}},
+        ]})
+    );
+
+    let json = serde_json::to_value(Filter::edited_since(midnight(from))).unwrap();
+
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "timestamp": "last_edited_time",
+            "last_edited_time": {"on_or_after": "2024-05-01T00:00:00Z"},
+        })
+    );
+}
+
+#[test]
+fn copied_properties_test() {
+    let properties = serde_json::json!({
+        "Name": {"id": "title", "name": "Name", "type": "title", "title": {}},
+        "Amount": {"id": "a", "name": "Amount", "type": "number", "number": {"format": "euro"}},
+        "Type": {
+            "id": "t",
+            "name": "Type",
+            "type": "select",
+            "select": {"options": [{"id": "1", "name": "Expense", "color": "red"}]},
+        },
+        "Category": {
+            "id": "c",
+            "name": "Category",
+            "type": "relation",
+            "relation": {
+                "database_id": "cats",
+                "synced_property_name": "Expenses",
+                "synced_property_id": "x",
+            },
+        },
+        "State": {"id": "s", "name": "State", "type": "status", "status": {"options": []}},
+    });
+
+    let (copied, skipped) = copied_properties(&properties);
+    assert_eq!(
+        copied,
+        serde_json::json!({
+            "Name": {"title": {}},
+            "Amount": {"number": {"format": "euro"}},
+            "Type": {"select": {"options": [{"name": "Expense", "color": "red"}]}},
+            "Category": {"relation": {"database_id": "cats"}},
+        })
+    );
+    assert_eq!(skipped, ["State"]);
+}
@@ -0,0 +1,253 @@
+use eyre::{eyre, Result};
+use notion::chrono::NaiveDate;
+use serde::Deserialize;
+
+const API_URL: &str = "https://bankaccountdata.gocardless.com/api/v2";
+
+/// Days a card payment can take to be booked, a recorded expense up to this
+/// much earlier is taken to be the same one.
+pub const BOOKING_DAYS: i64 = 3;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Amount {
+    pub amount: String,
+    pub currency: String,
+}
+
+/// A transaction as the bank reports it, with the fields entries are made of.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Transaction {
+    pub booking_date: Option<NaiveDate>,
+    pub value_date: Option<NaiveDate>,
+    pub transaction_amount: Amount,
+    pub creditor_name: Option<String>,
+    pub remittance_information_unstructured: Option<String>,
+    #[serde(default)]
+    pub remittance_information_unstructured_array: Vec<String>,
+}
+
+impl Transaction {
+    /// Who was paid, or the description the bank gave the payment.
+    fn name(&self) -> String {
+        let description = (!self.remittance_information_unstructured_array.is_empty())
+            .then(|| self.remittance_information_unstructured_array.join(" "));
+
+        [
+            self.creditor_name.clone(),
+            self.remittance_information_unstructured.clone(),
+            description,
+        ]
+        .into_iter()
+        .flatten()
+        .map(|name| name.trim().to_string())
+        .find(|name| !name.is_empty())
+        .unwrap_or("Bank transaction".to_string())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Transactions {
+    booked: Vec<Transaction>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TransactionsResponse {
+    transactions: Transactions,
+}
+
+/// An authorization to read the accounts at a bank.
+#[derive(Debug, Deserialize)]
+pub struct Requisition {
+    pub id: String,
+    /// Where the bank is connected.
+    pub link: String,
+    #[serde(default)]
+    pub accounts: Vec<String>,
+}
+
+/// The payments among `transactions` as rows to import, the money received
+/// is left out.
+pub fn rows(transactions: &[Transaction]) -> Result<Vec<crate::import::Row>> {
+    transactions
+        .iter()
+        .filter_map(|transaction| {
+            let amount = &transaction.transaction_amount.amount;
+            let amount = match amount.trim().parse::<rust_decimal::Decimal>() {
+                Ok(amount) => amount,
+                Err(_) => return Some(Err(eyre!("Invalid transaction amount `{}`", amount))),
+            };
+            if !amount.is_sign_negative() || amount.is_zero() {
+                return None;
+            }
+
+            let date = transaction.booking_date.or(transaction.value_date)?;
+            Some(Ok(crate::import::Row {
+                name: transaction.name(),
+                amount: -amount,
+                date,
+            }))
+        })
+        .collect()
+}
+
+/// Whether `page` already records the payment `row`, whatever it's called.
+pub fn is_recorded(
+    row: &crate::import::Row,
+    page: &notion::models::Page,
+    names: &crate::settings::PropertyNames,
+) -> bool {
+    let earliest = row.date - notion::chrono::Duration::days(BOOKING_DAYS);
+
+    crate::report::page_date(page, &names.date)
+        .is_some_and(|date| date >= earliest && date <= row.date)
+        && crate::report::page_amount(page, &names.amount)
+            .is_some_and(|amount| (amount - crate::calc::to_f64(row.amount)).abs() < 0.005)
+}
+
+/// GoCardless Bank Account Data, signed in.
+pub struct Client {
+    http: reqwest::Client,
+    access: String,
+}
+
+async fn send<T: serde::de::DeserializeOwned>(request: reqwest::RequestBuilder) -> Result<T> {
+    let response = request.send().await?;
+    let status = response.status();
+    let body: serde_json::Value = response.json().await?;
+    tracing::debug!(%status, "Got a response from GoCardless");
+
+    if !status.is_success() {
+        return Err(eyre!(
+            "GoCardless: {} {}",
+            body["summary"].as_str().unwrap_or(status.as_str()),
+            body["detail"].as_str().unwrap_or_default()
+        ));
+    }
+
+    Ok(serde_json::from_value(body)?)
+}
+
+impl Client {
+    #[tracing::instrument(skip_all)]
+    pub async fn login(settings: &crate::settings::BankSettings) -> Result<Self> {
+        let secret_key = match &settings.secret_key {
+            Some(secret_key) => secret_key.clone(),
+            None => std::env::var(crate::settings::BANK_SECRET_KEY_VAR).map_err(|_| {
+                eyre!(
+                    "Set `bank.secret_key` or {}",
+                    crate::settings::BANK_SECRET_KEY_VAR
+                )
+            })?,
+        };
+
+        let http = reqwest::Client::new();
+        let token: serde_json::Value = send(http.post(format!("{}/token/new/", API_URL)).json(
+            &serde_json::json!({
+                "secret_id": settings.secret_id,
+                "secret_key": secret_key,
+            }),
+        ))
+        .await?;
+        let access = token["access"]
+            .as_str()
+            .ok_or_else(|| eyre!("GoCardless didn't return an access token"))?
+            .to_string();
+
+        Ok(Self { http, access })
+    }
+
+    /// The booked transactions of `account` since `from`.
+    #[tracing::instrument(skip(self))]
+    pub async fn transactions(&self, account: &str, from: NaiveDate) -> Result<Vec<Transaction>> {
+        let response: TransactionsResponse = send(
+            self.http
+                .get(format!("{}/accounts/{}/transactions/", API_URL, account))
+                .bearer_auth(&self.access)
+                .query(&[("date_from", from.to_string())]),
+        )
+        .await?;
+
+        Ok(response.transactions.booked)
+    }
+
+    /// Starts connecting the bank `institution`, which is done at the link
+    /// returned.
+    pub async fn link(&self, institution: &str) -> Result<Requisition> {
+        send(
+            self.http
+                .post(format!("{}/requisitions/", API_URL))
+                .bearer_auth(&self.access)
+                .json(&serde_json::json!({
+                    "institution_id": institution,
+                    "redirect": "https://gocardless.com",
+                })),
+        )
+        .await
+    }
+
+    pub async fn requisition(&self, id: &str) -> Result<Requisition> {
+        send(
+            self.http
+                .get(format!("{}/requisitions/{}/", API_URL, id))
+                .bearer_auth(&self.access),
+        )
+        .await
+    }
+}
+
+#[test]
+fn rows_test() {
+    let response: TransactionsResponse = serde_json::from_value(serde_json::json!({
+        "transactions": {
+            "booked": [
+                {
+                    "transactionId": "1",
+                    "bookingDate": "2024-05-03",
+                    "valueDate": "2024-05-02",
+                    "transactionAmount": {"amount": "-43.20", "currency": "EUR"},
+                    "creditorName": "GREEN GROCER",
+                },
+                {
+                    "bookingDate": "2024-05-04",
+                    "transactionAmount": {"amount": "1500.00", "currency": "EUR"},
+                    "debtorName": "ACME LTD",
+                },
+                {
+                    "valueDate": "2024-05-05",
+                    "transactionAmount": {"amount": "-2.5", "currency": "EUR"},
+                    "remittanceInformationUnstructuredArray": ["CITY", "TRANSIT"],
+                },
+            ],
+            "pending": [],
+        },
+    }))
+    .unwrap();
+
+    let rows = rows(&response.transactions.booked).unwrap();
+    assert_eq!(
+        rows,
+        [
+            crate::import::Row {
+                name: "GREEN GROCER".to_string(),
+                amount: "43.20".parse().unwrap(),
+                date: NaiveDate::from_ymd_opt(2024, 5, 3).unwrap(),
+            },
+            crate::import::Row {
+                name: "CITY TRANSIT".to_string(),
+                amount: "2.5".parse().unwrap(),
+                date: NaiveDate::from_ymd_opt(2024, 5, 5).unwrap(),
+            },
+        ]
+    );
+
+    let page = crate::report::page(serde_json::json!({
+        "Name": {"id": "title", "type": "title", "title": []},
+        "Amount": {"id": "a", "type": "number", "number": 43.2},
+        "Date": {"id": "d", "type": "date", "date": {"start": "2024-05-01"}},
+    }));
+    let names = crate::settings::PropertyNames::default();
+    assert!(is_recorded(&rows[0], &page, &names));
+    assert!(!is_recorded(&rows[1], &page, &names));
+}
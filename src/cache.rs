@@ -0,0 +1,94 @@
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::path::PathBuf;
+
+#[derive(Serialize, Deserialize)]
+struct CachedCategories {
+    fetched_at: notion::chrono::DateTime<notion::chrono::Utc>,
+    database_id: notion::ids::DatabaseId,
+    pages: Vec<notion::models::Page>,
+}
+
+/// Category pages kept between runs, so they aren't fetched on every start.
+pub struct CategoryCache {
+    path: PathBuf,
+}
+
+impl CategoryCache {
+    pub fn new() -> Result<Self> {
+        Self::named("categories")
+    }
+
+    /// A cache of the pages of another related database, in `<name>.json`.
+    pub fn named(name: &str) -> Result<Self> {
+        let dir = crate::settings::project_dirs()?.cache_dir().to_path_buf();
+        std::fs::create_dir_all(&dir)?;
+
+        Ok(Self {
+            path: dir.join(format!("{}.json", name)),
+        })
+    }
+
+    /// The cached pages of `database_id`, unless they are older than `ttl`.
+    pub fn load(
+        &self,
+        database_id: &notion::ids::DatabaseId,
+        ttl: notion::chrono::Duration,
+    ) -> Option<Vec<notion::models::Page>> {
+        let normalize = |id: &notion::ids::DatabaseId| id.to_string().replace('-', "");
+
+        File::open(&self.path)
+            .ok()
+            .and_then(|file| serde_json::from_reader::<_, CachedCategories>(file).ok())
+            .filter(|cached| normalize(&cached.database_id) == normalize(database_id))
+            .filter(|cached| cached.fetched_at + ttl > notion::chrono::Utc::now())
+            .map(|cached| cached.pages)
+    }
+
+    /// The cached pages of whichever database, however old.
+    pub fn load_any(&self) -> Option<Vec<notion::models::Page>> {
+        File::open(&self.path)
+            .ok()
+            .and_then(|file| serde_json::from_reader::<_, CachedCategories>(file).ok())
+            .map(|cached| cached.pages)
+    }
+
+    pub fn store(
+        &self,
+        database_id: &notion::ids::DatabaseId,
+        pages: &[notion::models::Page],
+    ) -> Result<()> {
+        let cached = CachedCategories {
+            fetched_at: notion::chrono::Utc::now(),
+            database_id: database_id.clone(),
+            pages: pages.to_vec(),
+        };
+        serde_json::to_writer(File::create(&self.path)?, &cached)?;
+
+        Ok(())
+    }
+}
+
+#[test]
+fn category_cache_test() {
+    let cache = CategoryCache {
+        path: std::env::temp_dir().join(format!("categories-{}.json", std::process::id())),
+    };
+    let database_id: notion::ids::DatabaseId = "db".parse().unwrap();
+    let page = crate::report::page(serde_json::json!({}));
+
+    cache
+        .store(&database_id, std::slice::from_ref(&page))
+        .unwrap();
+
+    let hour = notion::chrono::Duration::hours(1);
+    assert_eq!(cache.load(&database_id, hour), Some(vec![page]));
+    assert_eq!(cache.load(&"other".parse().unwrap(), hour), None);
+    assert_eq!(
+        cache.load(&database_id, notion::chrono::Duration::zero()),
+        None
+    );
+
+    std::fs::remove_file(&cache.path).unwrap();
+}
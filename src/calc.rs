@@ -0,0 +1,532 @@
+use eyre::{eyre, Result};
+use rust_decimal::Decimal;
+
+fn too_large() -> eyre::Report {
+    eyre!("The amount is too large")
+}
+
+/// `amount` rounded half away from zero to `places` digits after the point.
+pub fn round(amount: Decimal, places: u32) -> Decimal {
+    amount
+        .round_dp_with_strategy(places, rust_decimal::RoundingStrategy::MidpointAwayFromZero)
+        .normalize()
+}
+
+/// The nearest `f64` to `amount`, for the reports and the settings compared to.
+pub fn to_f64(amount: Decimal) -> f64 {
+    rust_decimal::prelude::ToPrimitive::to_f64(&amount).unwrap_or_default()
+}
+
+/// `amount` rounded to `places` digits after the point, as the Notion API
+/// takes numbers.
+pub fn number(amount: Decimal, places: u32) -> Option<serde_json::Number> {
+    round(amount, places).to_string().parse().ok()
+}
+
+/// Languages writing `1.234,56` rather than `1,234.56`.
+const COMMA_DECIMAL_LANGUAGES: &[&str] = &[
+    "bg", "cs", "da", "de", "el", "es", "et", "fi", "fr", "hr", "hu", "id", "it", "lt", "lv", "nb",
+    "nl", "nn", "no", "pl", "pt", "ro", "ru", "sk", "sl", "sr", "sv", "tr", "uk", "vi",
+];
+
+/// How amounts are written in a locale.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NumberFormat {
+    decimal: char,
+    group: char,
+}
+
+impl NumberFormat {
+    /// The format of `locale`, like `de` or `en_US.UTF-8`.
+    pub fn for_locale(locale: &str) -> Self {
+        let language = locale
+            .split(['-', '_', '.'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+
+        if COMMA_DECIMAL_LANGUAGES.contains(&language.as_str()) {
+            Self {
+                decimal: ',',
+                group: '.',
+            }
+        } else {
+            Self {
+                decimal: '.',
+                group: ',',
+            }
+        }
+    }
+
+    /// `input` with the thousands separators left out and a point for
+    /// decimals. A separator not followed by three digits is kept as it is,
+    /// `calc` then takes it for a decimal point or reports it.
+    pub fn normalize(self, input: &str) -> String {
+        let chars: Vec<char> = input.chars().collect();
+        let digits = |range: std::ops::Range<usize>| {
+            chars
+                .get(range)
+                .is_some_and(|chars| chars.iter().all(char::is_ascii_digit))
+        };
+
+        chars
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &c)| {
+                let grouping = c == self.group
+                    && i > 0
+                    && digits(i - 1..i)
+                    && digits(i + 1..i + 4)
+                    && !digits(i + 4..i + 5);
+                match c {
+                    _ if grouping => None,
+                    _ if c == self.decimal => Some('.'),
+                    _ => Some(c),
+                }
+            })
+            .collect()
+    }
+
+    /// `amount` written the way `normalize` reads it back, without thousands
+    /// separators.
+    pub fn format(self, amount: Decimal) -> String {
+        amount.to_string().replace('.', &self.decimal.to_string())
+    }
+}
+
+/// `input` without the currency it starts or ends with, like `€12.50` or
+/// `12.50 eur`, and the code of that currency. Letters only count as a code
+/// when they're a word of their own.
+pub fn split_currency(
+    input: &str,
+    currencies: &std::collections::HashMap<String, Vec<String>>,
+) -> (String, Option<String>) {
+    let input = input.trim();
+    let boundary = |c: Option<char>| c.is_none_or(|c| !c.is_alphabetic());
+
+    currencies
+        .iter()
+        .flat_map(|(code, symbols)| {
+            std::iter::once(code)
+                .chain(symbols)
+                .map(move |symbol| (code, symbol))
+        })
+        .filter(|(_, symbol)| !symbol.is_empty())
+        .filter_map(|(code, symbol)| {
+            let matches = |part: Option<&str>| {
+                part.is_some_and(|part| part.to_lowercase() == symbol.to_lowercase())
+            };
+            let rest = if matches(input.get(..symbol.len())) {
+                let rest = &input[symbol.len()..];
+                boundary(rest.chars().next()).then_some(rest)
+            } else if matches(
+                input
+                    .len()
+                    .checked_sub(symbol.len())
+                    .and_then(|i| input.get(i..)),
+            ) {
+                let rest = &input[..input.len() - symbol.len()];
+                boundary(rest.chars().next_back()).then_some(rest)
+            } else {
+                None
+            }?;
+            Some((symbol.len(), rest.trim().to_string(), code.clone()))
+        })
+        .max_by_key(|(len, _, _)| *len)
+        .map_or((input.to_string(), None), |(_, rest, code)| {
+            (rest, Some(code))
+        })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token {
+    Number(Decimal),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    LParen,
+    RParen,
+}
+
+/// Amounts an expression can name, like `lunch`, by their names in lowercase.
+/// `_` is the previous entry's amount.
+pub type Variables = std::collections::HashMap<String, Decimal>;
+
+fn tokenize(expression: &str, variables: &Variables) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        let token = match c {
+            _ if c.is_whitespace() => continue,
+            '+' => Token::Plus,
+            '-' => Token::Minus,
+            '*' | '×' => Token::Star,
+            '/' | '÷' => Token::Slash,
+            '%' => Token::Percent,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            _ if c.is_ascii_digit() || matches!(c, '.' | ',') => {
+                let mut number = c.to_string();
+                while let Some((_, c)) =
+                    chars.next_if(|(_, c)| c.is_ascii_digit() || matches!(c, '.' | ','))
+                {
+                    number.push(c);
+                }
+
+                let value = number
+                    .replace(',', ".")
+                    .parse::<Decimal>()
+                    .map_err(|_| eyre!("Invalid number `{}`", number))?
+                    .normalize();
+
+                Token::Number(value)
+            }
+            _ if c.is_alphabetic() || c == '_' => {
+                let mut name = c.to_string();
+                while let Some((_, c)) = chars.next_if(|(_, c)| c.is_alphanumeric() || *c == '_') {
+                    name.push(c);
+                }
+
+                match variables.get(&name.to_lowercase()) {
+                    Some(value) => Token::Number(*value),
+                    None if name == "_" => return Err(eyre!("There's no previous amount for `_`")),
+                    None => return Err(eyre!("Unknown name `{}`", name)),
+                }
+            }
+            _ => return Err(eyre!("Unexpected `{}` at position {}", c, i)),
+        };
+
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+/// Intermediate result that remembers whether it was written as a percentage,
+/// so `100+10%` can mean "100 plus 10% of it".
+#[derive(Debug, Clone, Copy)]
+struct Value {
+    number: Decimal,
+    percent: bool,
+}
+
+impl Value {
+    fn plain(number: Decimal) -> Self {
+        Self {
+            number,
+            percent: false,
+        }
+    }
+
+    fn resolve(self) -> Decimal {
+        if self.percent {
+            self.number / Decimal::ONE_HUNDRED
+        } else {
+            self.number
+        }
+    }
+}
+
+/// Recursive-descent parser over the grammar
+///
+/// ```text
+/// expr   := term (('+' | '-') term)*
+/// term   := unary (('*' | '/') unary)*
+/// unary  := ('+' | '-') unary | atom '%'?
+/// atom   := number | name | '(' expr ')'
+/// ```
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<Token> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek();
+        self.pos += 1;
+        token
+    }
+
+    fn expr(&mut self) -> Result<Value> {
+        let mut lhs = self.term()?;
+
+        while let Some(op @ (Token::Plus | Token::Minus)) = self.peek() {
+            self.next();
+            let rhs = self.term()?;
+            let lhs_number = lhs.resolve();
+
+            let rhs_number = if rhs.percent {
+                lhs_number
+                    .checked_mul(rhs.resolve())
+                    .ok_or_else(too_large)?
+            } else {
+                rhs.number
+            };
+
+            lhs = Value::plain(
+                match op {
+                    Token::Plus => lhs_number.checked_add(rhs_number),
+                    _ => lhs_number.checked_sub(rhs_number),
+                }
+                .ok_or_else(too_large)?,
+            );
+        }
+
+        Ok(lhs)
+    }
+
+    fn term(&mut self) -> Result<Value> {
+        let mut lhs = self.unary()?;
+
+        while let Some(op @ (Token::Star | Token::Slash)) = self.peek() {
+            self.next();
+            let rhs = self.unary()?.resolve();
+
+            lhs = Value::plain(match op {
+                Token::Star => lhs.resolve().checked_mul(rhs).ok_or_else(too_large)?,
+                _ if rhs.is_zero() => return Err(eyre!("Division by zero")),
+                _ => lhs.resolve().checked_div(rhs).ok_or_else(too_large)?,
+            });
+        }
+
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Value> {
+        match self.peek() {
+            Some(Token::Minus) => {
+                self.next();
+                let value = self.unary()?;
+                Ok(Value {
+                    number: -value.number,
+                    ..value
+                })
+            }
+            Some(Token::Plus) => {
+                self.next();
+                self.unary()
+            }
+            _ => {
+                let mut value = self.atom()?;
+                if let Some(Token::Percent) = self.peek() {
+                    self.next();
+                    value.percent = true;
+                }
+                Ok(value)
+            }
+        }
+    }
+
+    fn atom(&mut self) -> Result<Value> {
+        match self.next() {
+            Some(Token::Number(number)) => Ok(Value::plain(number)),
+            Some(Token::LParen) => {
+                let value = self.expr()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(Value::plain(value.resolve())),
+                    _ => Err(eyre!("Missing closing parenthesis")),
+                }
+            }
+            Some(token) => Err(eyre!("Unexpected {:?}", token)),
+            None => Err(eyre!("Unexpected end of expression")),
+        }
+    }
+}
+
+/// Evaluates an amount expression like `(12.5+3)*2` or `100+10%`.
+pub fn calc(expression: &str) -> Result<Decimal> {
+    calc_with(expression, &Variables::new())
+}
+
+/// Like `calc`, with the names in `variables` standing for their amounts, as
+/// in `lunch+2.5` or `_*2`.
+pub fn calc_with(expression: &str, variables: &Variables) -> Result<Decimal> {
+    let mut parser = Parser {
+        tokens: tokenize(expression, variables)?,
+        pos: 0,
+    };
+
+    let value = parser.expr()?;
+
+    match parser.peek() {
+        None => Ok(value.resolve().normalize()),
+        Some(token) => Err(eyre!("Unexpected {:?}", token)),
+    }
+}
+
+#[test]
+fn calc_test() {
+    let result = calc("10+10").unwrap();
+    assert_eq!(result.to_string(), "20");
+
+    let result = calc("10+10+10").unwrap();
+    assert_eq!(result.to_string(), "30");
+
+    let result = calc("10+10.1+10").unwrap();
+    assert_eq!(result.to_string(), "30.1");
+
+    let result = calc("10+10+10-30").unwrap();
+    assert_eq!(result.to_string(), "0");
+
+    let result = calc("10-30+10+10+3").unwrap();
+    assert_eq!(result.to_string(), "3");
+
+    let result = calc("10/2").unwrap();
+    assert_eq!(result.to_string(), "5");
+
+    let result = calc("10*2").unwrap();
+    assert_eq!(result.to_string(), "20");
+
+    let result = calc("10").unwrap();
+    assert_eq!(result.to_string(), "10");
+
+    let result = calc("10.1").unwrap();
+    assert_eq!(result.to_string(), "10.1");
+
+    let result = calc("10+(10*3)").unwrap();
+    assert_eq!(result.to_string(), "40");
+
+    let result = calc("10-10").unwrap();
+    assert_eq!(result.to_string(), "0");
+
+    let result = calc("10*10").unwrap();
+    assert_eq!(result.to_string(), "100");
+
+    let result = calc("10/10").unwrap();
+    assert_eq!(result.to_string(), "1");
+}
+
+#[test]
+fn calc_precedence_test() {
+    assert_eq!(calc("2+3*4").unwrap().to_string(), "14");
+    assert_eq!(calc("(2+3)*4").unwrap().to_string(), "20");
+    assert_eq!(calc("-5+2").unwrap().to_string(), "-3");
+    assert_eq!(calc("2*-3").unwrap().to_string(), "-6");
+    assert_eq!(calc("-(1+2)*2").unwrap().to_string(), "-6");
+    assert_eq!(calc(" 12.5 * 2 ").unwrap().to_string(), "25");
+    assert_eq!(calc("4,5+1").unwrap().to_string(), "5.5");
+}
+
+#[test]
+fn calc_percent_test() {
+    assert_eq!(calc("100+10%").unwrap().to_string(), "110");
+    assert_eq!(calc("200-25%").unwrap().to_string(), "150");
+    assert_eq!(calc("80*50%").unwrap().to_string(), "40");
+    assert_eq!(calc("50%").unwrap().to_string(), "0.5");
+    assert_eq!(calc("(40+60)+10%").unwrap().to_string(), "110");
+}
+
+#[test]
+fn calc_error_test() {
+    assert!(calc("").is_err());
+    assert!(calc("10+").is_err());
+    assert!(calc("(10+2").is_err());
+    assert!(calc("10)").is_err());
+    assert!(calc("10/0").is_err());
+    assert!(calc("10$").is_err());
+}
+
+#[test]
+fn calc_decimal_test() {
+    assert_eq!(calc("0.1+0.2").unwrap().to_string(), "0.3");
+    assert_eq!(calc("1.15*3").unwrap().to_string(), "3.45");
+    assert_eq!(round(calc("10/3").unwrap(), 2).to_string(), "3.33");
+    assert_eq!(round(calc("2.675").unwrap(), 2).to_string(), "2.68");
+    assert_eq!(round(calc("-2.5").unwrap(), 0).to_string(), "-3");
+    assert_eq!(calc("12.50-0.5").unwrap().to_string(), "12");
+    assert!(calc("79228162514264337593543950335*2").is_err());
+
+    assert_eq!(
+        number(calc("4.499+0.001").unwrap(), 2),
+        serde_json::Number::from_f64(4.5)
+    );
+    assert_eq!(
+        number(calc("12.3456").unwrap(), 3),
+        serde_json::Number::from_f64(12.346)
+    );
+}
+
+#[test]
+fn calc_variables_test() {
+    let variables: Variables = [("vat", "1.19"), ("lunch", "12"), ("_", "43.20")]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.parse().unwrap()))
+        .collect();
+
+    assert_eq!(calc_with("_*2", &variables).unwrap().to_string(), "86.4");
+    assert_eq!(
+        calc_with("Lunch+2.5", &variables).unwrap().to_string(),
+        "14.5"
+    );
+    assert_eq!(calc_with("100*vat", &variables).unwrap().to_string(), "119");
+    assert!(calc_with("dinner", &variables).is_err());
+    assert!(calc_with("2lunch", &variables).is_err());
+    assert!(calc("_").is_err());
+}
+
+#[test]
+fn normalize_test() {
+    let en = NumberFormat::for_locale("en_US.UTF-8");
+    let de = NumberFormat::for_locale("de");
+
+    assert_eq!(en.normalize("1,234.56"), "1234.56");
+    assert_eq!(en.normalize("1,234,567+4,5"), "1234567+4,5");
+    assert_eq!(de.normalize("1.234,56"), "1234.56");
+    assert_eq!(de.normalize("30 Food + 13,20 Home"), "30 Food + 13.20 Home");
+
+    assert_eq!(calc(&en.normalize("1,234")).unwrap().to_string(), "1234");
+    assert_eq!(calc(&de.normalize("1.234")).unwrap().to_string(), "1234");
+    assert!(calc(&en.normalize("1.234,56")).is_err());
+    assert!(calc(&de.normalize("1,234.56")).is_err());
+}
+
+#[test]
+fn format_test() {
+    let en = NumberFormat::for_locale("en");
+    let de = NumberFormat::for_locale("de_DE");
+    let amount = Decimal::new(1125, 3);
+
+    assert_eq!(en.format(amount), "1.125");
+    assert_eq!(de.format(amount), "1,125");
+    assert_eq!(calc(&en.normalize(&en.format(amount))).unwrap(), amount);
+    assert_eq!(calc(&de.normalize(&de.format(amount))).unwrap(), amount);
+    assert_eq!(de.format(Decimal::from(1234)), "1234");
+}
+
+#[test]
+fn split_currency_test() {
+    let currencies = [
+        ("EUR".to_string(), vec!["€".to_string()]),
+        ("USD".to_string(), vec!["$".to_string(), "US$".to_string()]),
+    ]
+    .into_iter()
+    .collect();
+
+    assert_eq!(
+        split_currency("€12.50", &currencies),
+        ("12.50".to_string(), Some("EUR".to_string()))
+    );
+    assert_eq!(
+        split_currency(" 12.50 eur", &currencies),
+        ("12.50".to_string(), Some("EUR".to_string()))
+    );
+    assert_eq!(
+        split_currency("US$5+2", &currencies),
+        ("5+2".to_string(), Some("USD".to_string()))
+    );
+    assert_eq!(
+        split_currency("12 euros", &currencies),
+        ("12 euros".to_string(), None)
+    );
+    assert_eq!(
+        split_currency("12.50", &currencies),
+        ("12.50".to_string(), None)
+    );
+}
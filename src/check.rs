@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+
+/// Entries with values missing or off, and categories no entry uses.
+#[derive(Debug, Default)]
+pub struct Problems<'a> {
+    pub missing_category: Vec<&'a notion::models::Page>,
+    pub missing_date: Vec<&'a notion::models::Page>,
+    pub missing_amount: Vec<&'a notion::models::Page>,
+    /// Amounts of zero or less, other than refunds linked to their expense.
+    pub non_positive: Vec<&'a notion::models::Page>,
+    pub unused_categories: Vec<String>,
+}
+
+impl<'a> Problems<'a> {
+    /// `categories` are the names of all the categories, `None` when the
+    /// database has no category property.
+    pub fn new(
+        entries: &'a [notion::models::Page],
+        names: &HashMap<&notion::ids::PageId, String>,
+        categories: Option<&[String]>,
+        properties: &crate::settings::PropertyNames,
+    ) -> Self {
+        let mut problems = Self::default();
+        let mut used = vec![];
+
+        for entry in entries {
+            if categories.is_some() {
+                match crate::report::page_category(entry, &properties.category, names) {
+                    Some(category) => used.push(category),
+                    None => problems.missing_category.push(entry),
+                }
+            }
+            if crate::report::page_date(entry, &properties.date).is_none() {
+                problems.missing_date.push(entry);
+            }
+            match crate::report::page_amount(entry, &properties.amount) {
+                None => problems.missing_amount.push(entry),
+                Some(amount)
+                    if amount <= 0.0
+                        && crate::report::page_relation_ids(entry, &properties.refund_of)
+                            .is_empty() =>
+                {
+                    problems.non_positive.push(entry)
+                }
+                Some(_) => {}
+            }
+        }
+
+        problems.unused_categories = categories
+            .unwrap_or_default()
+            .iter()
+            .filter(|category| !used.contains(category))
+            .cloned()
+            .collect();
+        problems.unused_categories.sort();
+
+        problems
+    }
+
+    /// The lists of entries with a heading for each.
+    pub fn sections(&self) -> [(&'static str, &[&'a notion::models::Page]); 4] {
+        [
+            ("Missing a category", &self.missing_category),
+            ("Missing a date", &self.missing_date),
+            ("Missing an amount", &self.missing_amount),
+            ("Zero or negative amounts", &self.non_positive),
+        ]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sections().iter().all(|(_, pages)| pages.is_empty())
+            && self.unused_categories.is_empty()
+    }
+}
+
+#[test]
+fn problems_test() {
+    let entry = |id: &str, properties: serde_json::Value| {
+        let mut page = crate::report::page(properties);
+        page.id = id.parse().unwrap();
+        page
+    };
+    let category = |name: &str| serde_json::json!({"id": "c", "type": "select", "select": {"name": name, "color": "default"}});
+    let amount = |amount: f64| serde_json::json!({"id": "a", "type": "number", "number": amount});
+    let date = serde_json::json!({"id": "d", "type": "date", "date": {"start": "2024-05-02"}});
+
+    let entries = [
+        entry(
+            "a",
+            serde_json::json!({"Category": category("Food"), "Amount": amount(4.5), "Date": date}),
+        ),
+        entry(
+            "b",
+            serde_json::json!({"Amount": amount(4.5), "Date": date}),
+        ),
+        entry(
+            "c",
+            serde_json::json!({"Category": category("Food"), "Amount": amount(0.0)}),
+        ),
+        entry(
+            "d",
+            serde_json::json!({"Category": category("Food"), "Date": date}),
+        ),
+        entry(
+            "e",
+            serde_json::json!({
+                "Category": category("Food"),
+                "Amount": amount(-4.5),
+                "Date": date,
+                "Refund of": {"id": "r", "type": "relation", "relation": [{"id": "a"}]},
+            }),
+        ),
+    ];
+    let categories = ["Food".to_string(), "Travel".to_string()];
+    let problems = Problems::new(
+        &entries,
+        &HashMap::new(),
+        Some(&categories),
+        &crate::settings::PropertyNames::default(),
+    );
+
+    let ids = |pages: &[&notion::models::Page]| -> Vec<String> {
+        pages.iter().map(|page| page.id.to_string()).collect()
+    };
+    assert_eq!(ids(&problems.missing_category), ["b"]);
+    assert_eq!(ids(&problems.missing_date), ["c"]);
+    assert_eq!(ids(&problems.missing_amount), ["d"]);
+    assert_eq!(ids(&problems.non_positive), ["c"]);
+    assert_eq!(problems.unused_categories, ["Travel"]);
+}
@@ -0,0 +1,451 @@
+use clap::{Args, Parser, Subcommand};
+use clap_complete::ArgValueCandidates;
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Track expenses in a Notion database")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    #[command(flatten)]
+    pub global: GlobalArgs,
+}
+
+/// Options every subcommand accepts.
+#[derive(Debug, Default, Clone, Args)]
+pub struct GlobalArgs {
+    /// Fetch the categories from Notion instead of the local cache
+    #[arg(long, global = true)]
+    pub refresh: bool,
+    /// Config file to use instead of the default one
+    #[arg(long, global = true)]
+    pub config: Option<std::path::PathBuf>,
+    /// Profile from the `profiles` section of the config to use
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+    /// Database from `notion.databases` to use, like `2024`
+    #[arg(long, global = true)]
+    pub database: Option<String>,
+    /// Only read, refusing anything that would change the database or the
+    /// config
+    #[arg(long, global = true)]
+    pub read_only: bool,
+    /// Work on the test database `notion.sandbox`, with a journal of its own
+    #[arg(long, global = true, conflicts_with = "database")]
+    pub sandbox: bool,
+    /// How many recent entries to show, instead of `display.recent_count`
+    #[arg(long, global = true)]
+    pub recent: Option<u8>,
+    /// Print JSON instead of text from `list`, `report` and `add`
+    #[arg(long, global = true)]
+    pub json: bool,
+    /// Log the Notion requests to stderr, -vv for their responses too
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    pub verbose: u8,
+    /// Use the local copy kept by `sync` instead of Notion, new entries are
+    /// uploaded by the next `sync`
+    #[arg(long, global = true)]
+    pub offline: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Add new expenses, prompting for anything not given as a flag (default)
+    Add {
+        /// The whole entry in one line, like "coffee 4.5 yesterday #food"
+        line: Option<String>,
+        /// Open the created entries in the browser and copy their links
+        #[arg(long)]
+        open: bool,
+        /// Photo of a receipt to read the merchant, total and date off, and
+        /// attach
+        #[arg(long, value_name = "PATH", value_hint = clap::ValueHint::FilePath)]
+        from_receipt: Option<std::path::PathBuf>,
+        /// Read the name and amount off the copied text, like a bank's
+        /// notification
+        #[arg(long, conflicts_with = "line")]
+        from_clipboard: bool,
+        #[command(flatten)]
+        args: Box<AddArgs>,
+    },
+    /// Show the most recent expenses, optionally filtered
+    List(ListArgs),
+    /// Summarize the expenses (defaults to `report month`)
+    Report {
+        #[command(subcommand)]
+        command: Option<ReportCommand>,
+        #[arg(short, long, value_enum, global = true, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+        /// File to write the report to instead of printing it
+        #[arg(short, long, global = true)]
+        output: Option<std::path::PathBuf>,
+        /// Mail the report to the addresses in the `email` section too
+        #[arg(long, global = true)]
+        email: bool,
+    },
+    /// Enter many entries, review them, then upload them at once
+    Batch,
+    /// Create entries from a file
+    Import {
+        #[command(subcommand)]
+        command: ImportCommand,
+    },
+    /// Find receipts in a mailbox and create entries from them
+    Ingest {
+        #[command(subcommand)]
+        command: IngestCommand,
+    },
+    /// Check the categorization rules from the `rules` section
+    Rules {
+        #[command(subcommand)]
+        command: RulesCommand,
+    },
+    /// Book the monthly expenses from the `recurring` section
+    Recurring {
+        #[command(subcommand)]
+        command: RecurringCommand,
+    },
+    /// Write every entry to a file
+    Export {
+        /// File to write to
+        output: std::path::PathBuf,
+        #[arg(short, long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+    },
+    /// Change the most recent entry
+    Edit,
+    /// Archive the most recently created entry
+    Undo,
+    /// Add the most recently created entry again, dated today
+    #[command(visible_alias = "repeat-last")]
+    Again,
+    /// Look for entries that were entered twice and archive the ones picked
+    Dedupe,
+    /// Add a refund of a recent expense, linked to it
+    Refund {
+        /// Refunded amount, the whole expense by default
+        #[arg(long)]
+        amount: Option<String>,
+    },
+    /// Report entries with a missing or non-positive value and unused categories
+    Check {
+        /// Pick a category for each entry missing one
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Upload entries that were saved while offline and update the local copy
+    /// used by `--offline`
+    Sync {
+        /// Download every entry again, dropping the ones deleted in Notion
+        #[arg(long)]
+        full: bool,
+    },
+    /// Create the expense database for a new year, with the same properties and
+    /// no entries, and switch the config to it
+    Rollover {
+        /// Title of the new database, by default the old one with the next year
+        #[arg(long)]
+        title: Option<String>,
+        /// Move the old database to the trash
+        #[arg(long)]
+        archive: bool,
+    },
+    /// Show the config file location and the predefined expenses
+    Config {
+        #[command(subcommand)]
+        command: Option<ConfigCommand>,
+    },
+    /// Connect to Notion and write a new config file
+    Init {
+        /// Create the expense database and a Categories one in a page, instead
+        /// of picking an existing database
+        #[arg(long)]
+        create_database: bool,
+    },
+    /// Check the config, the Notion connection and the database schema
+    Doctor,
+    /// Stay running and add an entry for every line written to a named pipe,
+    /// like `echo "coffee 3.5" > ~/.cache/notion/pipe`
+    #[cfg(unix)]
+    Daemon {
+        /// Pipe to create and read, instead of `pipe` in the cache directory
+        #[arg(long)]
+        pipe: Option<std::path::PathBuf>,
+    },
+    /// Send last week's total, top categories and biggest expenses to
+    /// `notify.url`, like from a weekly cron job
+    Digest {
+        /// Mail it to the addresses in the `email` section too
+        #[arg(long)]
+        email: bool,
+    },
+    /// Browse the month's entries and totals, and add entries, in a dashboard
+    Tui,
+    /// Print the script that sets up tab completion, like
+    /// `source <(notion-bot completions bash)`
+    Completions { shell: Shell },
+    /// Serve an HTTP API for adding and listing expenses
+    Serve {
+        /// Address to listen on, instead of `server.listen`
+        #[arg(long)]
+        listen: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Args, serde::Deserialize)]
+pub struct ListArgs {
+    /// How many entries to show, defaults to `display.recent_count`
+    #[arg(short, long)]
+    pub limit: Option<u8>,
+    /// Only entries on or after this date (YYYY-MM-DD)
+    #[arg(long)]
+    pub from: Option<notion::chrono::NaiveDate>,
+    /// Only entries on or before this date (YYYY-MM-DD)
+    #[arg(long)]
+    pub to: Option<notion::chrono::NaiveDate>,
+    /// Only entries of this category
+    #[arg(long, add = ArgValueCandidates::new(crate::completion::categories))]
+    pub category: Option<String>,
+    /// Only entries whose name contains this text
+    #[arg(long)]
+    pub contains: Option<String>,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum ReportCommand {
+    /// Totals per category for a month
+    Month {
+        /// Month to report on (YYYY-MM), defaults to the current one
+        #[arg(value_parser = parse_month)]
+        month: Option<notion::chrono::NaiveDate>,
+        /// Add the report to the page or database set as `notion.reports` too
+        #[arg(long)]
+        to_notion: bool,
+    },
+    /// Expenses that recur monthly, with their yearly cost and price changes
+    Subscriptions,
+    /// A year's totals per category and month, and its most expensive entries
+    Year {
+        /// Year to report on, defaults to the current one
+        year: Option<i32>,
+    },
+    /// This month's and week's totals per category next to the last ones
+    Compare,
+    /// Total spending per month, charted, with the trend of some categories
+    Trend {
+        /// How many months to show, up to the current one
+        #[arg(long, default_value_t = 12, value_parser = clap::value_parser!(u32).range(1..))]
+        months: u32,
+        /// Category to show the trend of too, can be repeated
+        #[arg(long, add = ArgValueCandidates::new(crate::completion::categories))]
+        category: Vec<String>,
+    },
+    /// Reimbursable entries that weren't paid back yet, by project
+    Reimbursable,
+    /// Total and daily expenses of a trip
+    Trip {
+        /// Title of the trip page
+        name: String,
+    },
+    /// Who owes whom for a month's expenses, split by the configured `shares`
+    Settle {
+        /// Month to settle (YYYY-MM), defaults to the current one
+        #[arg(value_parser = parse_month)]
+        month: Option<notion::chrono::NaiveDate>,
+    },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum RecurringCommand {
+    /// Create the entries due this month that aren't in Notion yet
+    Run,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum ConfigCommand {
+    /// Store the Notion API key in the system keychain
+    SetKey,
+    /// Edit the predefined expenses, the categories preselected by title
+    Map {
+        #[command(subcommand)]
+        command: MapCommand,
+    },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum MapCommand {
+    /// Preselect CATEGORY for entries titled TITLE
+    Add {
+        #[arg(add = ArgValueCandidates::new(crate::completion::titles))]
+        title: String,
+        #[arg(add = ArgValueCandidates::new(crate::completion::categories))]
+        category: String,
+    },
+    /// Show the predefined expenses
+    List,
+    /// Stop preselecting a category for TITLE
+    Remove {
+        #[arg(add = ArgValueCandidates::new(crate::completion::titles))]
+        title: String,
+    },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum ImportCommand {
+    /// Bank statement export, the columns are configured under `csv`
+    Csv {
+        /// Path to the CSV file
+        path: std::path::PathBuf,
+    },
+    /// Recent payments from the accounts under `bank`
+    Bank {
+        /// How many days back to look, `bank.days` by default
+        #[arg(long)]
+        days: Option<u32>,
+        /// Connect the bank with this institution ID, like `REVOLUT_REVOLT21`,
+        /// and print the IDs of its accounts
+        #[arg(long, value_name = "INSTITUTION")]
+        link: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum IngestCommand {
+    /// The folder and rules are configured under `imap`
+    Imap {
+        /// How many days back to look, `imap.days` by default
+        #[arg(long)]
+        days: Option<u32>,
+    },
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum RulesCommand {
+    /// Show which rule fires for an entry titled TITLE
+    Test {
+        #[arg(add = ArgValueCandidates::new(crate::completion::titles))]
+        title: String,
+        #[arg(long)]
+        amount: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum ReportFormat {
+    Text,
+    /// GitHub-flavored markdown, with the tables as markdown tables
+    Markdown,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    /// A ledger-cli journal, booked to the accounts under `ledger`
+    Ledger,
+}
+
+pub fn parse_month(value: &str) -> Result<notion::chrono::NaiveDate, String> {
+    notion::chrono::NaiveDate::parse_from_str(&format!("{}-01", value), "%Y-%m-%d")
+        .map_err(|_| format!("expected a month like 2024-05, got `{}`", value))
+}
+
+/// Pre-filled values for a new entry; whatever is missing is prompted for.
+#[derive(Debug, Default, Clone, PartialEq, Args, serde::Serialize, serde::Deserialize)]
+pub struct AddArgs {
+    /// Title of the expense
+    #[arg(long, add = ArgValueCandidates::new(crate::completion::titles))]
+    pub name: Option<String>,
+    /// Amount, may be an expression like `10+2.5`, a name from `variables` or `_`
+    /// for the previous amount
+    #[arg(long, allow_hyphen_values = true)]
+    pub amount: Option<String>,
+    /// Date of the expense (YYYY-MM-DD)
+    #[arg(long)]
+    pub date: Option<notion::chrono::NaiveDate>,
+    /// Name of the category page
+    #[arg(long, add = ArgValueCandidates::new(crate::completion::categories))]
+    pub category: Option<String>,
+    /// Expense, Income or Transfer, when the database has a Type property
+    #[arg(long = "type")]
+    #[serde(alias = "type")]
+    pub entry_type: Option<String>,
+    /// Description, when the database has a Notes property
+    #[arg(long)]
+    pub notes: Option<String>,
+    /// Tag to add, may be repeated; unknown tags are created
+    #[arg(long = "tag")]
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Who paid, when the database has a Payer property
+    #[arg(long)]
+    pub payer: Option<String>,
+    /// Mark it as paid back later, when the database has a Reimbursable property
+    #[arg(long)]
+    #[serde(default)]
+    pub reimbursable: bool,
+    /// Project or employer that pays it back, implies `--reimbursable`; unknown
+    /// ones are created
+    #[arg(long)]
+    pub project: Option<String>,
+    /// Name of the trip page, when the database has a Trip relation
+    #[arg(long)]
+    pub trip: Option<String>,
+    /// Image or PDF of the receipt to upload to the page
+    #[arg(long, value_hint = clap::ValueHint::FilePath)]
+    #[serde(skip)]
+    pub attach: Option<std::path::PathBuf>,
+}
+
+impl AddArgs {
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.amount.is_none()
+            && self.date.is_none()
+            && self.category.is_none()
+            && self.entry_type.is_none()
+            && self.notes.is_none()
+            && self.tags.is_empty()
+            && self.payer.is_none()
+            && !self.reimbursable
+            && self.project.is_none()
+            && self.trip.is_none()
+            && self.attach.is_none()
+    }
+
+    /// These values, with the missing ones taken from `other`.
+    pub fn or(self, other: Self) -> Self {
+        Self {
+            name: self.name.or(other.name),
+            amount: self.amount.or(other.amount),
+            date: self.date.or(other.date),
+            category: self.category.or(other.category),
+            entry_type: self.entry_type.or(other.entry_type),
+            notes: self.notes.or(other.notes),
+            tags: if self.tags.is_empty() {
+                other.tags
+            } else {
+                self.tags
+            },
+            payer: self.payer.or(other.payer),
+            reimbursable: self.reimbursable || other.reimbursable,
+            project: self.project.or(other.project),
+            trip: self.trip.or(other.trip),
+            attach: self.attach.or(other.attach),
+        }
+    }
+
+    /// Whether nothing is left to prompt for.
+    pub fn is_complete(&self) -> bool {
+        self.name.is_some()
+            && self.amount.is_some()
+            && self.date.is_some()
+            && self.category.is_some()
+    }
+}
@@ -0,0 +1,64 @@
+use clap_complete::env::EnvCompleter;
+use clap_complete::CompletionCandidate;
+use eyre::Result;
+use std::collections::HashMap;
+
+/// The parts of the config completions use. It's read as it is, without the
+/// profiles or the API key, since completing has to be quick.
+#[derive(Default, serde::Deserialize)]
+struct CompletionConfig {
+    #[serde(default)]
+    map: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    budgets: HashMap<String, f64>,
+}
+
+fn config() -> CompletionConfig {
+    crate::settings::Settings::config_path(None)
+        .ok()
+        .and_then(|path| std::fs::File::open(path).ok())
+        .and_then(|file| serde_yaml::from_reader(file).ok())
+        .unwrap_or_default()
+}
+
+fn candidates(mut names: Vec<String>) -> Vec<CompletionCandidate> {
+    names.sort();
+    names.dedup();
+    names.into_iter().map(CompletionCandidate::new).collect()
+}
+
+/// Category names from the category cache and the config, for `--category`.
+pub fn categories() -> Vec<CompletionCandidate> {
+    let config = config();
+    let cached = crate::cache::CategoryCache::new()
+        .ok()
+        .and_then(|cache| cache.load_any())
+        .unwrap_or_default();
+
+    candidates(
+        cached
+            .iter()
+            .filter_map(|page| page.title())
+            .chain(config.map.into_keys())
+            .chain(config.budgets.into_keys())
+            .collect(),
+    )
+}
+
+/// The titles predefined under `map`, for `--name`.
+pub fn titles() -> Vec<CompletionCandidate> {
+    candidates(config().map.into_values().flatten().collect())
+}
+
+/// Writes the script that has `shell` ask the binary for completions.
+pub fn write_registration(shell: crate::cli::Shell, writer: &mut dyn std::io::Write) -> Result<()> {
+    let completer: &dyn EnvCompleter = match shell {
+        crate::cli::Shell::Bash => &clap_complete::env::Bash,
+        crate::cli::Shell::Zsh => &clap_complete::env::Zsh,
+        crate::cli::Shell::Fish => &clap_complete::env::Fish,
+    };
+    let bin = env!("CARGO_PKG_NAME");
+    completer.write_registration("COMPLETE", bin, bin, bin, writer)?;
+
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use eyre::{eyre, Result};
+use std::os::unix::fs::FileTypeExt;
+use tokio::io::AsyncBufReadExt;
+
+/// Creates the named pipe at `path`, or reuses the one left there.
+fn create_pipe(path: &std::path::Path) -> Result<()> {
+    match std::fs::metadata(path) {
+        Ok(metadata) if metadata.file_type().is_fifo() => Ok(()),
+        Ok(_) => Err(eyre!("{} exists and isn't a pipe", path.display())),
+        Err(_) => {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            nix::unistd::mkfifo(
+                path,
+                nix::sys::stat::Mode::S_IRUSR | nix::sys::stat::Mode::S_IWUSR,
+            )?;
+            Ok(())
+        }
+    }
+}
+
+/// Keeps `app` loaded and adds an entry for every line written to the pipe at
+/// `path`, parsed like `add "<line>"`, until Ctrl+C. Lines that can't be added
+/// are reported and skipped.
+pub async fn run(mut app: crate::app::App, path: &std::path::Path) -> Result<()> {
+    let (db, _) = app.prepare().await?;
+    create_pipe(path)?;
+
+    // Opened for writing too, so the pipe stays open between writers.
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)?;
+    let mut lines =
+        tokio::io::BufReader::new(tokio::net::unix::pipe::Receiver::from_file(file)?).lines();
+    println!("Reading entries from {}", path.display());
+
+    loop {
+        let line = tokio::select! {
+            line = lines.next_line() => line?,
+            _ = tokio::signal::ctrl_c() => break,
+        };
+        let Some(line) = line.filter(|line| !line.trim().is_empty()) else {
+            continue;
+        };
+
+        let today = notion::chrono::offset::Local::now().date_naive();
+        match app
+            .add_entry(&db, crate::quick::parse_line(&line, today))
+            .await
+        {
+            Ok(_) => println!("Added {}", line.trim()),
+            Err(err) => eprintln!("Couldn't add `{}`: {}", line.trim(), err),
+        }
+    }
+
+    std::fs::remove_file(path)?;
+
+    Ok(())
+}
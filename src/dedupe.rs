@@ -0,0 +1,111 @@
+use fuzzy_matcher::FuzzyMatcher;
+
+/// `title` lowercased with only its letters and digits.
+fn normalize(title: &str) -> String {
+    title
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Whether the shorter title is a fuzzy match within the longer one, and at
+/// least half as long, so "Coffe" matches "Coffee" but "Bus" not "Business
+/// lunch".
+fn similar(a: &str, b: &str) -> bool {
+    let (a, b) = (normalize(a), normalize(b));
+    let (shorter, longer) = match a.chars().count() <= b.chars().count() {
+        true => (a, b),
+        false => (b, a),
+    };
+    if shorter.chars().count() * 2 < longer.chars().count() {
+        return false;
+    }
+
+    shorter == longer
+        || fuzzy_matcher::skim::SkimMatcherV2::default()
+            .fuzzy_match(&longer, &shorter)
+            .is_some()
+}
+
+/// Entries that look like the same expense entered more than once: the same
+/// date and amount, and similar titles. Each group is ordered by when its
+/// entries were created, so the first is the original.
+pub fn groups<'a>(
+    entries: &'a [notion::models::Page],
+    properties: &crate::settings::PropertyNames,
+) -> Vec<Vec<&'a notion::models::Page>> {
+    let mut groups: Vec<(notion::chrono::NaiveDate, i64, Vec<&notion::models::Page>)> = vec![];
+    for entry in entries {
+        let (Some(date), Some(amount)) = (
+            crate::report::page_date(entry, &properties.date),
+            crate::report::page_amount(entry, &properties.amount),
+        ) else {
+            continue;
+        };
+        let cents = (amount * 100.0).round() as i64;
+        let title = entry.title().unwrap_or_default();
+
+        let group = groups.iter_mut().find(|(group_date, group_cents, pages)| {
+            *group_date == date
+                && *group_cents == cents
+                && similar(&pages[0].title().unwrap_or_default(), &title)
+        });
+        match group {
+            Some((_, _, pages)) => pages.push(entry),
+            None => groups.push((date, cents, vec![entry])),
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter(|(_, _, pages)| pages.len() > 1)
+        .map(|(_, _, mut pages)| {
+            pages.sort_by_key(|page| page.created_time);
+            pages
+        })
+        .collect()
+}
+
+#[test]
+fn groups_test() {
+    let entry = |id: &str, created: &str, name: &str, date: &str, amount: f64| {
+        let mut page = crate::report::page(serde_json::json!({
+            "Name": {"id": "title", "type": "title", "title": [
+                {"type": "text", "plain_text": name, "text": {"content": name, "link": null}}
+            ]},
+            "Amount": {"id": "a", "type": "number", "number": amount},
+            "Date": {"id": "d", "type": "date", "date": {"start": date}},
+        }));
+        page.id = id.parse().unwrap();
+        page.created_time = created.parse().unwrap();
+        page
+    };
+
+    let entries = [
+        entry(
+            "b",
+            "2024-05-02T10:00:00Z",
+            "Groceries!",
+            "2024-05-02",
+            43.2,
+        ),
+        entry("a", "2024-05-02T09:00:00Z", "groceries", "2024-05-02", 43.2),
+        entry("c", "2024-05-02T11:00:00Z", "Grocries", "2024-05-02", 43.2),
+        entry("d", "2024-05-02T12:00:00Z", "Groceries", "2024-05-03", 43.2),
+        entry("e", "2024-05-02T12:00:00Z", "Groceries", "2024-05-02", 12.0),
+        entry("f", "2024-05-04T12:00:00Z", "Bus", "2024-05-04", 3.0),
+        entry(
+            "g",
+            "2024-05-04T12:00:00Z",
+            "Business lunch",
+            "2024-05-04",
+            3.0,
+        ),
+    ];
+    let groups = groups(&entries, &crate::settings::PropertyNames::default());
+
+    assert_eq!(groups.len(), 1);
+    let ids: Vec<String> = groups[0].iter().map(|page| page.id.to_string()).collect();
+    assert_eq!(ids, ["a", "b", "c"]);
+}
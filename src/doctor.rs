@@ -0,0 +1,154 @@
+use notion::models::properties::PropertyConfiguration;
+
+fn kind(config: &PropertyConfiguration) -> &'static str {
+    match config {
+        PropertyConfiguration::Title { .. } => "title",
+        PropertyConfiguration::Number { .. } => "number",
+        PropertyConfiguration::Date { .. } => "date",
+        PropertyConfiguration::Relation { .. } => "relation",
+        PropertyConfiguration::Select { .. } => "select",
+        _ => "other",
+    }
+}
+
+/// The properties every expense database needs, by their key under
+/// `properties`, with the types they may have.
+fn expected(
+    names: &crate::settings::PropertyNames,
+) -> [(&'static str, &String, &'static [&'static str]); 4] {
+    [
+        ("name", &names.name, &["title"]),
+        ("amount", &names.amount, &["number"]),
+        ("date", &names.date, &["date"]),
+        ("category", &names.category, &["relation", "select"]),
+    ]
+}
+
+/// What's wrong with the expense database schema, one message per property.
+pub fn schema_problems(
+    db: &notion::models::Database,
+    names: &crate::settings::PropertyNames,
+) -> Vec<String> {
+    expected(names)
+        .into_iter()
+        .filter_map(|(_, name, kinds)| match db.properties.get(name) {
+            None => Some(format!(
+                "`{}` property is missing, add it or rename it under `properties` in the config",
+                name
+            )),
+            Some(config) if !kinds.contains(&kind(config)) => Some(format!(
+                "`{}` property should be a {}",
+                name,
+                kinds.join(" or ")
+            )),
+            Some(_) => None,
+        })
+        .collect()
+}
+
+/// A needed property missing from the database, with the properties of its
+/// type no setting names, which it was likely renamed to.
+#[derive(Debug, PartialEq)]
+pub struct Drift {
+    /// Its key under `properties`.
+    pub setting: &'static str,
+    pub name: String,
+    /// Ones differing only in case first.
+    pub candidates: Vec<String>,
+}
+
+pub fn schema_drift(
+    db: &notion::models::Database,
+    names: &crate::settings::PropertyNames,
+) -> Vec<Drift> {
+    expected(names)
+        .into_iter()
+        .filter(|(_, name, _)| !db.properties.contains_key(*name))
+        .map(|(setting, name, kinds)| {
+            let mut candidates: Vec<String> = db
+                .properties
+                .iter()
+                .filter(|(property, config)| {
+                    kinds.contains(&kind(config)) && !names.contains(property)
+                })
+                .map(|(property, _)| property.clone())
+                .collect();
+            candidates
+                .sort_by_key(|property| (!property.eq_ignore_ascii_case(name), property.clone()));
+
+            Drift {
+                setting,
+                name: name.clone(),
+                candidates,
+            }
+        })
+        .collect()
+}
+
+/// A hint on how to fix a failed Notion request.
+pub fn advice(err: &eyre::Report) -> Option<&'static str> {
+    use notion::models::error::ErrorCode;
+
+    match err.downcast_ref::<notion::Error>() {
+        Some(notion::Error::ApiError { error }) => match error.code {
+            ErrorCode::Unauthorized => Some("the API key is invalid, run `notion-bot init`"),
+            ErrorCode::ObjectNotFound | ErrorCode::RestrictedResource => {
+                Some("share the database with the integration, or check `database_id`")
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[test]
+fn schema_problems_test() {
+    let db: notion::models::Database = serde_json::from_value(serde_json::json!({
+        "id": "db",
+        "created_time": "2024-05-01T00:00:00Z",
+        "last_edited_time": "2024-05-01T00:00:00Z",
+        "title": [],
+        "properties": {
+            "Name": {"id": "title", "type": "title", "title": {}},
+            "Amount": {"id": "a", "type": "rich_text", "rich_text": {}},
+            "Category": {"id": "c", "type": "select", "select": {"options": []}},
+        },
+    }))
+    .unwrap();
+
+    assert_eq!(
+        schema_problems(&db, &crate::settings::PropertyNames::default()),
+        vec![
+            "`Amount` property should be a number",
+            "`Date` property is missing, add it or rename it under `properties` in the config",
+        ]
+    );
+}
+
+#[test]
+fn schema_drift_test() {
+    let db: notion::models::Database = serde_json::from_value(serde_json::json!({
+        "id": "db",
+        "created_time": "2024-05-01T00:00:00Z",
+        "last_edited_time": "2024-05-01T00:00:00Z",
+        "title": [],
+        "properties": {
+            "Name": {"id": "title", "type": "title", "title": {}},
+            "Cost": {"id": "a", "type": "number", "number": {"format": "number"}},
+            "amount": {"id": "b", "type": "number", "number": {"format": "number"}},
+            "Date": {"id": "d", "type": "date", "date": {}},
+            "Category": {"id": "c", "type": "select", "select": {"options": []}},
+            "Notes": {"id": "n", "type": "rich_text", "rich_text": {}},
+        },
+    }))
+    .unwrap();
+
+    assert_eq!(
+        schema_drift(&db, &crate::settings::PropertyNames::default()),
+        [Drift {
+            setting: "amount",
+            name: "Amount".to_string(),
+            candidates: vec!["amount".to_string(), "Cost".to_string()],
+        }]
+    );
+}
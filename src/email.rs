@@ -0,0 +1,66 @@
+use crate::settings::{EmailSettings, SmtpSecurity};
+use eyre::Result;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
+
+/// `text` without the escape codes that color it in a terminal.
+fn strip_colors(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            // Up to the letter ending the sequence, like the `m` of `\e[31m`.
+            chars.by_ref().find(|c| c.is_ascii_alphabetic());
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
+
+/// Mails `body` as plain text to everyone in `settings.to`.
+#[tracing::instrument(skip_all, fields(subject))]
+pub async fn send(settings: &EmailSettings, subject: &str, body: &str) -> Result<()> {
+    let mut message = lettre::Message::builder()
+        .from(settings.from.parse()?)
+        .subject(subject)
+        .header(lettre::message::header::ContentType::TEXT_PLAIN);
+    for to in &settings.to {
+        message = message.to(to.parse()?);
+    }
+    let message = message.body(strip_colors(body))?;
+
+    let mut transport = match settings.security {
+        SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&settings.host)?,
+        SmtpSecurity::Starttls => {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&settings.host)?
+        }
+        SmtpSecurity::None => {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&settings.host)
+        }
+    };
+    if let Some(port) = settings.port {
+        transport = transport.port(port);
+    }
+    if let Some(username) = &settings.username {
+        let password = match &settings.password {
+            Some(password) => password.clone(),
+            None => std::env::var(crate::settings::SMTP_PASSWORD_VAR).unwrap_or_default(),
+        };
+        transport = transport.credentials(
+            lettre::transport::smtp::authentication::Credentials::new(username.clone(), password),
+        );
+    }
+
+    transport.build().send(message).await?;
+    tracing::debug!(to = ?settings.to, "Sent an email");
+
+    Ok(())
+}
+
+#[test]
+fn strip_colors_test() {
+    assert_eq!(
+        strip_colors("Food \u{1b}[38;5;9m+2.50\u{1b}[39m\u{1b}[0m"),
+        "Food +2.50"
+    );
+}
@@ -0,0 +1,298 @@
+use eyre::Result;
+use notion::models::properties::{DateOrDateTime, DateValue, FormulaResultValue, PropertyValue};
+use serde_json::Value;
+use std::collections::HashMap;
+
+fn plain_text(text: &[notion::models::text::RichText]) -> Value {
+    Value::String(text.iter().map(|text| text.plain_text()).collect())
+}
+
+fn date(date: &Option<DateValue>) -> Value {
+    let to_string = |date: &DateOrDateTime| match date {
+        DateOrDateTime::Date(date) => date.to_string(),
+        DateOrDateTime::DateTime(date_time) => date_time.to_rfc3339(),
+    };
+
+    match date {
+        Some(DateValue {
+            start,
+            end: Some(end),
+            ..
+        }) => Value::String(format!("{}/{}", to_string(start), to_string(end))),
+        Some(DateValue { start, .. }) => Value::String(to_string(start)),
+        None => Value::Null,
+    }
+}
+
+/// A property value as plain JSON, with relations replaced by the titles in
+/// `relation_names`.
+pub fn property_json(
+    value: &PropertyValue,
+    relation_names: &HashMap<notion::ids::PageId, String>,
+) -> Value {
+    match value {
+        PropertyValue::Title { title, .. } => plain_text(title),
+        PropertyValue::Text { rich_text, .. } => plain_text(rich_text),
+        PropertyValue::Number { number, .. } => number.clone().map_or(Value::Null, Value::Number),
+        PropertyValue::Select { select, .. } | PropertyValue::Status { status: select, .. } => {
+            select
+                .as_ref()
+                .and_then(|select| select.name.clone())
+                .map_or(Value::Null, Value::String)
+        }
+        PropertyValue::MultiSelect { multi_select, .. } => Value::Array(
+            multi_select
+                .iter()
+                .flatten()
+                .filter_map(|select| select.name.clone().map(Value::String))
+                .collect(),
+        ),
+        PropertyValue::Date { date: value, .. } => date(value),
+        PropertyValue::Formula { formula, .. } => match formula {
+            FormulaResultValue::String { string } => {
+                string.clone().map_or(Value::Null, Value::String)
+            }
+            FormulaResultValue::Number { number } => {
+                number.clone().map_or(Value::Null, Value::Number)
+            }
+            FormulaResultValue::Boolean { boolean } => boolean.map_or(Value::Null, Value::Bool),
+            FormulaResultValue::Date { date: value } => date(value),
+        },
+        PropertyValue::Relation { relation, .. } => Value::Array(
+            relation
+                .iter()
+                .flatten()
+                .map(|value| {
+                    Value::String(
+                        relation_names
+                            .get(&value.id)
+                            .cloned()
+                            .unwrap_or_else(|| value.id.to_string()),
+                    )
+                })
+                .collect(),
+        ),
+        PropertyValue::Checkbox { checkbox, .. } => Value::Bool(*checkbox),
+        PropertyValue::Url { url: text, .. } | PropertyValue::Email { email: text, .. } => {
+            text.clone().map_or(Value::Null, Value::String)
+        }
+        PropertyValue::PhoneNumber { phone_number, .. } => Value::String(phone_number.clone()),
+        PropertyValue::CreatedTime { created_time, .. } => Value::String(created_time.to_rfc3339()),
+        PropertyValue::LastEditedTime {
+            last_edited_time, ..
+        } => Value::String(last_edited_time.to_rfc3339()),
+        _ => Value::Null,
+    }
+}
+
+fn properties_json(
+    page: &notion::models::Page,
+    relation_names: &HashMap<notion::ids::PageId, String>,
+) -> serde_json::Map<String, Value> {
+    page.properties
+        .properties
+        .iter()
+        .map(|(name, value)| (name.clone(), property_json(value, relation_names)))
+        .collect()
+}
+
+/// The properties of `pages` as JSON objects.
+pub fn rows(
+    pages: &[notion::models::Page],
+    relation_names: &HashMap<notion::ids::PageId, String>,
+) -> Vec<serde_json::Map<String, Value>> {
+    pages
+        .iter()
+        .map(|page| properties_json(page, relation_names))
+        .collect()
+}
+
+/// Where a page opens in Notion.
+pub fn page_url(id: &notion::ids::PageId) -> String {
+    format!("https://www.notion.so/{}", id.to_string().replace('-', ""))
+}
+
+/// A page's id, URL and properties, for scripts.
+pub fn page_json(
+    page: &notion::models::Page,
+    relation_names: &HashMap<notion::ids::PageId, String>,
+) -> Value {
+    serde_json::json!({
+        "id": page.id.to_string(),
+        "url": page_url(&page.id),
+        "properties": properties_json(page, relation_names),
+    })
+}
+
+fn csv_field(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Array(values)) => values
+            .iter()
+            .map(|value| csv_field(Some(value)))
+            .collect::<Vec<_>>()
+            .join(", "),
+        Some(value) => value.to_string(),
+    }
+}
+
+pub fn write_csv(
+    writer: impl std::io::Write,
+    columns: &[String],
+    rows: &[serde_json::Map<String, Value>],
+) -> Result<()> {
+    let mut writer = csv::Writer::from_writer(writer);
+
+    writer.write_record(columns)?;
+    for row in rows {
+        writer.write_record(columns.iter().map(|column| csv_field(row.get(column))))?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+pub fn write_json(
+    writer: impl std::io::Write,
+    rows: &[serde_json::Map<String, Value>],
+) -> Result<()> {
+    Ok(serde_json::to_writer_pretty(writer, rows)?)
+}
+
+/// `pages` as ledger-cli transactions, oldest first. Entries without a date or
+/// an amount are left out.
+pub fn write_ledger(
+    mut writer: impl std::io::Write,
+    pages: &[notion::models::Page],
+    relation_names: &HashMap<notion::ids::PageId, String>,
+    names: &crate::settings::PropertyNames,
+    accounts: &crate::settings::LedgerAccounts,
+) -> Result<()> {
+    use crate::report::EntryType;
+
+    let mut transactions: Vec<_> = pages
+        .iter()
+        .filter_map(|page| {
+            let date = crate::report::page_date(page, &names.date)?;
+            let amount = crate::report::page_amount(page, &names.amount)?;
+            Some((date, amount, page))
+        })
+        .collect();
+    transactions.sort_by_key(|(date, _, _)| *date);
+
+    for (date, amount, page) in transactions {
+        let category = crate::report::page_relation_ids(page, &names.category)
+            .first()
+            .and_then(|id| relation_names.get(id).cloned())
+            .or_else(|| crate::report::page_select_name(page, &names.category));
+        let entry_type = EntryType::of(page, &names.entry_type);
+        let category_account = accounts.category_account(category.as_deref(), entry_type);
+
+        // Income is paid into the account, the rest is paid from it.
+        let (to, from) = match entry_type {
+            EntryType::Income => (accounts.account.as_str(), category_account.as_str()),
+            EntryType::Expense | EntryType::Transfer => {
+                (category_account.as_str(), accounts.account.as_str())
+            }
+        };
+        let amount = match &accounts.commodity {
+            Some(commodity) => format!("{:.2} {}", amount, commodity),
+            None => format!("{:.2}", amount),
+        };
+
+        writeln!(
+            writer,
+            "{} {}",
+            date.format("%Y/%m/%d"),
+            page.title().unwrap_or("Untitled".to_string())
+        )?;
+        writeln!(writer, "    {:<36}  {:>12}", to, amount)?;
+        writeln!(writer, "    {}", from)?;
+        writeln!(writer)?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+#[test]
+fn write_csv_test() {
+    let page: notion::models::Page = crate::report::page(serde_json::json!({
+        "Name": {"id": "title", "type": "title", "title": [
+            {"type": "text", "plain_text": "Coffee, large", "text": {"content": "Coffee, large", "link": null}}
+        ]},
+        "Amount": {"id": "a", "type": "number", "number": 3.5},
+        "Date": {"id": "d", "type": "date", "date": {"start": "2024-05-02"}},
+        "Category": {"id": "c", "type": "relation", "relation": [{"id": "food"}]},
+    }));
+
+    let relation_names = HashMap::from([("food".parse().unwrap(), "Food".to_string())]);
+    let columns = ["Date", "Name", "Amount", "Category", "Missing"].map(String::from);
+
+    let mut output = vec![];
+    write_csv(&mut output, &columns, &rows(&[page], &relation_names)).unwrap();
+
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "Date,Name,Amount,Category,Missing\n2024-05-02,\"Coffee, large\",3.5,Food,\n"
+    );
+}
+
+#[test]
+fn page_json_test() {
+    let mut page = crate::report::page(serde_json::json!({
+        "Amount": {"id": "a", "type": "number", "number": 3.5},
+    }));
+    page.id = "5c6a2821-6bb1-4a7e-b6e1-c50111515c3d".parse().unwrap();
+
+    assert_eq!(
+        page_json(&page, &HashMap::new()),
+        serde_json::json!({
+            "id": "5c6a2821-6bb1-4a7e-b6e1-c50111515c3d",
+            "url": "https://www.notion.so/5c6a28216bb14a7eb6e1c50111515c3d",
+            "properties": {"Amount": 3.5},
+        })
+    );
+}
+
+#[test]
+fn write_ledger_test() {
+    let page = |name: &str, amount: f64, date: &str, entry_type: &str| -> notion::models::Page {
+        crate::report::page(serde_json::json!({
+            "Name": {"id": "title", "type": "title", "title": [
+                {"type": "text", "plain_text": name, "text": {"content": name, "link": null}}
+            ]},
+            "Amount": {"id": "a", "type": "number", "number": amount},
+            "Date": {"id": "d", "type": "date", "date": {"start": date}},
+            "Category": {"id": "c", "type": "relation", "relation": [{"id": "food"}]},
+            "Type": {"id": "t", "type": "select", "select": {"name": entry_type, "color": "default"}},
+        }))
+    };
+
+    let relation_names = HashMap::from([("food".parse().unwrap(), "Food".to_string())]);
+    let accounts = crate::settings::LedgerAccounts {
+        income: "Income:Refunds".to_string(),
+        commodity: Some("EUR".to_string()),
+        ..Default::default()
+    };
+
+    let mut output = vec![];
+    write_ledger(
+        &mut output,
+        &[
+            page("Groceries", 43.2, "2024-05-03", "Expense"),
+            page("Returned kettle", 20.0, "2024-05-02", "Income"),
+        ],
+        &relation_names,
+        &crate::settings::PropertyNames::default(),
+        &accounts,
+    )
+    .unwrap();
+
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "2024/05/02 Returned kettle\n    Assets:Checking                          20.00 EUR\n    Income:Refunds:Food\n\n\
+         2024/05/03 Groceries\n    Expenses:Food                            43.20 EUR\n    Assets:Checking\n\n"
+    );
+}
@@ -0,0 +1,235 @@
+use base64::Engine;
+use eyre::{eyre, Result};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+
+type Stream = BufReader<tokio_native_tls::TlsStream<tokio::net::TcpStream>>;
+
+/// A line the server sent, with the literals that came with it.
+#[derive(Debug, Default, PartialEq)]
+struct Response {
+    text: String,
+    literals: Vec<Vec<u8>>,
+}
+
+/// The size of the literal announced at the end of `line`, like `{1024}`.
+fn literal_size(line: &str) -> Option<usize> {
+    line.strip_suffix('}')?.rsplit_once('{')?.1.parse().ok()
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// `name` in the modified UTF-7 mailbox names are sent in.
+fn mailbox_name(name: &str) -> String {
+    let mut encoded = String::new();
+    let mut wide = vec![];
+    let flush = |encoded: &mut String, wide: &mut Vec<u8>| {
+        if !wide.is_empty() {
+            let base64 = base64::engine::general_purpose::STANDARD_NO_PAD.encode(&*wide);
+            encoded.push_str(&format!("&{}-", base64.replace('/', ",")));
+            wide.clear();
+        }
+    };
+
+    for c in name.chars() {
+        match c {
+            '&' => {
+                flush(&mut encoded, &mut wide);
+                encoded.push_str("&-");
+            }
+            ' '..='~' => {
+                flush(&mut encoded, &mut wide);
+                encoded.push(c);
+            }
+            _ => {
+                let mut units = [0; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    wide.extend_from_slice(&unit.to_be_bytes());
+                }
+            }
+        }
+    }
+    flush(&mut encoded, &mut wide);
+
+    encoded
+}
+
+/// The UIDs in the `SEARCH` responses.
+fn search_results(responses: &[Response]) -> Vec<u32> {
+    responses
+        .iter()
+        .filter_map(|response| response.text.strip_prefix("* SEARCH"))
+        .flat_map(|uids| uids.split_whitespace().filter_map(|uid| uid.parse().ok()))
+        .collect()
+}
+
+/// The UID and the first literal of each `FETCH` response.
+fn fetched(responses: &mut [Response]) -> Vec<(u32, Vec<u8>)> {
+    responses
+        .iter_mut()
+        .filter(|response| response.text.contains(" FETCH ("))
+        .filter_map(|response| {
+            let (_, after) = response.text.split_once("UID ")?;
+            let uid = after
+                .split(|c: char| !c.is_ascii_digit())
+                .next()?
+                .parse()
+                .ok()?;
+            let content = response.literals.drain(..).next()?;
+            Some((uid, content))
+        })
+        .collect()
+}
+
+/// Just enough IMAP to read the mails of one folder.
+pub struct Session {
+    stream: Stream,
+    tag: u32,
+}
+
+impl Session {
+    /// Connects over TLS and logs in.
+    #[tracing::instrument(skip_all, fields(host = settings.host))]
+    pub async fn connect(settings: &crate::settings::ImapSettings) -> Result<Self> {
+        let tcp = tokio::net::TcpStream::connect((settings.host.as_str(), settings.port))
+            .await
+            .map_err(|err| eyre!("Couldn't connect to {}: {}", settings.host, err))?;
+        let connector = tokio_native_tls::TlsConnector::from(
+            tokio_native_tls::native_tls::TlsConnector::new()?,
+        );
+        let tls = connector.connect(&settings.host, tcp).await?;
+
+        let mut session = Self {
+            stream: BufReader::new(tls),
+            tag: 0,
+        };
+        let greeting = session.read_response().await?;
+        if !greeting.text.starts_with("* OK") {
+            return Err(eyre!("Unexpected IMAP greeting: {}", greeting.text));
+        }
+
+        let password = match &settings.password {
+            Some(password) => password.clone(),
+            None => std::env::var(crate::settings::IMAP_PASSWORD_VAR).map_err(|_| {
+                eyre!(
+                    "Set `imap.password` or {}",
+                    crate::settings::IMAP_PASSWORD_VAR
+                )
+            })?,
+        };
+        session
+            .command(&format!(
+                "LOGIN {} {}",
+                quote(&settings.username),
+                quote(&password)
+            ))
+            .await?;
+
+        Ok(session)
+    }
+
+    async fn read_response(&mut self) -> Result<Response> {
+        let mut response = Response::default();
+        loop {
+            let mut line = vec![];
+            if self.stream.read_until(b'\n', &mut line).await? == 0 {
+                return Err(eyre!("The IMAP server closed the connection"));
+            }
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\r', '\n']);
+            response.text.push_str(line);
+
+            let Some(size) = literal_size(line) else {
+                return Ok(response);
+            };
+            let mut literal = vec![0; size];
+            self.stream.read_exact(&mut literal).await?;
+            response.literals.push(literal);
+        }
+    }
+
+    /// Sends `command` and returns the untagged responses to it.
+    async fn command(&mut self, command: &str) -> Result<Vec<Response>> {
+        self.tag += 1;
+        let tag = format!("A{} ", self.tag);
+        let stream = self.stream.get_mut();
+        stream
+            .write_all(format!("{}{}\r\n", tag, command).as_bytes())
+            .await?;
+        stream.flush().await?;
+
+        let mut responses = vec![];
+        loop {
+            let response = self.read_response().await?;
+            let Some(status) = response.text.strip_prefix(&tag) else {
+                responses.push(response);
+                continue;
+            };
+            if status.starts_with("OK") {
+                return Ok(responses);
+            }
+
+            let verb = command.split_whitespace().next().unwrap_or_default();
+            return Err(eyre!("IMAP {} failed: {}", verb, status));
+        }
+    }
+
+    pub async fn select(&mut self, folder: &str) -> Result<()> {
+        self.command(&format!("SELECT {}", quote(&mailbox_name(folder))))
+            .await?;
+        Ok(())
+    }
+
+    /// UIDs of the mails received on `date` or later.
+    pub async fn search_since(&mut self, date: notion::chrono::NaiveDate) -> Result<Vec<u32>> {
+        let responses = self
+            .command(&format!("UID SEARCH SINCE {}", date.format("%-d-%b-%Y")))
+            .await?;
+        Ok(search_results(&responses))
+    }
+
+    /// `section` of each of the mails `uids`, like `HEADER` or the whole mail
+    /// with an empty one, without marking them as read.
+    pub async fn fetch(&mut self, uids: &[u32], section: &str) -> Result<Vec<(u32, Vec<u8>)>> {
+        if uids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let set = uids
+            .iter()
+            .map(|uid| uid.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        let mut responses = self
+            .command(&format!("UID FETCH {} (UID BODY.PEEK[{}])", set, section))
+            .await?;
+        Ok(fetched(&mut responses))
+    }
+
+    pub async fn logout(mut self) -> Result<()> {
+        self.command("LOGOUT").await?;
+        Ok(())
+    }
+}
+
+#[test]
+fn response_test() {
+    assert_eq!(literal_size("* 1 FETCH (UID 7 BODY[] {342}"), Some(342));
+    assert_eq!(literal_size("* OK ready"), None);
+    assert_eq!(quote(r#"pa"ss\"#), r#""pa\"ss\\""#);
+    assert_eq!(mailbox_name("订单 & Co"), "&i6JTVQ- &- Co");
+
+    let mut responses = vec![
+        Response {
+            text: "* SEARCH 4 7".to_string(),
+            literals: vec![],
+        },
+        Response {
+            text: "* 2 FETCH (BODY[] {5} UID 7)".to_string(),
+            literals: vec![b"Hello".to_vec()],
+        },
+    ];
+    assert_eq!(search_results(&responses), [4, 7]);
+    assert_eq!(fetched(&mut responses), [(7, b"Hello".to_vec())]);
+}
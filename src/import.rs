@@ -0,0 +1,100 @@
+use eyre::{eyre, Result, WrapErr};
+use notion::chrono::NaiveDate;
+
+/// A single line of a bank statement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row {
+    pub name: String,
+    pub amount: rust_decimal::Decimal,
+    pub date: NaiveDate,
+}
+
+impl Row {
+    /// Whether `page` already records this expense.
+    pub fn matches(
+        &self,
+        page: &notion::models::Page,
+        names: &crate::settings::PropertyNames,
+    ) -> bool {
+        crate::report::page_date(page, &names.date) == Some(self.date)
+            && crate::report::page_amount(page, &names.amount)
+                .is_some_and(|amount| (amount - crate::calc::to_f64(self.amount)).abs() < 0.005)
+            && page
+                .title()
+                .is_some_and(|title| title.trim().eq_ignore_ascii_case(self.name.trim()))
+    }
+}
+
+pub fn read_csv(
+    reader: impl std::io::Read,
+    columns: &crate::settings::CsvColumns,
+) -> Result<Vec<Row>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(columns.delimiter as u8)
+        .trim(csv::Trim::All)
+        .from_reader(reader);
+
+    let headers = reader.headers()?.clone();
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|header| header == name)
+            .ok_or_else(|| eyre!("Column `{}` not found", name))
+    };
+
+    let name = column(&columns.name)?;
+    let amount = column(&columns.amount)?;
+    let date = column(&columns.date)?;
+
+    reader
+        .records()
+        .enumerate()
+        .map(|(i, record)| {
+            let record = record?;
+            let field = |index: usize| record.get(index).unwrap_or_default();
+
+            Ok(Row {
+                name: field(name).to_string(),
+                amount: crate::calc::calc(field(amount))
+                    .wrap_err_with(|| format!("Invalid amount on line {}", i + 2))?,
+                date: NaiveDate::parse_from_str(field(date), &columns.date_format)
+                    .wrap_err_with(|| format!("Invalid date on line {}", i + 2))?,
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn read_csv_test() {
+    let columns = crate::settings::CsvColumns {
+        name: "Description".to_string(),
+        amount: "Value".to_string(),
+        date: "Booked".to_string(),
+        date_format: "%d.%m.%Y".to_string(),
+        delimiter: ';',
+    };
+
+    let rows = read_csv(
+        "Booked;Description;Value\n02.05.2024; Groceries ;43,20\n03.05.2024;Bus;2.5\n".as_bytes(),
+        &columns,
+    )
+    .unwrap();
+
+    assert_eq!(
+        rows,
+        vec![
+            Row {
+                name: "Groceries".to_string(),
+                amount: "43.2".parse().unwrap(),
+                date: NaiveDate::from_ymd_opt(2024, 5, 2).unwrap(),
+            },
+            Row {
+                name: "Bus".to_string(),
+                amount: "2.5".parse().unwrap(),
+                date: NaiveDate::from_ymd_opt(2024, 5, 3).unwrap(),
+            },
+        ]
+    );
+
+    assert!(read_csv("Booked;Value\n".as_bytes(), &columns).is_err());
+}
@@ -0,0 +1,365 @@
+use base64::Engine;
+use eyre::{eyre, Result};
+use notion::chrono::NaiveDate;
+use regex_automata::meta::Regex;
+
+/// The parts of a mail receipts are read from.
+#[derive(Debug, Default, PartialEq)]
+pub struct Mail {
+    pub from: String,
+    pub subject: String,
+    pub date: Option<NaiveDate>,
+    /// The plain text part, or the HTML one without the markup.
+    pub text: String,
+}
+
+/// The headers and the body of a message or a part of one.
+fn split(raw: &[u8]) -> (&[u8], &[u8]) {
+    let end = (0..raw.len()).find_map(|i| {
+        let rest = &raw[i..];
+        if rest.starts_with(b"\r\n\r\n") {
+            Some((i, i + 4))
+        } else if rest.starts_with(b"\n\n") {
+            Some((i, i + 2))
+        } else {
+            None
+        }
+    });
+
+    match end {
+        Some((headers, body)) => (&raw[..headers], &raw[body..]),
+        None => (raw, &[]),
+    }
+}
+
+/// The unfolded headers with their names in lowercase.
+fn headers(raw: &[u8]) -> Vec<(String, String)> {
+    let mut headers: Vec<(String, String)> = vec![];
+    for line in String::from_utf8_lossy(raw).lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_lowercase(), value.trim().to_string()));
+        }
+    }
+    headers
+}
+
+fn header<'a>(headers: &'a [(String, String)], name: &str) -> &'a str {
+    headers
+        .iter()
+        .find(|(header, _)| header == name)
+        .map(|(_, value)| value.as_str())
+        .unwrap_or_default()
+}
+
+/// The `name` parameter of a header like `Content-Type`.
+fn parameter(value: &str, name: &str) -> Option<String> {
+    value.split(';').skip(1).find_map(|parameter| {
+        let (key, value) = parameter.split_once('=')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then(|| value.trim().trim_matches('"').to_string())
+    })
+}
+
+fn decode_charset(bytes: &[u8], charset: &str) -> String {
+    let encoding =
+        encoding_rs::Encoding::for_label(charset.trim().as_bytes()).unwrap_or(encoding_rs::UTF_8);
+    encoding.decode(bytes).0.into_owned()
+}
+
+fn decode_base64(text: &[u8]) -> Vec<u8> {
+    let text: Vec<u8> = text
+        .iter()
+        .copied()
+        .filter(|c| !c.is_ascii_whitespace())
+        .collect();
+    base64::engine::general_purpose::STANDARD
+        .decode(text)
+        .unwrap_or_default()
+}
+
+/// `value` with its `=?charset?B?...?=` and `=?charset?Q?...?=` words decoded.
+fn decode_words(value: &str) -> String {
+    let mut decoded = String::new();
+    let mut rest = value;
+    let mut after_word = false;
+    while let Some(start) = rest.find("=?") {
+        let word = rest[start + 2..].splitn(3, '?').collect::<Vec<_>>();
+        let [charset, encoding, text] = word[..] else {
+            break;
+        };
+        let Some(end) = text.find("?=") else {
+            break;
+        };
+        let bytes = match encoding {
+            "B" | "b" => decode_base64(&text.as_bytes()[..end]),
+            "Q" | "q" => quoted_printable::decode(
+                text[..end].replace('_', " "),
+                quoted_printable::ParseMode::Robust,
+            )
+            .unwrap_or_default(),
+            _ => break,
+        };
+
+        // The space between two encoded words isn't part of the text.
+        let before = &rest[..start];
+        if !(after_word && before.trim().is_empty()) {
+            decoded.push_str(before);
+        }
+        decoded.push_str(&decode_charset(&bytes, charset));
+        after_word = true;
+        rest = &text[end + 2..];
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// `html` as text, without the tags, styles and scripts.
+fn strip_html(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        text.push(' ');
+        let tag = rest[start + 1..].to_ascii_lowercase();
+        let skip = ["style", "script"]
+            .iter()
+            .find(|name| tag.starts_with(*name))
+            .and_then(|name| tag.find(&format!("</{}", name)));
+        rest = &rest[start + 1 + skip.unwrap_or(0)..];
+        rest = rest.split_once('>').map_or("", |(_, rest)| rest);
+    }
+    text.push_str(rest);
+
+    [
+        ("&nbsp;", " "),
+        ("&yen;", "¥"),
+        ("&euro;", "€"),
+        ("&pound;", "£"),
+        ("&lt;", "<"),
+        ("&gt;", ">"),
+        ("&quot;", "\""),
+        ("&#39;", "'"),
+        ("&amp;", "&"),
+    ]
+    .iter()
+    .fold(text, |text, (entity, c)| text.replace(entity, c))
+    .lines()
+    .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+    .filter(|line| !line.is_empty())
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+/// The readable text of a part, preferring plain text over HTML.
+fn part_text(raw: &[u8]) -> Option<(bool, String)> {
+    let (head, body) = split(raw);
+    let headers = headers(head);
+    let content_type = header(&headers, "content-type").to_lowercase();
+
+    if content_type.starts_with("multipart/") {
+        let boundary = format!(
+            "--{}",
+            parameter(header(&headers, "content-type"), "boundary")?
+        );
+        let body = String::from_utf8_lossy(body);
+        let texts: Vec<_> = body
+            .split(&boundary)
+            .skip(1)
+            .filter(|part| !part.starts_with("--"))
+            .filter_map(|part| part_text(part.trim_start_matches(['\r', '\n']).as_bytes()))
+            .collect();
+        return texts
+            .iter()
+            .find(|(html, _)| !html)
+            .or(texts.first())
+            .cloned();
+    }
+
+    let html = content_type.starts_with("text/html");
+    if !content_type.is_empty() && !html && !content_type.starts_with("text/plain") {
+        return None;
+    }
+
+    let body = match header(&headers, "content-transfer-encoding")
+        .to_lowercase()
+        .as_str()
+    {
+        "base64" => decode_base64(body),
+        "quoted-printable" => {
+            quoted_printable::decode(body, quoted_printable::ParseMode::Robust).unwrap_or_default()
+        }
+        _ => body.to_vec(),
+    };
+    let charset = parameter(header(&headers, "content-type"), "charset").unwrap_or_default();
+    let text = decode_charset(&body, &charset);
+    let text = if html { strip_html(&text) } else { text };
+
+    Some((html, text.trim().to_string()))
+}
+
+impl Mail {
+    pub fn parse(raw: &[u8]) -> Self {
+        let headers = headers(split(raw).0);
+        let date = notion::chrono::DateTime::parse_from_rfc2822(header(&headers, "date"))
+            .ok()
+            .map(|date| date.date_naive());
+
+        Self {
+            from: decode_words(header(&headers, "from")),
+            subject: decode_words(header(&headers, "subject")),
+            date,
+            text: part_text(raw).map(|(_, text)| text).unwrap_or_default(),
+        }
+    }
+
+    /// The sender's name, or their address when the mail has none.
+    pub fn sender(&self) -> String {
+        match self.from.split_once('<') {
+            Some((name, _)) if !name.trim().is_empty() => name.trim().trim_matches('"').to_string(),
+            Some((_, address)) => address.trim_end_matches('>').to_string(),
+            None => self.from.clone(),
+        }
+    }
+}
+
+/// Whether a mail titled `subject` is about a purchase.
+pub fn is_receipt(subject: &str, keywords: &[String]) -> bool {
+    let subject = subject.to_lowercase();
+    keywords
+        .iter()
+        .any(|keyword| subject.contains(&keyword.to_lowercase()))
+}
+
+/// A `ReceiptRule` with its expressions compiled.
+pub struct Rule {
+    from: Option<String>,
+    merchant: Option<Regex>,
+    amount: Regex,
+    date: Option<Regex>,
+    date_format: Option<String>,
+}
+
+fn compile(pattern: &str) -> Result<Regex> {
+    Regex::new(pattern).map_err(|err| eyre!("Invalid rule `{}`: {}", pattern, err))
+}
+
+impl Rule {
+    pub fn new(rule: &crate::settings::ReceiptRule) -> Result<Self> {
+        Ok(Self {
+            from: rule.from.as_ref().map(|from| from.to_lowercase()),
+            merchant: rule.merchant.as_deref().map(compile).transpose()?,
+            amount: compile(&rule.amount)?,
+            date: rule.date.as_deref().map(compile).transpose()?,
+            date_format: rule.date_format.clone(),
+        })
+    }
+}
+
+/// The first group of `regex` in `text`, or the whole match.
+fn capture<'a>(regex: &Regex, text: &'a str) -> Option<&'a str> {
+    let mut captures = regex.create_captures();
+    regex.captures(text, &mut captures);
+    let span = captures.get_group(1).or(captures.get_group(0))?;
+    Some(text[span.range()].trim())
+}
+
+/// The expense in `mail` by the first of `rules` finding an amount.
+pub fn extract(mail: &Mail, rules: &[Rule], today: NaiveDate) -> Option<crate::import::Row> {
+    let from = mail.from.to_lowercase();
+
+    rules
+        .iter()
+        .filter(|rule| rule.from.as_ref().is_none_or(|part| from.contains(part)))
+        .find_map(|rule| {
+            let amount = capture(&rule.amount, &mail.text)?;
+            let amount = crate::ocr::amount(amount).or_else(|| amount.parse().ok())?;
+
+            let name = match &rule.merchant {
+                Some(merchant) => capture(merchant, &mail.text)?.to_string(),
+                None => mail.sender(),
+            };
+            let date = match &rule.date {
+                Some(date) => {
+                    let date = capture(date, &mail.text)?;
+                    match &rule.date_format {
+                        Some(format) => NaiveDate::parse_from_str(date, format).ok(),
+                        None => crate::ocr::date(date, today),
+                    }
+                }
+                None => None,
+            };
+
+            Some(crate::import::Row {
+                name,
+                amount,
+                date: date.or(mail.date).unwrap_or(today),
+            })
+        })
+}
+
+#[test]
+fn extract_test() {
+    let raw = "From: =?UTF-8?B?5Lqs5Lic?= <orders@jd.com>\r\n\
+        Subject: =?UTF-8?B?5oKo55qE6K6i5Y2V?= =?UTF-8?Q?_12345?=\r\n\
+        Date: Tue, 14 May 2024 10:00:00 +0800\r\n\
+        Content-Type: multipart/alternative;\r\n\
+        \tboundary=\"b1\"\r\n\
+        \r\n\
+        --b1\r\n\
+        Content-Type: text/html; charset=utf-8\r\n\
+        \r\n\
+        <style>td { color: red }</style><td>&#x5b9e;</td>\r\n\
+        --b1\r\n\
+        Content-Type: text/plain; charset=utf-8\r\n\
+        Content-Transfer-Encoding: base64\r\n\
+        \r\n\
+        5ZWG5ZOB5ZCI6K6hOiDCpTEyOC4wMArlrp7ku5jmrL46IMKlMSwwOTkuNTA=\r\n\
+        --b1--\r\n";
+
+    let mail = Mail::parse(raw.as_bytes());
+    assert_eq!(mail.from, "京东 <orders@jd.com>");
+    assert_eq!(mail.subject, "您的订单 12345");
+    assert_eq!(mail.text, "商品合计: ¥128.00\n实付款: ¥1,099.50");
+    assert!(is_receipt(
+        &mail.subject,
+        &["Receipt".to_string(), "订单".to_string()]
+    ));
+
+    let today = NaiveDate::from_ymd_opt(2024, 5, 20).unwrap();
+    let rules = [
+        crate::settings::ReceiptRule {
+            from: Some("amazon".to_string()),
+            merchant: None,
+            amount: r"(\d+\.\d{2})".to_string(),
+            date: None,
+            date_format: None,
+        },
+        crate::settings::ReceiptRule {
+            from: Some("JD.com".to_string()),
+            merchant: None,
+            amount: r"实付款\D*([\d,]+\.\d{2})".to_string(),
+            date: None,
+            date_format: None,
+        },
+    ]
+    .iter()
+    .map(Rule::new)
+    .collect::<Result<Vec<_>>>()
+    .unwrap();
+
+    assert_eq!(
+        extract(&mail, &rules, today),
+        Some(crate::import::Row {
+            name: "京东".to_string(),
+            amount: "1099.5".parse().unwrap(),
+            date: NaiveDate::from_ymd_opt(2024, 5, 14).unwrap(),
+        })
+    );
+    assert!(strip_html("<p>Total&nbsp;<b>$5.00</b></p>").contains("Total $5.00"));
+}
@@ -0,0 +1,33 @@
+pub mod app;
+pub mod attachment;
+pub mod backend;
+pub mod bank;
+pub mod cache;
+pub mod calc;
+pub mod check;
+pub mod cli;
+pub mod completion;
+#[cfg(unix)]
+pub mod daemon;
+pub mod dedupe;
+pub mod doctor;
+pub mod email;
+pub mod export;
+pub mod imap;
+pub mod import;
+pub mod ingest;
+pub mod notify;
+pub mod ocr;
+pub mod prompt;
+pub mod queue;
+pub mod quick;
+pub mod receipt;
+pub mod recurring;
+pub mod report;
+pub mod rules;
+pub mod server;
+pub mod settings;
+pub mod split;
+pub mod sqlite;
+pub mod sync;
+pub mod ui;
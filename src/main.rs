@@ -1,13 +1,47 @@
+use clap::{CommandFactory, Parser};
 use eyre::Result;
 
-mod app;
-mod settings;
+/// Logs to stderr at the level the `-v` flags ask for, or else the one in
+/// `RUST_LOG`, warnings by default.
+fn init_logging(verbose: u8) {
+    let filter = match verbose {
+        0 => tracing_subscriber::EnvFilter::try_from_default_env()
+            .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("warn")),
+        1 => tracing_subscriber::EnvFilter::new("notion_bot=debug"),
+        _ => tracing_subscriber::EnvFilter::new("notion_bot=trace"),
+    };
+
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .init();
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    crate::app::App::run().await?;
+    // Answers the shell when it's completing rather than running a command.
+    clap_complete::CompleteEnv::with_factory(notion_bot::cli::Cli::command).complete();
+
+    let cli = notion_bot::cli::Cli::parse();
+    init_logging(cli.global.verbose);
+
+    // The completion script is sourced and markdown reports are pasted, so
+    // nothing but them may be printed.
+    let quiet = cli.global.json
+        || matches!(
+            cli.command,
+            Some(notion_bot::cli::Command::Completions { .. })
+                | Some(notion_bot::cli::Command::Report {
+                    format: notion_bot::cli::ReportFormat::Markdown,
+                    output: None,
+                    ..
+                })
+        );
+    notion_bot::app::App::run(cli).await?;
 
-    println!("Done");
+    if !quiet {
+        println!("Done");
+    }
 
     Ok(())
 }
@@ -0,0 +1,142 @@
+use crate::settings::{NotifyFormat, NotifySettings};
+use eyre::Result;
+
+/// What a notification is about.
+#[derive(Debug)]
+pub enum Event<'a> {
+    Created(&'a crate::cli::AddArgs),
+    /// `spent` got past the threshold of `limit`.
+    Budget {
+        category: &'a str,
+        spent: f64,
+        limit: f64,
+    },
+    Digest(&'a crate::report::Digest),
+}
+
+/// `template` with every `{key}` replaced by its value.
+fn render(template: &str, values: &[(&str, String)]) -> String {
+    values
+        .iter()
+        .fold(template.to_string(), |message, (key, value)| {
+            message.replace(&format!("{{{}}}", key), value)
+        })
+}
+
+pub fn message(settings: &NotifySettings, event: &Event) -> String {
+    match event {
+        Event::Created(fields) => render(
+            &settings.entry_template,
+            &[
+                ("name", fields.name.clone().unwrap_or_default()),
+                ("amount", fields.amount.clone().unwrap_or_default()),
+                ("category", fields.category.clone().unwrap_or_default()),
+                (
+                    "date",
+                    fields.date.map(|date| date.to_string()).unwrap_or_default(),
+                ),
+            ],
+        ),
+        Event::Budget {
+            category,
+            spent,
+            limit,
+        } => render(
+            &settings.budget_template,
+            &[
+                ("category", category.to_string()),
+                ("spent", format!("{:.2}", spent)),
+                ("limit", format!("{:.2}", limit)),
+            ],
+        ),
+        Event::Digest(digest) => digest.to_string(),
+    }
+}
+
+/// The request body for `event` in the configured format.
+fn body(settings: &NotifySettings, event: &Event) -> reqwest::Body {
+    let message = message(settings, event);
+
+    let json = match (settings.format, event) {
+        (NotifyFormat::Text, _) => return message.into(),
+        (NotifyFormat::Slack, _) => serde_json::json!({ "text": message }),
+        (NotifyFormat::Json, Event::Created(fields)) => {
+            serde_json::json!({ "event": "created", "message": message, "entry": fields })
+        }
+        (
+            NotifyFormat::Json,
+            Event::Budget {
+                category,
+                spent,
+                limit,
+            },
+        ) => serde_json::json!({
+            "event": "budget",
+            "message": message,
+            "category": category,
+            "spent": spent,
+            "limit": limit,
+        }),
+        (NotifyFormat::Json, Event::Digest(digest)) => {
+            serde_json::json!({ "event": "digest", "message": message, "digest": digest.json() })
+        }
+    };
+    json.to_string().into()
+}
+
+/// Posts `event` to `settings.url`.
+#[tracing::instrument(skip_all)]
+pub async fn send(settings: &NotifySettings, event: Event<'_>) -> Result<()> {
+    let content_type = match settings.format {
+        NotifyFormat::Text => "text/plain; charset=utf-8",
+        NotifyFormat::Json | NotifyFormat::Slack => "application/json",
+    };
+
+    let response = reqwest::Client::new()
+        .post(&settings.url)
+        .header(reqwest::header::CONTENT_TYPE, content_type)
+        .body(body(settings, &event))
+        .send()
+        .await?;
+    tracing::debug!(status = %response.status(), ?event, "Sent a notification");
+    response.error_for_status()?;
+
+    Ok(())
+}
+
+/// Whether adding `amount` to `spent` gets past `threshold` of `limit`.
+pub fn crosses_budget(spent: f64, amount: f64, limit: f64, threshold: f64) -> bool {
+    let threshold = limit * threshold;
+    spent < threshold && spent + amount >= threshold
+}
+
+#[test]
+fn message_test() {
+    let settings: NotifySettings = serde_yaml::from_str("url: https://ntfy.sh/expenses").unwrap();
+    let fields = crate::cli::AddArgs {
+        name: Some("Groceries".to_string()),
+        amount: Some("43.20".to_string()),
+        category: Some("Food".to_string()),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        message(&settings, &Event::Created(&fields)),
+        "Groceries: 43.20 (Food)"
+    );
+    assert_eq!(
+        message(
+            &settings,
+            &Event::Budget {
+                category: "Food",
+                spent: 310.5,
+                limit: 300.0
+            }
+        ),
+        "Food: 310.50 of the 300.00 budget spent this month"
+    );
+
+    assert!(crosses_budget(290.0, 20.0, 300.0, 1.0));
+    assert!(!crosses_budget(310.0, 20.0, 300.0, 1.0));
+    assert!(crosses_budget(230.0, 20.0, 300.0, 0.8));
+}
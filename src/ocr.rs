@@ -0,0 +1,142 @@
+use eyre::{eyre, Result};
+use notion::chrono::NaiveDate;
+
+/// What could be read off a receipt.
+#[derive(Debug, Default, PartialEq)]
+pub struct Receipt {
+    /// The first line that reads like a name, usually the shop's.
+    pub merchant: Option<String>,
+    pub total: Option<rust_decimal::Decimal>,
+    pub date: Option<NaiveDate>,
+}
+
+/// Runs the OCR command on the image at `path` and returns the text it printed.
+pub async fn recognize(
+    settings: &crate::settings::OcrSettings,
+    path: &std::path::Path,
+) -> Result<String> {
+    let (program, args) = settings
+        .command
+        .split_first()
+        .ok_or_else(|| eyre!("`ocr.command` is empty"))?;
+    let path = path.to_string_lossy();
+
+    let output = tokio::process::Command::new(program)
+        .args(args.iter().map(|arg| arg.replace("{path}", &path)))
+        .output()
+        .await
+        .map_err(|err| {
+            eyre!(
+                "Couldn't run {}: {}, install it or set `ocr.command`",
+                program,
+                err
+            )
+        })?;
+    if !output.status.success() {
+        return Err(eyre!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// `word` as an amount with cents, like `12.50`, `12,50` or `$12.50`. Whole
+/// numbers are left out, they are quantities and codes more often than not.
+pub fn amount(word: &str) -> Option<rust_decimal::Decimal> {
+    let word = word.trim_matches(|c: char| !c.is_ascii_digit());
+    let (whole, cents) = word.rsplit_once(['.', ','])?;
+    let whole = whole.replace([',', '.', ' '], "");
+    if cents.len() != 2 || whole.is_empty() || !whole.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    format!("{}.{}", whole, cents).parse().ok()
+}
+
+/// The date `word` is in one of the common receipt formats. Day and month
+/// can be either way around in `05/08/2024`, the reading closest to `today`
+/// without being after it wins.
+pub fn date(word: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let word = word.trim_matches(|c: char| !c.is_ascii_digit());
+
+    [
+        "%Y-%m-%d", "%d.%m.%Y", "%d.%m.%y", "%d/%m/%Y", "%m/%d/%Y", "%Y/%m/%d",
+    ]
+    .iter()
+    .filter_map(|format| NaiveDate::parse_from_str(word, format).ok())
+    .filter(|date| *date <= today)
+    .max()
+}
+
+/// Reads the merchant, the total and the date out of the OCR `text`.
+pub fn parse(text: &str, today: NaiveDate) -> Receipt {
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    let merchant = lines
+        .iter()
+        .find(|line| {
+            line.chars().filter(|c| c.is_alphabetic()).count() >= 3
+                && !line.split_whitespace().any(|word| amount(word).is_some())
+        })
+        .map(|line| line.to_string());
+
+    // When there are several totals, like one before and one after a discount,
+    // the largest is usually what was paid.
+    let total = lines
+        .iter()
+        .filter(|line| {
+            let line = line.to_lowercase();
+            let total =
+                line.contains("total") && !line.contains("subtotal") && !line.contains("sub total");
+            total || line.contains("amount due") || line.contains("balance due")
+        })
+        .filter_map(|line| line.split_whitespace().rev().find_map(amount))
+        .max();
+
+    let date = lines
+        .iter()
+        .flat_map(|line| line.split_whitespace())
+        .find_map(|word| date(word, today));
+
+    Receipt {
+        merchant,
+        total,
+        date,
+    }
+}
+
+#[test]
+fn parse_test() {
+    let today = NaiveDate::from_ymd_opt(2024, 5, 20).unwrap();
+    let text = "
+        GREEN GROCER
+        12 Market St.
+        05/08/2024 14:32
+
+        Apples 2 x 1.20      2.40
+        Bread                3,10
+        SUBTOTAL             5.50
+        Discount            -0.50
+        TOTAL  EUR           5.00
+        Card                 5.00
+    ";
+
+    assert_eq!(
+        parse(text, today),
+        Receipt {
+            merchant: Some("GREEN GROCER".to_string()),
+            total: "5.00".parse().ok(),
+            date: NaiveDate::from_ymd_opt(2024, 5, 8),
+        }
+    );
+    assert_eq!(amount("$1,234.56"), "1234.56".parse().ok());
+    assert_eq!(amount("12"), None);
+    assert_eq!(parse("", today), Receipt::default());
+}
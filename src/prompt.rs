@@ -0,0 +1,235 @@
+use eyre::Result;
+use notion::models::properties::{
+    PropertyConfiguration, PropertyValue, SelectOption, SelectedValue,
+};
+
+pub fn rich_text(content: &str) -> Vec<notion::models::text::RichText> {
+    vec![notion::models::text::RichText::Text {
+        rich_text: notion::models::text::RichTextCommon {
+            plain_text: content.to_string(),
+            href: None,
+            annotations: None,
+        },
+        text: notion::models::text::Text {
+            content: content.to_string(),
+            link: None,
+        },
+    }]
+}
+
+pub fn selected_value(option: &SelectOption) -> SelectedValue {
+    SelectedValue {
+        id: Some(option.id.clone()),
+        name: Some(option.name.clone()),
+        color: option.color,
+    }
+}
+
+fn skippable_text(message: &str) -> Result<Option<String>> {
+    Ok(inquire::Text::new(message)
+        .with_help_message("Leave empty to skip")
+        .prompt_skippable()?
+        .filter(|value| !value.trim().is_empty()))
+}
+
+/// Prompts for a single line of notes, or for longer ones in `$EDITOR` when the
+/// answer is `!`. Returns `None` when skipped.
+pub fn notes(message: &str, initial: Option<&str>) -> Result<Option<String>> {
+    let mut prompt =
+        inquire::Text::new(message).with_help_message("Leave empty to skip, `!` opens $EDITOR");
+    if let Some(initial) = initial {
+        prompt = prompt.with_initial_value(initial);
+    }
+
+    let notes = match prompt.prompt_skippable()? {
+        Some(notes) if notes.trim() == "!" => inquire::Editor::new(message)
+            .with_predefined_text(initial.unwrap_or_default())
+            .with_file_extension(".md")
+            .prompt_skippable()?,
+        notes => notes,
+    };
+
+    Ok(notes
+        .map(|notes| notes.trim().to_string())
+        .filter(|notes| !notes.is_empty()))
+}
+
+/// The options named in `names`, with new options for the names that aren't one.
+/// Notion creates those on write.
+pub fn tag_values(options: &[SelectOption], names: &[String]) -> Vec<SelectedValue> {
+    let mut values: Vec<SelectedValue> = vec![];
+    for name in names.iter().map(|name| name.trim()) {
+        let value = match options.iter().find(|o| o.name.eq_ignore_ascii_case(name)) {
+            Some(option) => selected_value(option),
+            None => SelectedValue {
+                id: None,
+                name: Some(name.to_string()),
+                color: notion::models::properties::Color::Default,
+            },
+        };
+
+        if !name.is_empty() && !values.iter().any(|v| v.name == value.name) {
+            values.push(value);
+        }
+    }
+
+    values
+}
+
+#[test]
+fn tag_values_test() {
+    let options: Vec<SelectOption> = serde_json::from_value(serde_json::json!([
+        {"id": "1", "name": "Work", "color": "blue"},
+    ]))
+    .unwrap();
+
+    let values = tag_values(&options, &["work", " Trip ", "", "Work"].map(String::from));
+
+    assert_eq!(
+        values,
+        vec![
+            selected_value(&options[0]),
+            SelectedValue {
+                id: None,
+                name: Some("Trip".to_string()),
+                color: notion::models::properties::Color::Default,
+            },
+        ]
+    );
+}
+
+/// Prompts for tags out of `options`, starting with `selected` checked. Picking
+/// the last entry asks for tags that don't exist yet. Returns `None` when skipped.
+pub fn tags(
+    message: &str,
+    options: &[SelectOption],
+    selected: &[String],
+) -> Result<Option<Vec<SelectedValue>>> {
+    const NEW_TAGS: &str = "+ New tags…";
+
+    let mut names: Vec<&str> = options.iter().map(|o| o.name.as_str()).collect();
+    names.push(NEW_TAGS);
+    let checked: Vec<usize> = options
+        .iter()
+        .enumerate()
+        .filter(|(_, o)| {
+            selected
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(&o.name))
+        })
+        .map(|(i, _)| i)
+        .collect();
+
+    let Some(choices) = inquire::MultiSelect::new(message, names)
+        .with_default(&checked)
+        .with_help_message("Esc to skip")
+        .prompt_skippable()?
+    else {
+        return Ok(None);
+    };
+
+    let mut tags: Vec<String> = choices
+        .iter()
+        .filter(|choice| **choice != NEW_TAGS)
+        .map(|choice| choice.to_string())
+        .collect();
+    if choices.contains(&NEW_TAGS) {
+        if let Some(new) = inquire::Text::new("New tags:")
+            .with_help_message("Separated by commas")
+            .prompt_skippable()?
+        {
+            tags.extend(new.split(',').map(String::from));
+        }
+    }
+
+    Ok(Some(tag_values(options, &tags)))
+}
+
+/// Prompts for a value of an arbitrary database property.
+///
+/// Returns `None` when the prompt was skipped or the property type can't be
+/// written to (formulas, rollups, timestamps, ...).
+pub fn prompt_property(
+    name: &str,
+    config: &PropertyConfiguration,
+) -> Result<Option<PropertyValue>> {
+    let message = format!("{}:", name);
+
+    let value = match config {
+        PropertyConfiguration::Checkbox { id } => inquire::Confirm::new(&message)
+            .with_default(false)
+            .prompt_skippable()?
+            .map(|checkbox| PropertyValue::Checkbox {
+                id: id.clone(),
+                checkbox,
+            }),
+        PropertyConfiguration::Select { id, select } => {
+            let names: Vec<&str> = select.options.iter().map(|o| o.name.as_str()).collect();
+
+            inquire::Select::new(&message, names)
+                .with_help_message("Esc to skip")
+                .prompt_skippable()?
+                .and_then(|choice| select.options.iter().find(|o| o.name == choice))
+                .map(|option| PropertyValue::Select {
+                    id: id.clone(),
+                    select: Some(selected_value(option)),
+                })
+        }
+        PropertyConfiguration::MultiSelect { id, multi_select } => {
+            let names: Vec<&str> = multi_select
+                .options
+                .iter()
+                .map(|o| o.name.as_str())
+                .collect();
+
+            inquire::MultiSelect::new(&message, names)
+                .with_help_message("Esc to skip")
+                .raw_prompt_skippable()?
+                .filter(|choices| !choices.is_empty())
+                .map(|choices| PropertyValue::MultiSelect {
+                    id: id.clone(),
+                    multi_select: Some(
+                        choices
+                            .iter()
+                            .map(|choice| selected_value(&multi_select.options[choice.index]))
+                            .collect(),
+                    ),
+                })
+        }
+        PropertyConfiguration::Url { id } => {
+            skippable_text(&message)?.map(|url| PropertyValue::Url {
+                id: id.clone(),
+                url: Some(url),
+            })
+        }
+        PropertyConfiguration::Text { id } => {
+            skippable_text(&message)?.map(|text| PropertyValue::Text {
+                id: id.clone(),
+                rich_text: rich_text(&text),
+            })
+        }
+        PropertyConfiguration::Number { id, .. } => inquire::Text::new(&message)
+            .with_help_message("Leave empty to skip")
+            .with_validator(|input: &str| {
+                if input.trim().is_empty() {
+                    return Ok(inquire::validator::Validation::Valid);
+                }
+
+                Ok(match crate::calc::calc(input) {
+                    Ok(_) => inquire::validator::Validation::Valid,
+                    Err(err) => inquire::validator::Validation::Invalid(err.into()),
+                })
+            })
+            .prompt_skippable()?
+            .filter(|value| !value.trim().is_empty())
+            .map(|value| crate::calc::calc(&value))
+            .transpose()?
+            .map(|number| PropertyValue::Number {
+                id: id.clone(),
+                number: number.to_string().parse().ok(),
+            }),
+        _ => None,
+    };
+
+    Ok(value)
+}
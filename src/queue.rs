@@ -0,0 +1,274 @@
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+
+/// A single expense as it was written to the local journal.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Entry {
+    pub created_at: notion::chrono::DateTime<notion::chrono::Utc>,
+    pub properties: notion::models::Properties,
+    #[serde(default)]
+    pub uploaded: bool,
+    /// Added to the content of the page once it's created.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub items: Vec<crate::receipt::LineItem>,
+    /// Receipt uploaded to the page once it's created.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attachment: Option<PathBuf>,
+}
+
+impl Entry {
+    pub fn new(properties: notion::models::Properties) -> Self {
+        Self {
+            created_at: notion::chrono::Utc::now(),
+            properties,
+            uploaded: false,
+            items: vec![],
+            attachment: None,
+        }
+    }
+}
+
+/// The fields of an entry that was left unfinished.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Draft {
+    pub saved_at: notion::chrono::DateTime<notion::chrono::Utc>,
+    pub fields: crate::cli::AddArgs,
+}
+
+/// Inputs the prompt history keeps of each kind at most.
+const PROMPT_HISTORY_SIZE: usize = 100;
+
+/// What was typed at the name and amount prompts, newest first, so later
+/// sessions can offer it again.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PromptHistory {
+    #[serde(default)]
+    pub names: Vec<String>,
+    #[serde(default)]
+    pub amounts: Vec<String>,
+}
+
+/// Puts `input` first in `inputs`, dropping its earlier copy.
+fn remember(inputs: &mut Vec<String>, input: &str) {
+    let input = input.trim();
+    if input.is_empty() {
+        return;
+    }
+
+    inputs.retain(|item| item != input);
+    inputs.insert(0, input.to_string());
+    inputs.truncate(PROMPT_HISTORY_SIZE);
+}
+
+impl PromptHistory {
+    pub fn add_name(&mut self, name: &str) {
+        remember(&mut self.names, name);
+    }
+
+    pub fn add_amount(&mut self, amount: &str) {
+        remember(&mut self.amounts, amount);
+    }
+}
+
+/// Every entry is appended to a JSON-lines journal before it is sent to Notion,
+/// so nothing is lost while offline.
+pub struct Queue {
+    dir: PathBuf,
+    /// Refuses new entries, which the next sync would upload.
+    read_only: bool,
+}
+
+impl Queue {
+    /// Each profile, and the sandbox, has its own journal.
+    pub fn new(settings: &crate::settings::Settings) -> Result<Self> {
+        Ok(Self {
+            dir: settings.data_dir()?,
+            read_only: settings.read_only,
+        })
+    }
+
+    fn journal_path(&self) -> PathBuf {
+        self.dir.join("journal.jsonl")
+    }
+
+    /// Each database has its own schema file.
+    fn database_path(&self, database_id: &notion::ids::DatabaseId) -> PathBuf {
+        self.dir.join(format!(
+            "database-{}.json",
+            database_id.to_string().replace('-', "")
+        ))
+    }
+
+    fn draft_path(&self) -> PathBuf {
+        self.dir.join("draft.json")
+    }
+
+    fn prompt_history_path(&self) -> PathBuf {
+        self.dir.join("history.json")
+    }
+
+    pub fn push(&self, entry: &Entry) -> Result<()> {
+        if self.read_only {
+            return Err(crate::settings::read_only_error());
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.journal_path())?;
+
+        writeln!(file, "{}", serde_json::to_string(entry)?)?;
+
+        Ok(())
+    }
+
+    pub fn entries(&self) -> Result<Vec<Entry>> {
+        let path = self.journal_path();
+        if !path.exists() {
+            return Ok(vec![]);
+        }
+
+        BufReader::new(File::open(path)?)
+            .lines()
+            .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+            .map(|line| Ok(serde_json::from_str(&line?)?))
+            .collect()
+    }
+
+    pub fn pending(&self) -> Result<Vec<Entry>> {
+        Ok(self
+            .entries()?
+            .into_iter()
+            .filter(|entry| !entry.uploaded)
+            .collect())
+    }
+
+    pub fn mark_uploaded(&self, entry: &Entry) -> Result<()> {
+        let mut entries = self.entries()?;
+
+        for item in entries.iter_mut() {
+            if item.created_at == entry.created_at {
+                item.uploaded = true;
+            }
+        }
+
+        self.save(&entries)
+    }
+
+    fn save(&self, entries: &[Entry]) -> Result<()> {
+        let tmp_path = self.journal_path().with_extension("jsonl.tmp");
+        let mut file = File::create(&tmp_path)?;
+
+        for entry in entries {
+            writeln!(file, "{}", serde_json::to_string(entry)?)?;
+        }
+
+        std::fs::rename(tmp_path, self.journal_path())?;
+
+        Ok(())
+    }
+
+    /// Keeps the last known database schema so entries can be prompted for offline.
+    pub fn store_database(&self, db: &notion::models::Database) -> Result<()> {
+        let file = File::create(self.database_path(&db.id))?;
+        serde_json::to_writer(file, db)?;
+
+        Ok(())
+    }
+
+    /// Keeps an unfinished entry until the next session offers to resume it.
+    pub fn store_draft(&self, fields: &crate::cli::AddArgs) -> Result<()> {
+        let draft = Draft {
+            saved_at: notion::chrono::Utc::now(),
+            fields: fields.clone(),
+        };
+        let file = File::create(self.draft_path())?;
+        serde_json::to_writer(file, &draft)?;
+
+        Ok(())
+    }
+
+    pub fn clear_draft(&self) -> Result<()> {
+        match std::fs::remove_file(self.draft_path()) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// The stored draft, which is removed so it's only offered once. A draft
+    /// that can't be read is dropped.
+    pub fn take_draft(&self) -> Result<Option<Draft>> {
+        let path = self.draft_path();
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let draft = serde_json::from_reader(File::open(&path)?).ok();
+        std::fs::remove_file(path)?;
+
+        Ok(draft)
+    }
+
+    /// What was typed at the prompts before, empty when it can't be read.
+    pub fn prompt_history(&self) -> PromptHistory {
+        File::open(self.prompt_history_path())
+            .ok()
+            .and_then(|file| serde_json::from_reader(file).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn store_prompt_history(&self, history: &PromptHistory) -> Result<()> {
+        let file = File::create(self.prompt_history_path())?;
+        serde_json::to_writer(file, history)?;
+
+        Ok(())
+    }
+
+    pub fn cached_database(
+        &self,
+        database_id: &notion::ids::DatabaseId,
+    ) -> Option<notion::models::Database> {
+        File::open(self.database_path(database_id))
+            .ok()
+            .and_then(|file| serde_json::from_reader::<_, notion::models::Database>(file).ok())
+    }
+}
+
+#[test]
+fn entry_roundtrip_test() {
+    let properties: notion::models::Properties = serde_json::from_value(serde_json::json!({
+        "Name": {
+            "id": "title",
+            "type": "title",
+            "title": [{"type": "text", "plain_text": "Coffee", "text": {"content": "Coffee", "link": null}}]
+        },
+        "Amount": {"id": "a", "type": "number", "number": 4.5},
+        "Date": {"id": "d", "type": "date", "date": {"start": "2024-05-02", "end": null, "time_zone": null}}
+    }))
+    .unwrap();
+
+    let entry = Entry::new(properties);
+    let line = serde_json::to_string(&entry).unwrap();
+    let parsed: Entry = serde_json::from_str(&line).unwrap();
+
+    assert_eq!(parsed.created_at, entry.created_at);
+    assert_eq!(parsed.properties, entry.properties);
+    assert!(!parsed.uploaded);
+}
+
+#[test]
+fn prompt_history_test() {
+    let mut history = PromptHistory::default();
+    for amount in ["12.50", "lunch+2", " 12.50 ", ""] {
+        history.add_amount(amount);
+    }
+    assert_eq!(history.amounts, ["12.50", "lunch+2"]);
+
+    for i in 0..=PROMPT_HISTORY_SIZE {
+        history.add_name(&format!("Coffee {}", i));
+    }
+    assert_eq!(history.names.len(), PROMPT_HISTORY_SIZE);
+    assert_eq!(history.names[0], format!("Coffee {}", PROMPT_HISTORY_SIZE));
+}
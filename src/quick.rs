@@ -0,0 +1,160 @@
+use notion::chrono::{Datelike, NaiveDate};
+
+fn parse_date(word: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let word = word.to_lowercase();
+    match word.as_str() {
+        "today" => return Some(today),
+        "yesterday" => return today.pred_opt(),
+        _ => {}
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(&word, "%Y-%m-%d") {
+        return Some(date);
+    }
+
+    // The latest such weekday, today included.
+    let weekday: notion::chrono::Weekday = word.parse().ok()?;
+    let days_back =
+        (today.weekday().num_days_from_monday() + 7 - weekday.num_days_from_monday()) % 7;
+    today.checked_sub_days(notion::chrono::Days::new(days_back.into()))
+}
+
+fn is_amount(word: &str) -> bool {
+    word.chars().any(|c| c.is_ascii_digit()) && crate::calc::calc(word).is_ok()
+}
+
+/// Splits a one-line entry like `coffee 4.5 yesterday #food` into the title,
+/// the amount (the last word that is an amount expression), a date keyword and
+/// a `#category`. Whatever isn't found, or is given twice, is left for the
+/// prompts.
+pub fn parse_line(line: &str, today: NaiveDate) -> crate::cli::AddArgs {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let mut dates = vec![];
+    let mut categories = vec![];
+    let mut rest = vec![];
+
+    for word in &words {
+        if let Some(category) = word.strip_prefix('#').filter(|c| !c.is_empty()) {
+            categories.push(category.to_string());
+        } else if let Some(date) = parse_date(word, today) {
+            dates.push(date);
+        } else {
+            rest.push(*word);
+        }
+    }
+
+    // Only the last number is the amount, the others are part of the title
+    // like in `2 bagels 5`.
+    let amount = rest.iter().rposition(|word| is_amount(word));
+    let title = rest
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| Some(*i) != amount)
+        .map(|(_, word)| *word)
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    crate::cli::AddArgs {
+        name: (!title.is_empty()).then_some(title),
+        amount: amount.map(|i| rest[i].to_string()),
+        date: (dates.len() == 1).then(|| dates[0]),
+        category: (categories.len() == 1).then(|| categories.remove(0)),
+        ..Default::default()
+    }
+}
+
+fn is_currency(word: &str) -> bool {
+    [
+        "€", "$", "£", "¥", "₹", "₽", "EUR", "USD", "GBP", "CHF", "JPY", "SEK", "NOK", "DKK", "PLN",
+    ]
+    .contains(&word)
+}
+
+/// `word` without a currency sign or code and trailing punctuation, if that
+/// leaves an amount, like `€12.50,` or `12.50EUR`.
+fn copied_amount(word: &str) -> Option<&str> {
+    let amount = word
+        .trim_end_matches(['.', ',', ';', ':', '!', ')'])
+        .trim_start_matches('(')
+        .trim_matches(|c: char| c.is_alphabetic() || "€$£¥₹₽".contains(c));
+    is_amount(amount).then_some(amount)
+}
+
+/// Like `parse_line`, for text copied from elsewhere such as a bank's
+/// notification, `Card payment of €12.50 at Green Grocer on 8 May`. The first
+/// amount is taken, currency and all, and the words after `at` until `on`
+/// or the end of the sentence are the name.
+pub fn parse_copied(text: &str, today: NaiveDate) -> crate::cli::AddArgs {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let Some(at) = words
+        .iter()
+        .position(|word| word.eq_ignore_ascii_case("at"))
+    else {
+        let line: Vec<&str> = words
+            .iter()
+            .filter(|word| !is_currency(word))
+            .map(|word| copied_amount(word).unwrap_or(word))
+            .collect();
+        return parse_line(&line.join(" "), today);
+    };
+
+    let mut name = vec![];
+    for word in &words[at + 1..] {
+        if word.eq_ignore_ascii_case("on") || copied_amount(word).is_some() {
+            break;
+        }
+        let trimmed = word.trim_end_matches(['.', ',', ';', '!']);
+        name.push(trimmed);
+        if trimmed.len() != word.len() {
+            break;
+        }
+    }
+
+    crate::cli::AddArgs {
+        name: (!name.is_empty()).then(|| name.join(" ")),
+        amount: words
+            .iter()
+            .find_map(|word| copied_amount(word))
+            .map(String::from),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn parse_line_test() {
+    let today = NaiveDate::from_ymd_opt(2024, 5, 8).unwrap(); // A Wednesday.
+
+    let args = parse_line("coffee 4.5 yesterday #food", today);
+    assert_eq!(args.name.as_deref(), Some("coffee"));
+    assert_eq!(args.amount.as_deref(), Some("4.5"));
+    assert_eq!(args.date, NaiveDate::from_ymd_opt(2024, 5, 7));
+    assert_eq!(args.category.as_deref(), Some("food"));
+
+    let args = parse_line("5 bagels 5 monday", today);
+    assert_eq!(args.name.as_deref(), Some("5 bagels"));
+    assert_eq!(args.amount.as_deref(), Some("5"));
+    assert_eq!(args.date, NaiveDate::from_ymd_opt(2024, 5, 6));
+    assert_eq!(args.category, None);
+
+    let args = parse_line("rent 2024-05-01 #home #bills", today);
+    assert_eq!(args.name.as_deref(), Some("rent"));
+    assert_eq!(args.amount, None);
+    assert_eq!(args.date, NaiveDate::from_ymd_opt(2024, 5, 1));
+    assert_eq!(args.category, None);
+}
+
+#[test]
+fn parse_copied_test() {
+    let today = NaiveDate::from_ymd_opt(2024, 5, 8).unwrap();
+
+    let args = parse_copied(
+        "Card payment of €12,50 at Green Grocer on 8 May. Balance: €230.10",
+        today,
+    );
+    assert_eq!(args.name.as_deref(), Some("Green Grocer"));
+    assert_eq!(args.amount.as_deref(), Some("12,50"));
+
+    let args = parse_copied("Netflix\n15.99 USD", today);
+    assert_eq!(args.name.as_deref(), Some("Netflix"));
+    assert_eq!(args.amount.as_deref(), Some("15.99"));
+}
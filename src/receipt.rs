@@ -0,0 +1,93 @@
+use eyre::{eyre, Result};
+use rust_decimal::Decimal;
+
+/// A line of an itemized receipt.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct LineItem {
+    pub name: String,
+    #[serde(with = "rust_decimal::serde::float")]
+    pub price: Decimal,
+}
+
+/// Reads a line like `Milk 2.49`, or `Eggs 2*1.25`: the price is the longest
+/// end of the line `calc` takes, and the name what's before it.
+pub fn parse_item(line: &str) -> Result<LineItem> {
+    let line = line.trim();
+    let price = line
+        .char_indices()
+        .filter(|(i, c)| c.is_whitespace() && *i > 0)
+        .find_map(|(i, _)| {
+            let name = line[..i].trim();
+            let price = crate::calc::calc(&line[i..]).ok()?;
+            Some((name, price))
+        });
+
+    match price {
+        Some((name, price)) => Ok(LineItem {
+            name: name.to_string(),
+            price,
+        }),
+        None => Err(eyre!("Expected a name and a price, like `Milk 2.49`")),
+    }
+}
+
+pub fn total(items: &[LineItem]) -> Decimal {
+    items.iter().map(|item| item.price).sum()
+}
+
+/// Checks that `items` add up to `amount`, to the cent.
+pub fn check_total(items: &[LineItem], amount: Decimal) -> Result<()> {
+    let total = total(items);
+    if !crate::calc::round(total - amount, 2).is_zero() {
+        return Err(eyre!(
+            "The items add up to {:.2}, not {:.2}",
+            crate::calc::round(total, 2),
+            crate::calc::round(amount, 2)
+        ));
+    }
+
+    Ok(())
+}
+
+/// `items` as a bulleted list, for the content of the entry.
+pub fn blocks(items: &[LineItem]) -> Vec<serde_json::Value> {
+    items
+        .iter()
+        .map(|item| {
+            serde_json::json!({
+                "object": "block",
+                "type": "bulleted_list_item",
+                "bulleted_list_item": {
+                    "rich_text": crate::report::rich_text(&format!(
+                        "{} — {}",
+                        item.name,
+                        crate::report::format_amount(crate::calc::to_f64(item.price))
+                    )),
+                },
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn parse_item_test() {
+    let item = |name: &str, price: &str| LineItem {
+        name: name.to_string(),
+        price: price.parse().unwrap(),
+    };
+
+    assert_eq!(parse_item("Milk 2.49").unwrap(), item("Milk", "2.49"));
+    assert_eq!(parse_item("Eggs 2 * 1.25").unwrap(), item("Eggs", "2.5"));
+    assert_eq!(parse_item("Coke 2l 1,99").unwrap(), item("Coke 2l", "1.99"));
+    assert!(parse_item("Milk").is_err());
+    assert!(parse_item("2.49").is_err());
+
+    let items = [item("Milk", "2.49"), item("Eggs", "2.5")];
+    assert!(check_total(&items, "4.99".parse().unwrap()).is_ok());
+    assert_eq!(
+        check_total(&items, "5.99".parse().unwrap())
+            .unwrap_err()
+            .to_string(),
+        "The items add up to 4.99, not 5.99"
+    );
+}
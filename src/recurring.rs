@@ -0,0 +1,74 @@
+use crate::settings::RecurringExpense;
+use notion::chrono::{Datelike, NaiveDate};
+
+/// The date `expense` falls on in the month of `today`, if it's due by then.
+pub fn due_date(expense: &RecurringExpense, today: NaiveDate) -> Option<NaiveDate> {
+    let month = crate::report::month_start(today);
+    let last_day = crate::report::next_month(month).pred_opt().unwrap().day();
+    let date = month.with_day(expense.day.clamp(1, last_day)).unwrap();
+
+    (date <= today).then_some(date)
+}
+
+/// The expenses due this month along with their dates, leaving out those
+/// already in `entries`.
+pub fn due<'a>(
+    expenses: &'a [RecurringExpense],
+    today: NaiveDate,
+    entries: &[notion::models::Page],
+) -> Vec<(&'a RecurringExpense, NaiveDate)> {
+    expenses
+        .iter()
+        .filter_map(|expense| due_date(expense, today).map(|date| (expense, date)))
+        .filter(|(expense, _)| {
+            !entries.iter().any(|page| {
+                page.title()
+                    .is_some_and(|title| title.trim().eq_ignore_ascii_case(expense.name.trim()))
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn due_test() {
+    let expense = |name: &str, day: u32| RecurringExpense {
+        name: name.to_string(),
+        amount: rust_decimal::Decimal::TEN,
+        category: "Home".to_string(),
+        day,
+    };
+    let rent = crate::report::page(serde_json::json!({
+        "Name": {"id": "title", "type": "title", "title": [
+            {"type": "text", "plain_text": "Rent", "text": {"content": "Rent", "link": null}}
+        ]},
+    }));
+
+    let expenses = [
+        expense("Rent", 1),
+        expense("Gym", 10),
+        expense("Insurance", 31),
+        expense("Phone", 20),
+    ];
+    let today = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+
+    let due: Vec<(&str, NaiveDate)> = due(&expenses, today, &[rent])
+        .into_iter()
+        .map(|(expense, date)| (expense.name.as_str(), date))
+        .collect();
+    assert_eq!(
+        due,
+        vec![
+            ("Gym", NaiveDate::from_ymd_opt(2024, 2, 10).unwrap()),
+            ("Insurance", NaiveDate::from_ymd_opt(2024, 2, 29).unwrap()),
+            ("Phone", NaiveDate::from_ymd_opt(2024, 2, 20).unwrap()),
+        ]
+    );
+
+    assert_eq!(
+        due_date(
+            &expense("Phone", 20),
+            NaiveDate::from_ymd_opt(2024, 2, 19).unwrap()
+        ),
+        None
+    );
+}
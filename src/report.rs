@@ -0,0 +1,2680 @@
+use notion::chrono::{Datelike, NaiveDate};
+use std::collections::HashMap;
+
+pub fn month_start(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap()
+}
+
+pub fn next_month(month: NaiveDate) -> NaiveDate {
+    month_start(month)
+        .checked_add_months(notion::chrono::Months::new(1))
+        .unwrap()
+}
+
+/// All entries with `date_property` within the month `month` belongs to.
+pub fn month_query(date_property: &str, month: NaiveDate) -> crate::backend::Query {
+    let from = month_start(month);
+
+    crate::backend::Query {
+        filter: Some(crate::backend::Filter::date_range(
+            date_property,
+            from,
+            next_month(from),
+        )),
+        sorts: Some(crate::backend::descending(date_property)),
+        paging: None,
+    }
+}
+
+/// All entries within the `months` months up to the one `today` belongs to.
+pub fn months_query(date_property: &str, today: NaiveDate, months: u32) -> crate::backend::Query {
+    let from = month_start(today)
+        .checked_sub_months(notion::chrono::Months::new(months.saturating_sub(1)))
+        .unwrap();
+
+    crate::backend::Query {
+        filter: Some(crate::backend::Filter::date_range(
+            date_property,
+            from,
+            next_month(today),
+        )),
+        sorts: Some(crate::backend::descending(date_property)),
+        paging: None,
+    }
+}
+
+pub fn page_amount(page: &notion::models::Page, property: &str) -> Option<f64> {
+    match page.properties.properties.get(property) {
+        Some(notion::models::properties::PropertyValue::Number {
+            number: Some(number),
+            ..
+        }) => number.as_f64(),
+        _ => None,
+    }
+}
+
+pub fn page_date(page: &notion::models::Page, property: &str) -> Option<NaiveDate> {
+    match page.properties.properties.get(property) {
+        Some(notion::models::properties::PropertyValue::Date {
+            date: Some(date), ..
+        }) => match date.start {
+            notion::models::properties::DateOrDateTime::Date(date) => Some(date),
+            notion::models::properties::DateOrDateTime::DateTime(date_time) => {
+                Some(date_time.with_timezone(&notion::chrono::Local).date_naive())
+            }
+        },
+        _ => None,
+    }
+}
+
+pub fn page_relation_ids(page: &notion::models::Page, property: &str) -> Vec<notion::ids::PageId> {
+    match page.properties.properties.get(property) {
+        Some(notion::models::properties::PropertyValue::Relation {
+            relation: Some(relation),
+            ..
+        }) => relation.iter().map(|value| value.id.clone()).collect(),
+        _ => vec![],
+    }
+}
+
+pub fn page_select_name(page: &notion::models::Page, property: &str) -> Option<String> {
+    match page.properties.properties.get(property) {
+        Some(notion::models::properties::PropertyValue::Select {
+            select: Some(select),
+            ..
+        }) => select.name.clone(),
+        _ => None,
+    }
+}
+
+pub fn page_checkbox(page: &notion::models::Page, property: &str) -> bool {
+    matches!(
+        page.properties.properties.get(property),
+        Some(notion::models::properties::PropertyValue::Checkbox { checkbox: true, .. })
+    )
+}
+
+/// The name of the selected option, or of the first person of a people property.
+pub fn payer_name(value: &notion::models::properties::PropertyValue) -> Option<String> {
+    use notion::models::users::User;
+
+    match value {
+        notion::models::properties::PropertyValue::Select {
+            select: Some(select),
+            ..
+        } => select.name.clone(),
+        notion::models::properties::PropertyValue::People { people, .. } => {
+            people.first().and_then(|user| match user {
+                User::Person { common, .. } | User::Bot { common, .. } => common.name.clone(),
+            })
+        }
+        _ => None,
+    }
+}
+
+pub fn page_payer(page: &notion::models::Page, property: &str) -> Option<String> {
+    page.properties
+        .properties
+        .get(property)
+        .and_then(payer_name)
+}
+
+/// Titles of the category pages by id.
+pub fn category_names(
+    categories: &[notion::models::Page],
+) -> HashMap<&notion::ids::PageId, String> {
+    categories
+        .iter()
+        .map(|page| (&page.id, page.title().unwrap_or("Untitled".to_string())))
+        .collect()
+}
+
+/// Name of the category of `page`, whether it's a relation or a select.
+pub fn page_category(
+    page: &notion::models::Page,
+    property: &str,
+    names: &HashMap<&notion::ids::PageId, String>,
+) -> Option<String> {
+    page_relation_ids(page, property)
+        .first()
+        .and_then(|id| names.get(id).cloned())
+        .or_else(|| page_select_name(page, property))
+}
+
+/// `amount` with two decimals and the thousands separated by spaces, like `1 234.56`.
+pub fn format_amount(amount: f64) -> String {
+    let formatted = format!("{:.2}", amount.abs());
+    let (whole, fraction) = formatted.split_once('.').unwrap();
+
+    let mut grouped = String::new();
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            grouped.push(' ');
+        }
+        grouped.push(digit);
+    }
+
+    let sign = if amount < 0.0 && formatted != "0.00" {
+        "-"
+    } else {
+        ""
+    };
+    format!("{}{}.{}", sign, grouped, fraction)
+}
+
+#[test]
+fn format_amount_test() {
+    assert_eq!(format_amount(1234.564), "1 234.56");
+    assert_eq!(format_amount(999.0), "999.00");
+    assert_eq!(format_amount(-1234567.0), "-1 234 567.00");
+    assert_eq!(format_amount(-0.001), "0.00");
+}
+
+/// Options of the Type select. Entries without a type are expenses.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EntryType {
+    Expense,
+    Income,
+    Transfer,
+}
+
+impl EntryType {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Expense => "Expense",
+            Self::Income => "Income",
+            Self::Transfer => "Transfer",
+        }
+    }
+
+    pub fn of(page: &notion::models::Page, property: &str) -> Self {
+        Self::named(page_select_name(page, property).as_deref())
+    }
+
+    /// The type with the option name `name`, case-insensitively.
+    pub fn named(name: Option<&str>) -> Self {
+        match name.map(str::to_lowercase) {
+            Some(name) if name == "income" => Self::Income,
+            Some(name) if name == "transfer" => Self::Transfer,
+            _ => Self::Expense,
+        }
+    }
+}
+
+/// Expense totals per category for a single month, along with the income.
+/// Transfers count towards neither.
+pub struct MonthReport {
+    month: NaiveDate,
+    totals: Vec<(String, f64)>,
+    total: f64,
+    income: f64,
+}
+
+impl MonthReport {
+    pub fn new(
+        month: NaiveDate,
+        entries: &[notion::models::Page],
+        categories: &[notion::models::Page],
+        properties: &crate::settings::PropertyNames,
+    ) -> Self {
+        let names = category_names(categories);
+
+        let mut totals: HashMap<String, f64> = HashMap::new();
+        let mut income = 0.0;
+        for entry in entries {
+            let amount = page_amount(entry, &properties.amount).unwrap_or(0.0);
+            match EntryType::of(entry, &properties.entry_type) {
+                EntryType::Expense => {}
+                EntryType::Income => {
+                    income += amount;
+                    continue;
+                }
+                EntryType::Transfer => continue,
+            }
+
+            let category = page_category(entry, &properties.category, &names)
+                .unwrap_or("Uncategorized".to_string());
+
+            *totals.entry(category).or_default() += amount;
+        }
+
+        let mut totals: Vec<(String, f64)> = totals.into_iter().collect();
+        totals.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        Self {
+            month: month_start(month),
+            total: totals.iter().map(|(_, amount)| amount).sum(),
+            totals,
+            income,
+        }
+    }
+}
+
+impl MonthReport {
+    /// Sum of the month's expenses.
+    pub fn total(&self) -> f64 {
+        self.total
+    }
+
+    pub fn income(&self) -> f64 {
+        self.income
+    }
+
+    /// Expenses per category, largest first.
+    pub fn totals(&self) -> &[(String, f64)] {
+        &self.totals
+    }
+
+    /// The month's spending so far extrapolated to its end, when `today` is
+    /// in it, against `budgets` by category name.
+    pub fn forecast(&self, today: NaiveDate, budgets: &HashMap<String, f64>) -> Option<Forecast> {
+        if month_start(today) != self.month {
+            return None;
+        }
+
+        let days = (next_month(self.month) - self.month).num_days() as u32;
+        let scale = days as f64 / today.day() as f64;
+        let mut budgets: Vec<(String, f64, f64)> = budgets
+            .iter()
+            .map(|(name, limit)| {
+                let spent = self
+                    .totals
+                    .iter()
+                    .find(|(category, _)| category.eq_ignore_ascii_case(name))
+                    .map_or(0.0, |(_, amount)| *amount);
+                (name.clone(), spent * scale, *limit)
+            })
+            .collect();
+        budgets.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Some(Forecast {
+            month: self.month,
+            elapsed: today.day(),
+            days,
+            spent: self.total,
+            projected: self.total * scale,
+            budgets,
+        })
+    }
+
+    pub fn category_total(&self, category: &str) -> f64 {
+        self.totals
+            .iter()
+            .find(|(name, _)| name == category)
+            .map_or(0.0, |(_, amount)| *amount)
+    }
+
+    /// Horizontal bars of the category totals, scaled so the largest fills
+    /// the `width` left after the names, with each category's share of the
+    /// total. Empty when nothing was spent.
+    pub fn chart(&self, width: usize) -> String {
+        if self.total <= 0.0 {
+            return String::new();
+        }
+
+        let name_width = self
+            .totals
+            .iter()
+            .map(|(name, _)| name.chars().count())
+            .max()
+            .unwrap_or(0);
+        let bar_width = width.saturating_sub(name_width + 7).max(10);
+        let largest = self.totals.first().map_or(0.0, |(_, amount)| *amount);
+
+        self.totals
+            .iter()
+            .map(|(name, amount)| {
+                let amount = amount.max(0.0);
+                format!(
+                    "{:<name_width$}  {:<bar_width$} {:>3.0}%",
+                    name,
+                    bar(amount / largest, bar_width),
+                    amount / self.total * 100.0,
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "month": self.month.format("%Y-%m").to_string(),
+            "categories": self
+                .totals
+                .iter()
+                .map(|(name, total)| serde_json::json!({"name": name, "total": total}))
+                .collect::<Vec<_>>(),
+            "total": self.total,
+            "income": self.income,
+        })
+    }
+}
+
+impl std::fmt::Display for MonthReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let width = self
+            .totals
+            .iter()
+            .map(|(name, _)| name.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(
+                if self.income != 0.0 {
+                    "Income"
+                } else {
+                    "Total"
+                }
+                .len(),
+            );
+
+        writeln!(f, "{}", self.month.format("%B %Y"))?;
+        for (name, amount) in &self.totals {
+            writeln!(f, "{:<width$}  {:>10.2}", name, amount, width = width)?;
+        }
+        write!(
+            f,
+            "{:<width$}  {:>10.2}",
+            "Total",
+            self.total,
+            width = width
+        )?;
+
+        if self.income != 0.0 {
+            write!(
+                f,
+                "\n{:<width$}  {:>10.2}\n{:<width$}  {:>10.2}",
+                "Income",
+                self.income,
+                "Net",
+                self.income - self.total,
+                width = width
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A bar of `fraction` of `width` characters, in eighths of a character.
+fn bar(fraction: f64, width: usize) -> String {
+    let eighths = (fraction.clamp(0.0, 1.0) * width as f64 * 8.0).round() as usize;
+    let mut bar = "█".repeat(eighths / 8);
+    match eighths % 8 {
+        0 => {}
+        partial => bar.push(['▏', '▎', '▍', '▌', '▋', '▊', '▉'][partial - 1]),
+    }
+    bar
+}
+
+/// One block per value, as high as its share of the largest one.
+fn sparkline(values: &[f64]) -> String {
+    const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let largest = values.iter().copied().fold(0.0, f64::max);
+
+    values
+        .iter()
+        .map(|value| match largest > 0.0 {
+            true => LEVELS[(value.max(0.0) / largest * 7.0).round() as usize],
+            false => LEVELS[0],
+        })
+        .collect()
+}
+
+/// Expense totals per month over a run of months, overall and for the
+/// selected categories.
+pub struct TrendReport {
+    /// The first day of each month, oldest first.
+    months: Vec<NaiveDate>,
+    totals: Vec<f64>,
+    categories: Vec<(String, Vec<f64>)>,
+}
+
+impl TrendReport {
+    /// How long the bars of the monthly totals are at most.
+    const BAR_WIDTH: usize = 30;
+
+    pub fn new(
+        today: NaiveDate,
+        months: u32,
+        entries: &[notion::models::Page],
+        categories: &[notion::models::Page],
+        selected: &[String],
+        properties: &crate::settings::PropertyNames,
+    ) -> Self {
+        let names = category_names(categories);
+        let months: Vec<NaiveDate> = (0..months.max(1))
+            .rev()
+            .map(|back| {
+                month_start(today)
+                    .checked_sub_months(notion::chrono::Months::new(back))
+                    .unwrap()
+            })
+            .collect();
+
+        let mut totals = vec![0.0; months.len()];
+        let mut category_totals = vec![vec![0.0; months.len()]; selected.len()];
+        for entry in entries {
+            if EntryType::of(entry, &properties.entry_type) != EntryType::Expense {
+                continue;
+            }
+            let Some(index) = page_date(entry, &properties.date)
+                .and_then(|date| months.iter().position(|month| *month == month_start(date)))
+            else {
+                continue;
+            };
+            let amount = page_amount(entry, &properties.amount).unwrap_or(0.0);
+            totals[index] += amount;
+
+            let category = page_category(entry, &properties.category, &names)
+                .unwrap_or("Uncategorized".to_string());
+            for (name, category_totals) in selected.iter().zip(&mut category_totals) {
+                if name.eq_ignore_ascii_case(&category) {
+                    category_totals[index] += amount;
+                }
+            }
+        }
+
+        Self {
+            months,
+            totals,
+            categories: selected.iter().cloned().zip(category_totals).collect(),
+        }
+    }
+
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "months": self
+                .months
+                .iter()
+                .enumerate()
+                .map(|(index, month)| serde_json::json!({
+                    "month": month.format("%Y-%m").to_string(),
+                    "total": self.totals[index],
+                    "categories": self
+                        .categories
+                        .iter()
+                        .map(|(name, totals)| (name.clone(), totals[index].into()))
+                        .collect::<serde_json::Map<String, serde_json::Value>>(),
+                }))
+                .collect::<Vec<_>>(),
+        })
+    }
+}
+
+impl std::fmt::Display for TrendReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let largest = self.totals.iter().copied().fold(0.0, f64::max);
+        for (month, total) in self.months.iter().zip(&self.totals) {
+            let fraction = match largest > 0.0 {
+                true => total / largest,
+                false => 0.0,
+            };
+            writeln!(
+                f,
+                "{}  {:<width$}  {:>10.2}",
+                month.format("%b %Y"),
+                bar(fraction, Self::BAR_WIDTH),
+                total,
+                width = Self::BAR_WIDTH
+            )?;
+        }
+
+        let width = self
+            .categories
+            .iter()
+            .map(|(name, _)| name.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max("Total".len());
+        let lines = std::iter::once(("Total", &self.totals)).chain(
+            self.categories
+                .iter()
+                .map(|(name, totals)| (name.as_str(), totals)),
+        );
+        for (name, totals) in lines {
+            write!(
+                f,
+                "\n{:<width$}  {}  average {:.2}",
+                name,
+                sparkline(totals),
+                totals.iter().sum::<f64>() / totals.len() as f64,
+                width = width
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// `content` as Notion rich text, for the blocks sent to the API.
+pub fn rich_text(content: &str) -> serde_json::Value {
+    serde_json::json!([{ "type": "text", "text": { "content": content } }])
+}
+
+pub fn heading_block(content: &str) -> serde_json::Value {
+    serde_json::json!({
+        "object": "block",
+        "type": "heading_2",
+        "heading_2": { "rich_text": rich_text(content) },
+    })
+}
+
+fn paragraph_block(content: &str) -> serde_json::Value {
+    serde_json::json!({
+        "object": "block",
+        "type": "paragraph",
+        "paragraph": { "rich_text": rich_text(content) },
+    })
+}
+
+impl MonthReport {
+    pub fn title(&self) -> String {
+        self.month.format("%B %Y").to_string()
+    }
+
+    /// The totals as a Notion table block, followed by a paragraph per line of
+    /// the forecast.
+    pub fn blocks(&self, forecast: Option<&Forecast>) -> Vec<serde_json::Value> {
+        let mut rows = vec![("Category".to_string(), "Amount".to_string())];
+        rows.extend(
+            self.totals
+                .iter()
+                .map(|(name, amount)| (name.clone(), format_amount(*amount))),
+        );
+        rows.push(("Total".to_string(), format_amount(self.total)));
+        if self.income != 0.0 {
+            rows.push(("Income".to_string(), format_amount(self.income)));
+            rows.push(("Net".to_string(), format_amount(self.income - self.total)));
+        }
+
+        let table = serde_json::json!({
+            "object": "block",
+            "type": "table",
+            "table": {
+                "table_width": 2,
+                "has_column_header": true,
+                "has_row_header": false,
+                "children": rows
+                    .iter()
+                    .map(|(name, amount)| serde_json::json!({
+                        "object": "block",
+                        "type": "table_row",
+                        "table_row": { "cells": [rich_text(name), rich_text(amount)] },
+                    }))
+                    .collect::<Vec<_>>(),
+            },
+        });
+
+        std::iter::once(table)
+            .chain(
+                forecast
+                    .map(|forecast| forecast.to_string())
+                    .iter()
+                    .flat_map(|forecast| forecast.lines())
+                    .map(paragraph_block),
+            )
+            .collect()
+    }
+}
+
+/// Where the current month's spending is headed at its daily average so far.
+pub struct Forecast {
+    month: NaiveDate,
+    /// Days of the month so far, today included.
+    elapsed: u32,
+    days: u32,
+    spent: f64,
+    projected: f64,
+    /// Category, projected spending and budget of the categories with one.
+    budgets: Vec<(String, f64, f64)>,
+}
+
+impl Forecast {
+    pub fn daily(&self) -> f64 {
+        self.spent / self.elapsed as f64
+    }
+
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "days": self.days,
+            "elapsed": self.elapsed,
+            "daily": self.daily(),
+            "projected": self.projected,
+            "budgets": self
+                .budgets
+                .iter()
+                .map(|(name, projected, limit)| serde_json::json!({
+                    "name": name,
+                    "projected": projected,
+                    "limit": limit,
+                }))
+                .collect::<Vec<_>>(),
+        })
+    }
+}
+
+impl std::fmt::Display for Forecast {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let over = |projected: f64, limit: f64| match projected - limit {
+            over if over >= 0.005 => format!(", {:.2} over", over),
+            _ => String::new(),
+        };
+
+        write!(
+            f,
+            "{:.2} a day over {} of {} days, {:.2} projected for {}",
+            self.daily(),
+            self.elapsed,
+            self.days,
+            self.projected,
+            self.month.format("%B")
+        )?;
+        if !self.budgets.is_empty() {
+            let limit: f64 = self.budgets.iter().map(|(_, _, limit)| limit).sum();
+            write!(
+                f,
+                " against the {:.2} budgeted{}",
+                limit,
+                over(self.projected, limit)
+            )?;
+        }
+        for (name, projected, limit) in &self.budgets {
+            write!(
+                f,
+                "\n{}: {:.2} of {:.2}{}",
+                name,
+                projected,
+                limit,
+                over(*projected, *limit)
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Entries that recur monthly with a similar amount.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Subscription {
+    pub name: String,
+    /// The latest amount.
+    pub monthly: f64,
+    /// Month of each price change, with the old and new amounts.
+    pub changes: Vec<(NaiveDate, f64, f64)>,
+}
+
+/// Subscriptions among a period's entries: titles booked once a month for at
+/// least three months in a row, with each amount within half of the one
+/// before.
+pub struct SubscriptionReport {
+    subscriptions: Vec<Subscription>,
+}
+
+impl SubscriptionReport {
+    /// How many months of entries the detection looks at.
+    pub const MONTHS: u32 = 12;
+
+    /// All entries within the `MONTHS` months up to the one `today` belongs to.
+    pub fn query(date_property: &str, today: NaiveDate) -> crate::backend::Query {
+        months_query(date_property, today, Self::MONTHS)
+    }
+
+    pub fn new(
+        entries: &[notion::models::Page],
+        properties: &crate::settings::PropertyNames,
+    ) -> Self {
+        // Oldest first, so the latest title and amount win.
+        let mut groups: HashMap<String, (String, Vec<(NaiveDate, f64)>)> = HashMap::new();
+        for entry in entries.iter().rev() {
+            if EntryType::of(entry, &properties.entry_type) != EntryType::Expense {
+                continue;
+            }
+            let (Some(title), Some(date), Some(amount)) = (
+                entry.title(),
+                page_date(entry, &properties.date),
+                page_amount(entry, &properties.amount),
+            ) else {
+                continue;
+            };
+
+            let group = groups
+                .entry(title.trim().to_lowercase())
+                .or_insert_with(|| (String::new(), vec![]));
+            group.0 = title.trim().to_string();
+            group.1.push((month_start(date), amount));
+        }
+
+        let mut subscriptions: Vec<Subscription> = groups
+            .into_values()
+            .filter_map(|(name, mut months)| {
+                months.sort_by_key(|(month, _)| *month);
+
+                let monthly = months
+                    .windows(2)
+                    .all(|pair| next_month(pair[0].0) == pair[1].0);
+                let similar = months
+                    .windows(2)
+                    .all(|pair| (pair[1].1 - pair[0].1).abs() <= pair[0].1.abs() / 2.0);
+                if months.len() < 3 || !monthly || !similar {
+                    return None;
+                }
+
+                Some(Subscription {
+                    name,
+                    monthly: months.last().unwrap().1,
+                    changes: months
+                        .windows(2)
+                        .filter(|pair| (pair[1].1 - pair[0].1).abs() >= 0.005)
+                        .map(|pair| (pair[1].0, pair[0].1, pair[1].1))
+                        .collect(),
+                })
+            })
+            .collect();
+        subscriptions.sort_by(|a, b| {
+            b.monthly
+                .total_cmp(&a.monthly)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        Self { subscriptions }
+    }
+
+    pub fn json(&self) -> serde_json::Value {
+        let monthly: f64 = self.subscriptions.iter().map(|s| s.monthly).sum();
+
+        serde_json::json!({
+            "subscriptions": self
+                .subscriptions
+                .iter()
+                .map(|subscription| serde_json::json!({
+                    "name": subscription.name,
+                    "monthly": subscription.monthly,
+                    "yearly": subscription.monthly * 12.0,
+                    "changes": subscription
+                        .changes
+                        .iter()
+                        .map(|(month, from, to)| serde_json::json!({
+                            "month": month.format("%Y-%m").to_string(),
+                            "from": from,
+                            "to": to,
+                        }))
+                        .collect::<Vec<_>>(),
+                }))
+                .collect::<Vec<_>>(),
+            "monthly": monthly,
+            "yearly": monthly * 12.0,
+        })
+    }
+}
+
+impl std::fmt::Display for SubscriptionReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.subscriptions.is_empty() {
+            return write!(f, "No subscriptions found");
+        }
+
+        let width = self
+            .subscriptions
+            .iter()
+            .map(|subscription| subscription.name.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max("Total".len());
+        let monthly: f64 = self.subscriptions.iter().map(|s| s.monthly).sum();
+
+        writeln!(
+            f,
+            "{:<width$}  {:>10}  {:>10}",
+            "",
+            "Monthly",
+            "Yearly",
+            width = width
+        )?;
+        for subscription in &self.subscriptions {
+            writeln!(
+                f,
+                "{:<width$}  {:>10.2}  {:>10.2}",
+                subscription.name,
+                subscription.monthly,
+                subscription.monthly * 12.0,
+                width = width
+            )?;
+            for (month, from, to) in &subscription.changes {
+                writeln!(
+                    f,
+                    "{:<width$}  ! {:.2} → {:.2} in {}",
+                    "",
+                    from,
+                    to,
+                    month.format("%B %Y"),
+                    width = width
+                )?;
+            }
+        }
+        write!(
+            f,
+            "{:<width$}  {:>10.2}  {:>10.2}",
+            "Total",
+            monthly,
+            monthly * 12.0,
+            width = width
+        )
+    }
+}
+
+/// A year's expenses per category and month, with the most expensive ones.
+pub struct YearReport {
+    year: i32,
+    /// Totals per month of each category, the largest yearly totals first.
+    categories: Vec<(String, [f64; 12])>,
+    /// How many months the averages are over, only those so far in the
+    /// current year.
+    months: usize,
+    top: Vec<crate::cli::AddArgs>,
+}
+
+impl YearReport {
+    /// How many of the most expensive entries are listed.
+    const TOP: usize = 10;
+
+    pub fn query(date_property: &str, year: i32) -> crate::backend::Query {
+        crate::backend::Query {
+            filter: Some(crate::backend::Filter::date_range(
+                date_property,
+                NaiveDate::from_ymd_opt(year, 1, 1).unwrap(),
+                NaiveDate::from_ymd_opt(year + 1, 1, 1).unwrap(),
+            )),
+            sorts: Some(crate::backend::descending(date_property)),
+            paging: None,
+        }
+    }
+
+    pub fn new(
+        year: i32,
+        today: NaiveDate,
+        entries: &[notion::models::Page],
+        categories: &[notion::models::Page],
+        properties: &crate::settings::PropertyNames,
+    ) -> Self {
+        let names = category_names(categories);
+
+        let mut totals: HashMap<String, [f64; 12]> = HashMap::new();
+        let mut expenses = vec![];
+        for entry in entries {
+            if EntryType::of(entry, &properties.entry_type) != EntryType::Expense {
+                continue;
+            }
+            let Some(date) = page_date(entry, &properties.date).filter(|date| date.year() == year)
+            else {
+                continue;
+            };
+            let amount = page_amount(entry, &properties.amount).unwrap_or(0.0);
+            let category = page_category(entry, &properties.category, &names)
+                .unwrap_or("Uncategorized".to_string());
+
+            totals.entry(category).or_default()[date.month0() as usize] += amount;
+            expenses.push((amount, entry));
+        }
+
+        let mut categories: Vec<(String, [f64; 12])> = totals.into_iter().collect();
+        categories.sort_by(|a, b| {
+            let total = |months: &[f64; 12]| months.iter().sum::<f64>();
+            total(&b.1)
+                .total_cmp(&total(&a.1))
+                .then_with(|| a.0.cmp(&b.0))
+        });
+
+        expenses.sort_by(|a, b| b.0.total_cmp(&a.0));
+        let top = expenses
+            .into_iter()
+            .take(Self::TOP)
+            .map(|(_, entry)| page_fields(entry, &names, properties))
+            .collect();
+
+        Self {
+            year,
+            categories,
+            months: match year.cmp(&today.year()) {
+                std::cmp::Ordering::Equal => today.month() as usize,
+                _ => 12,
+            },
+            top,
+        }
+    }
+
+    fn monthly_totals(&self) -> [f64; 12] {
+        std::array::from_fn(|month| {
+            self.categories
+                .iter()
+                .map(|(_, months)| months[month])
+                .sum()
+        })
+    }
+
+    pub fn json(&self) -> serde_json::Value {
+        let months = self.monthly_totals();
+        let total: f64 = months.iter().sum();
+
+        serde_json::json!({
+            "year": self.year,
+            "categories": self
+                .categories
+                .iter()
+                .map(|(name, months)| {
+                    let total: f64 = months.iter().sum();
+                    serde_json::json!({
+                        "name": name,
+                        "months": months,
+                        "total": total,
+                        "average": total / self.months as f64,
+                    })
+                })
+                .collect::<Vec<_>>(),
+            "months": months,
+            "total": total,
+            "average": total / self.months as f64,
+            "top": self.top,
+        })
+    }
+
+    /// Categories by month, with their totals and monthly averages.
+    pub fn table(&self) -> comfy_table::Table {
+        use comfy_table::{Attribute, Cell, CellAlignment};
+
+        let row = |name: &str, months: &[f64; 12]| {
+            let total: f64 = months.iter().sum();
+            std::iter::once(name.to_string())
+                .chain(months.iter().map(|amount| match amount.abs() < 0.005 {
+                    true => String::new(),
+                    false => format!("{:.2}", amount),
+                }))
+                .chain([
+                    format!("{:.2}", total),
+                    format!("{:.2}", total / self.months as f64),
+                ])
+                .map(Cell::new)
+                .collect::<Vec<_>>()
+        };
+
+        let mut table = comfy_table::Table::new();
+        table
+            .load_style(comfy_table::presets::UTF8_FULL_CONDENSED)
+            .set_header(
+                std::iter::once("Category".to_string())
+                    .chain((1..=12).map(|month| {
+                        NaiveDate::from_ymd_opt(self.year, month, 1)
+                            .unwrap()
+                            .format("%b")
+                            .to_string()
+                    }))
+                    .chain(["Total".to_string(), "Average".to_string()])
+                    .map(|name| Cell::new(name).add_attribute(Attribute::Bold)),
+            );
+        for (name, months) in &self.categories {
+            table.add_row(row(name, months));
+        }
+        table.add_row(
+            row("Total", &self.monthly_totals())
+                .into_iter()
+                .map(|cell| cell.add_attribute(Attribute::Bold)),
+        );
+
+        for index in 1..15 {
+            if let Some(column) = table.column_mut(index) {
+                column.set_cell_alignment(CellAlignment::Right);
+            }
+        }
+
+        table
+    }
+}
+
+impl std::fmt::Display for YearReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}\n{}", self.year, self.table())?;
+        if !self.top.is_empty() {
+            write!(
+                f,
+                "\nThe {} most expensive\n{}",
+                self.top.len(),
+                fields_table(&self.top, &Default::default())
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Expense totals per category of a period next to those of the period
+/// before it.
+pub struct Comparison {
+    previous: String,
+    current: String,
+    /// Category, previous and current total, the largest current totals first.
+    rows: Vec<(String, f64, f64)>,
+}
+
+impl Comparison {
+    /// Compares the entries dated within `current`, from its first day until
+    /// the second one, with those within `previous`.
+    pub fn new(
+        (previous, current): ((NaiveDate, NaiveDate), (NaiveDate, NaiveDate)),
+        labels: (String, String),
+        entries: &[notion::models::Page],
+        categories: &[notion::models::Page],
+        properties: &crate::settings::PropertyNames,
+    ) -> Self {
+        let names = category_names(categories);
+
+        let mut totals: HashMap<String, (f64, f64)> = HashMap::new();
+        for entry in entries {
+            if EntryType::of(entry, &properties.entry_type) != EntryType::Expense {
+                continue;
+            }
+            let Some(date) = page_date(entry, &properties.date) else {
+                continue;
+            };
+            let amount = page_amount(entry, &properties.amount).unwrap_or(0.0);
+            let category = page_category(entry, &properties.category, &names)
+                .unwrap_or("Uncategorized".to_string());
+
+            if (previous.0..previous.1).contains(&date) {
+                totals.entry(category).or_default().0 += amount;
+            } else if (current.0..current.1).contains(&date) {
+                totals.entry(category).or_default().1 += amount;
+            }
+        }
+
+        let mut rows: Vec<(String, f64, f64)> = totals
+            .into_iter()
+            .map(|(name, (previous, current))| (name, previous, current))
+            .collect();
+        rows.sort_by(|a, b| {
+            b.2.total_cmp(&a.2)
+                .then_with(|| b.1.total_cmp(&a.1))
+                .then_with(|| a.0.cmp(&b.0))
+        });
+
+        Self {
+            previous: labels.0,
+            current: labels.1,
+            rows,
+        }
+    }
+
+    /// This month and the one before, up to the end of the month `today`
+    /// belongs to.
+    pub fn months(today: NaiveDate) -> ((NaiveDate, NaiveDate), (NaiveDate, NaiveDate)) {
+        let current = month_start(today);
+        let previous = current
+            .checked_sub_months(notion::chrono::Months::new(1))
+            .unwrap();
+
+        ((previous, current), (current, next_month(current)))
+    }
+
+    /// The week `today` belongs to and the one before, starting on `start`.
+    pub fn weeks(
+        today: NaiveDate,
+        start: notion::chrono::Weekday,
+    ) -> ((NaiveDate, NaiveDate), (NaiveDate, NaiveDate)) {
+        let current = today.week(start).first_day();
+        let week = notion::chrono::Days::new(7);
+
+        (
+            (current.checked_sub_days(week).unwrap(), current),
+            (current, current.checked_add_days(week).unwrap()),
+        )
+    }
+
+    fn totals(&self) -> (f64, f64) {
+        self.rows
+            .iter()
+            .fold((0.0, 0.0), |(previous, current), row| {
+                (previous + row.1, current + row.2)
+            })
+    }
+
+    /// The change in percent, none when nothing was spent before.
+    fn change(previous: f64, current: f64) -> Option<f64> {
+        (previous.abs() >= 0.005).then(|| (current - previous) / previous * 100.0)
+    }
+
+    pub fn json(&self) -> serde_json::Value {
+        let (previous, current) = self.totals();
+
+        serde_json::json!({
+            "previous": self.previous,
+            "current": self.current,
+            "categories": self
+                .rows
+                .iter()
+                .map(|(name, previous, current)| serde_json::json!({
+                    "name": name,
+                    "previous": previous,
+                    "current": current,
+                    "change": current - previous,
+                    "percent": Self::change(*previous, *current),
+                }))
+                .collect::<Vec<_>>(),
+            "previous_total": previous,
+            "current_total": current,
+        })
+    }
+
+    /// The totals with their changes, more spending in red and less in green.
+    pub fn table(&self) -> comfy_table::Table {
+        use comfy_table::{Attribute, Cell, CellAlignment, Color};
+
+        let row = |name: &str, previous: f64, current: f64| {
+            let delta = current - previous;
+            let color = match delta {
+                delta if delta >= 0.005 => Color::Red,
+                delta if delta <= -0.005 => Color::Green,
+                _ => Color::Reset,
+            };
+
+            [
+                Cell::new(name),
+                Cell::new(format!("{:.2}", previous)),
+                Cell::new(format!("{:.2}", current)),
+                Cell::new(format!("{:+.2}", delta)).fg(color),
+                Cell::new(
+                    Self::change(previous, current)
+                        .map(|percent| format!("{:+.0}%", percent))
+                        .unwrap_or_default(),
+                )
+                .fg(color),
+            ]
+        };
+
+        let mut table = comfy_table::Table::new();
+        table
+            .load_style(comfy_table::presets::UTF8_FULL_CONDENSED)
+            .set_header(
+                ["Category", &self.previous, &self.current, "Change", "%"]
+                    .map(|name| Cell::new(name).add_attribute(Attribute::Bold)),
+            );
+        for (name, previous, current) in &self.rows {
+            table.add_row(row(name, *previous, *current));
+        }
+        let (previous, current) = self.totals();
+        table.add_row(
+            row("Total", previous, current).map(|cell| cell.add_attribute(Attribute::Bold)),
+        );
+
+        for index in 1..5 {
+            if let Some(column) = table.column_mut(index) {
+                column.set_cell_alignment(CellAlignment::Right);
+            }
+        }
+
+        table
+    }
+}
+
+/// A week's spending in short, for sending as a notification.
+#[derive(Debug)]
+pub struct Digest {
+    /// Its Monday.
+    week: NaiveDate,
+    total: f64,
+    /// The largest category totals, at most `TOP`.
+    categories: Vec<(String, f64)>,
+    /// The most expensive entries, at most `TOP`.
+    expenses: Vec<crate::cli::AddArgs>,
+}
+
+impl Digest {
+    const TOP: usize = 3;
+
+    /// Totals the expenses among `entries` from `from` until `to`.
+    pub fn new(
+        (from, to): (NaiveDate, NaiveDate),
+        entries: &[notion::models::Page],
+        categories: &[notion::models::Page],
+        properties: &crate::settings::PropertyNames,
+    ) -> Self {
+        let names = category_names(categories);
+
+        let mut totals: HashMap<String, f64> = HashMap::new();
+        let mut expenses = vec![];
+        for entry in entries {
+            if EntryType::of(entry, &properties.entry_type) != EntryType::Expense
+                || !page_date(entry, &properties.date)
+                    .is_some_and(|date| (from..to).contains(&date))
+            {
+                continue;
+            }
+            let amount = page_amount(entry, &properties.amount).unwrap_or(0.0);
+            let category = page_category(entry, &properties.category, &names)
+                .unwrap_or("Uncategorized".to_string());
+
+            *totals.entry(category).or_default() += amount;
+            expenses.push((amount, entry));
+        }
+
+        let mut categories: Vec<(String, f64)> = totals.into_iter().collect();
+        categories.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        expenses.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        Self {
+            week: from,
+            // Summing nothing gives -0.
+            total: categories
+                .iter()
+                .fold(0.0, |total, (_, amount)| total + amount),
+            categories: categories.into_iter().take(Self::TOP).collect(),
+            expenses: expenses
+                .into_iter()
+                .take(Self::TOP)
+                .map(|(_, entry)| page_fields(entry, &names, properties))
+                .collect(),
+        }
+    }
+
+    pub fn title(&self) -> String {
+        self.week.format("Week of %b %-d").to_string()
+    }
+
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "week": self.week.to_string(),
+            "total": self.total,
+            "categories": self
+                .categories
+                .iter()
+                .map(|(name, total)| serde_json::json!({"name": name, "total": total}))
+                .collect::<Vec<_>>(),
+            "expenses": self.expenses,
+        })
+    }
+}
+
+impl std::fmt::Display for Digest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} spent", self.title(), format_amount(self.total))?;
+        if self.categories.is_empty() {
+            return Ok(());
+        }
+
+        let categories: Vec<String> = self
+            .categories
+            .iter()
+            .map(|(name, amount)| format!("{} {}", name, format_amount(*amount)))
+            .collect();
+        write!(f, "\nTop categories: {}", categories.join(", "))?;
+
+        let expenses: Vec<String> = self
+            .expenses
+            .iter()
+            .map(|entry| {
+                format!(
+                    "{} {}",
+                    entry.name.as_deref().unwrap_or("Untitled"),
+                    entry
+                        .amount
+                        .as_deref()
+                        .and_then(|amount| amount.parse().ok())
+                        .map(format_amount)
+                        .unwrap_or_default()
+                )
+            })
+            .collect();
+        write!(f, "\nBiggest expenses: {}", expenses.join(", "))
+    }
+}
+
+/// Who paid how much of a month's expenses, what their share is, and the
+/// transfers that even it out.
+#[derive(Debug)]
+pub struct Settlement {
+    month: NaiveDate,
+    /// Name, paid and share, by name.
+    people: Vec<(String, f64, f64)>,
+    /// From, to and amount, largest first.
+    transfers: Vec<(String, String, f64)>,
+    /// How many expenses have no payer and were left out.
+    unassigned: usize,
+}
+
+impl Settlement {
+    /// Splits the expenses among `entries` by `shares`, which are weights by
+    /// payer name. Everyone who paid something weighs the same when it's empty.
+    pub fn new(
+        month: NaiveDate,
+        entries: &[notion::models::Page],
+        shares: &HashMap<String, f64>,
+        properties: &crate::settings::PropertyNames,
+    ) -> Self {
+        // By lowercased name, so `alex` in the config is the Alex option.
+        let mut people: HashMap<String, (String, f64, f64)> = shares
+            .iter()
+            .map(|(name, weight)| (name.to_lowercase(), (name.clone(), 0.0, *weight)))
+            .collect();
+        let mut unassigned = 0;
+        for entry in entries {
+            if EntryType::of(entry, &properties.entry_type) != EntryType::Expense {
+                continue;
+            }
+            let amount = page_amount(entry, &properties.amount).unwrap_or(0.0);
+            let Some(payer) = page_payer(entry, &properties.payer) else {
+                unassigned += 1;
+                continue;
+            };
+
+            let weight = if shares.is_empty() { 1.0 } else { 0.0 };
+            let person = people
+                .entry(payer.to_lowercase())
+                .or_insert((String::new(), 0.0, weight));
+            person.0 = payer;
+            person.1 += amount;
+        }
+
+        let total: f64 = people.values().map(|(_, paid, _)| paid).sum();
+        let weights: f64 = people.values().map(|(_, _, weight)| weight).sum();
+        let mut people: Vec<(String, f64, f64)> = people
+            .into_values()
+            .map(|(name, paid, weight)| {
+                let share = match weights > 0.0 {
+                    true => total * weight / weights,
+                    false => 0.0,
+                };
+                (name, paid, share)
+            })
+            .collect();
+        people.sort_by(|a, b| a.0.cmp(&b.0));
+
+        // Whoever owes the most pays whoever is owed the most, until it's even.
+        let mut balances: Vec<(&String, f64)> = people
+            .iter()
+            .map(|(name, paid, share)| (name, paid - share))
+            .collect();
+        let mut transfers = vec![];
+        loop {
+            balances.sort_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.cmp(b.0)));
+            let (Some(debtor), Some(creditor)) = (balances.first(), balances.last()) else {
+                break;
+            };
+            let amount = (-debtor.1).min(creditor.1);
+            if amount < 0.005 {
+                break;
+            }
+
+            transfers.push((debtor.0.clone(), creditor.0.clone(), amount));
+            let last = balances.len() - 1;
+            balances[0].1 += amount;
+            balances[last].1 -= amount;
+        }
+
+        Self {
+            month: month_start(month),
+            people,
+            transfers,
+            unassigned,
+        }
+    }
+
+    pub fn title(&self) -> String {
+        self.month.format("Settlement for %B %Y").to_string()
+    }
+
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "month": self.month.format("%Y-%m").to_string(),
+            "people": self
+                .people
+                .iter()
+                .map(|(name, paid, share)| serde_json::json!({
+                    "name": name,
+                    "paid": paid,
+                    "share": share,
+                }))
+                .collect::<Vec<_>>(),
+            "transfers": self
+                .transfers
+                .iter()
+                .map(|(from, to, amount)| serde_json::json!({
+                    "from": from,
+                    "to": to,
+                    "amount": amount,
+                }))
+                .collect::<Vec<_>>(),
+            "unassigned": self.unassigned,
+        })
+    }
+
+    /// The transfers as lines like `Sam owes Alex 12.50`.
+    fn lines(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self
+            .transfers
+            .iter()
+            .map(|(from, to, amount)| format!("{} owes {} {}", from, to, format_amount(*amount)))
+            .collect();
+        if lines.is_empty() {
+            lines.push("All settled".to_string());
+        }
+        if self.unassigned > 0 {
+            lines.push(format!(
+                "{} expenses without a payer left out",
+                self.unassigned
+            ));
+        }
+        lines
+    }
+}
+
+impl std::fmt::Display for Settlement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.title())?;
+        if self.people.is_empty() {
+            return write!(f, "No expenses with a payer");
+        }
+
+        let width = self
+            .people
+            .iter()
+            .map(|(name, _, _)| name.chars().count())
+            .max()
+            .unwrap_or(0);
+        writeln!(
+            f,
+            "{:<width$}  {:>10}  {:>10}",
+            "",
+            "Paid",
+            "Share",
+            width = width
+        )?;
+        for (name, paid, share) in &self.people {
+            writeln!(
+                f,
+                "{:<width$}  {:>10}  {:>10}",
+                name,
+                format_amount(*paid),
+                format_amount(*share),
+                width = width
+            )?;
+        }
+        write!(f, "\n{}", self.lines().join("\n"))
+    }
+}
+
+/// The expenses of a trip by day.
+#[derive(Debug)]
+pub struct TripReport {
+    name: String,
+    /// Each day with expenses and their total, oldest first.
+    days: Vec<(NaiveDate, f64)>,
+}
+
+impl TripReport {
+    /// The entries related to the trip page `trip` through `properties.trip`.
+    pub fn query(
+        trip: &notion::ids::PageId,
+        properties: &crate::settings::PropertyNames,
+    ) -> crate::backend::Query {
+        crate::backend::Query {
+            filter: Some(crate::backend::Filter::property(
+                &properties.trip,
+                notion::models::search::PropertyCondition::Relation(
+                    notion::models::search::RelationCondition::Contains(trip.clone()),
+                ),
+            )),
+            sorts: Some(crate::backend::descending(&properties.date)),
+            paging: None,
+        }
+    }
+
+    pub fn new(
+        name: &str,
+        entries: &[notion::models::Page],
+        properties: &crate::settings::PropertyNames,
+    ) -> Self {
+        let mut days: HashMap<NaiveDate, f64> = HashMap::new();
+        for entry in entries {
+            if EntryType::of(entry, &properties.entry_type) != EntryType::Expense {
+                continue;
+            }
+            if let Some(date) = page_date(entry, &properties.date) {
+                *days.entry(date).or_default() +=
+                    page_amount(entry, &properties.amount).unwrap_or(0.0);
+            }
+        }
+
+        let mut days: Vec<(NaiveDate, f64)> = days.into_iter().collect();
+        days.sort_by_key(|(date, _)| *date);
+
+        Self {
+            name: name.to_string(),
+            days,
+        }
+    }
+
+    fn total(&self) -> f64 {
+        // Summing nothing gives -0.
+        self.days
+            .iter()
+            .fold(0.0, |total, (_, amount)| total + amount)
+    }
+
+    /// The total spread over the days from the first to the last expense.
+    fn per_day(&self) -> f64 {
+        match (self.days.first(), self.days.last()) {
+            (Some((first, _)), Some((last, _))) => {
+                self.total() / ((*last - *first).num_days() + 1) as f64
+            }
+            _ => 0.0,
+        }
+    }
+
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "trip": self.name,
+            "days": self
+                .days
+                .iter()
+                .map(|(date, total)| serde_json::json!({"date": date.to_string(), "total": total}))
+                .collect::<Vec<_>>(),
+            "total": self.total(),
+            "per_day": self.per_day(),
+        })
+    }
+}
+
+impl std::fmt::Display for TripReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.days.is_empty() {
+            return write!(f, "No expenses in {}", self.name);
+        }
+
+        writeln!(f, "{}", self.name)?;
+        for (date, total) in &self.days {
+            writeln!(
+                f,
+                "{:<14}  {:>10}",
+                date.format("%Y-%m-%d %a"),
+                format_amount(*total)
+            )?;
+        }
+        writeln!(f, "{:<14}  {:>10}", "Total", format_amount(self.total()))?;
+        write!(
+            f,
+            "{:<14}  {:>10}",
+            "Per day",
+            format_amount(self.per_day())
+        )
+    }
+}
+
+/// The reimbursable entries that weren't paid back yet, by project.
+#[derive(Debug)]
+pub struct ReimbursableReport {
+    /// Project name and its entries oldest first, by name with the entries
+    /// without a project last.
+    projects: Vec<(String, Vec<crate::cli::AddArgs>)>,
+}
+
+impl ReimbursableReport {
+    /// The entries with the Reimbursable checkbox set.
+    pub fn query(properties: &crate::settings::PropertyNames) -> crate::backend::Query {
+        crate::backend::Query {
+            filter: Some(crate::backend::Filter::property(
+                &properties.reimbursable,
+                notion::models::search::PropertyCondition::Checkbox(
+                    notion::models::search::CheckboxCondition::Equals(true),
+                ),
+            )),
+            sorts: Some(crate::backend::descending(&properties.date)),
+            paging: None,
+        }
+    }
+
+    /// Groups `entries` but those reimbursed already, which is a checked box or
+    /// one of the `complete` statuses.
+    pub fn new(
+        entries: &[notion::models::Page],
+        complete: &[String],
+        properties: &crate::settings::PropertyNames,
+    ) -> Self {
+        let mut projects: HashMap<Option<String>, Vec<crate::cli::AddArgs>> = HashMap::new();
+        for entry in entries.iter().rev() {
+            let reimbursed = match entry.properties.properties.get(&properties.reimbursed) {
+                Some(notion::models::properties::PropertyValue::Checkbox { checkbox, .. }) => {
+                    *checkbox
+                }
+                Some(notion::models::properties::PropertyValue::Status {
+                    status: Some(status),
+                    ..
+                }) => status
+                    .name
+                    .as_ref()
+                    .is_some_and(|name| complete.contains(name)),
+                _ => false,
+            };
+            if reimbursed || !page_checkbox(entry, &properties.reimbursable) {
+                continue;
+            }
+
+            projects
+                .entry(page_select_name(entry, &properties.project))
+                .or_default()
+                .push(page_fields(entry, &HashMap::new(), properties));
+        }
+
+        let mut projects: Vec<(Option<String>, Vec<crate::cli::AddArgs>)> =
+            projects.into_iter().collect();
+        // `None` would sort first otherwise.
+        projects.sort_by(|a, b| (a.0.is_none(), &a.0).cmp(&(b.0.is_none(), &b.0)));
+
+        Self {
+            projects: projects
+                .into_iter()
+                .map(|(name, entries)| (name.unwrap_or("No project".to_string()), entries))
+                .collect(),
+        }
+    }
+
+    fn amount(entry: &crate::cli::AddArgs) -> f64 {
+        entry
+            .amount
+            .as_deref()
+            .and_then(|amount| amount.parse().ok())
+            .unwrap_or(0.0)
+    }
+
+    // Summing nothing gives -0.
+    fn total(entries: &[crate::cli::AddArgs]) -> f64 {
+        entries
+            .iter()
+            .fold(0.0, |total, entry| total + Self::amount(entry))
+    }
+
+    fn grand_total(&self) -> f64 {
+        self.projects
+            .iter()
+            .fold(0.0, |total, (_, entries)| total + Self::total(entries))
+    }
+
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "projects": self
+                .projects
+                .iter()
+                .map(|(name, entries)| serde_json::json!({
+                    "name": name,
+                    "total": Self::total(entries),
+                    "entries": entries,
+                }))
+                .collect::<Vec<_>>(),
+            "total": self.grand_total(),
+        })
+    }
+}
+
+impl std::fmt::Display for ReimbursableReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.projects.is_empty() {
+            return write!(f, "Nothing to be reimbursed");
+        }
+
+        let line = |entry: &crate::cli::AddArgs| {
+            format!(
+                "  {} {}",
+                entry.date.map(|date| date.to_string()).unwrap_or_default(),
+                entry.name.as_deref().unwrap_or("Untitled")
+            )
+        };
+        let width = self
+            .projects
+            .iter()
+            .flat_map(|(name, entries)| {
+                std::iter::once(name.chars().count())
+                    .chain(entries.iter().map(|entry| line(entry).chars().count()))
+            })
+            .max()
+            .unwrap_or(0);
+
+        for (name, entries) in &self.projects {
+            writeln!(
+                f,
+                "{:<width$}  {:>10}",
+                name,
+                format_amount(Self::total(entries)),
+                width = width
+            )?;
+            for entry in entries {
+                writeln!(
+                    f,
+                    "{:<width$}  {:>10}",
+                    line(entry),
+                    format_amount(Self::amount(entry)),
+                    width = width
+                )?;
+            }
+        }
+        write!(
+            f,
+            "{:<width$}  {:>10}",
+            "Total",
+            format_amount(self.grand_total()),
+            width = width
+        )
+    }
+}
+
+/// A GitHub-flavored markdown table, with the columns but those in `left`
+/// aligned right.
+fn markdown_table(header: &[String], rows: &[Vec<String>], left: &[usize]) -> String {
+    let line = |cells: &[String]| {
+        let cells: Vec<String> = cells.iter().map(|cell| cell.replace('|', "\\|")).collect();
+        format!("| {} |", cells.join(" | "))
+    };
+    let alignment: Vec<String> = (0..header.len())
+        .map(|index| match left.contains(&index) {
+            true => "---".to_string(),
+            false => "---:".to_string(),
+        })
+        .collect();
+
+    std::iter::once(line(header))
+        .chain(std::iter::once(line(&alignment)))
+        .chain(rows.iter().map(|row| line(row)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn bold(text: impl std::fmt::Display) -> String {
+    format!("**{}**", text)
+}
+
+impl MonthReport {
+    pub fn markdown(&self, forecast: Option<&Forecast>) -> String {
+        let mut rows: Vec<Vec<String>> = self
+            .totals
+            .iter()
+            .map(|(name, amount)| vec![name.clone(), format_amount(*amount)])
+            .collect();
+        rows.push(vec![bold("Total"), bold(format_amount(self.total))]);
+        if self.income != 0.0 {
+            rows.push(vec!["Income".to_string(), format_amount(self.income)]);
+            rows.push(vec![
+                "Net".to_string(),
+                format_amount(self.income - self.total),
+            ]);
+        }
+
+        let mut markdown = format!(
+            "## {}\n\n{}",
+            self.title(),
+            markdown_table(&["Category".to_string(), "Amount".to_string()], &rows, &[0])
+        );
+        if let Some(forecast) = forecast {
+            for line in forecast.to_string().lines() {
+                markdown.push_str(&format!("\n\n{}", line));
+            }
+        }
+        markdown
+    }
+}
+
+impl SubscriptionReport {
+    pub fn markdown(&self) -> String {
+        if self.subscriptions.is_empty() {
+            return "No subscriptions found".to_string();
+        }
+
+        let mut rows: Vec<Vec<String>> = self
+            .subscriptions
+            .iter()
+            .map(|subscription| {
+                vec![
+                    subscription.name.clone(),
+                    format_amount(subscription.monthly),
+                    format_amount(subscription.monthly * 12.0),
+                    subscription
+                        .changes
+                        .iter()
+                        .map(|(month, from, to)| {
+                            format!("{:.2} → {:.2} in {}", from, to, month.format("%B %Y"))
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                ]
+            })
+            .collect();
+        let monthly: f64 = self.subscriptions.iter().map(|s| s.monthly).sum();
+        rows.push(vec![
+            bold("Total"),
+            bold(format_amount(monthly)),
+            bold(format_amount(monthly * 12.0)),
+            String::new(),
+        ]);
+
+        format!(
+            "## Subscriptions\n\n{}",
+            markdown_table(
+                &["", "Monthly", "Yearly", "Changes"].map(String::from),
+                &rows,
+                &[0, 3],
+            )
+        )
+    }
+}
+
+impl TrendReport {
+    pub fn markdown(&self) -> String {
+        let header: Vec<String> = ["Month".to_string(), "Total".to_string()]
+            .into_iter()
+            .chain(self.categories.iter().map(|(name, _)| name.clone()))
+            .collect();
+        let rows: Vec<Vec<String>> = self
+            .months
+            .iter()
+            .enumerate()
+            .map(|(index, month)| {
+                [
+                    month.format("%B %Y").to_string(),
+                    format_amount(self.totals[index]),
+                ]
+                .into_iter()
+                .chain(
+                    self.categories
+                        .iter()
+                        .map(|(_, totals)| format_amount(totals[index])),
+                )
+                .collect()
+            })
+            .collect();
+
+        format!(
+            "## Spending from {} to {}\n\n{}",
+            self.months[0].format("%B %Y"),
+            self.months[self.months.len() - 1].format("%B %Y"),
+            markdown_table(&header, &rows, &[0])
+        )
+    }
+}
+
+impl YearReport {
+    pub fn markdown(&self) -> String {
+        let row = |name: String, months: &[f64; 12]| -> Vec<String> {
+            let total: f64 = months.iter().sum();
+            std::iter::once(name)
+                .chain(months.iter().map(|amount| match amount.abs() < 0.005 {
+                    true => String::new(),
+                    false => format_amount(*amount),
+                }))
+                .chain([
+                    format_amount(total),
+                    format_amount(total / self.months as f64),
+                ])
+                .collect()
+        };
+
+        let header: Vec<String> = std::iter::once("Category".to_string())
+            .chain((1..=12).map(|month| {
+                NaiveDate::from_ymd_opt(self.year, month, 1)
+                    .unwrap()
+                    .format("%b")
+                    .to_string()
+            }))
+            .chain(["Total".to_string(), "Average".to_string()])
+            .collect();
+        let mut rows: Vec<Vec<String>> = self
+            .categories
+            .iter()
+            .map(|(name, months)| row(name.clone(), months))
+            .collect();
+        rows.push(
+            row("Total".to_string(), &self.monthly_totals())
+                .into_iter()
+                .map(|cell| match cell.is_empty() {
+                    true => cell,
+                    false => bold(cell),
+                })
+                .collect(),
+        );
+
+        let mut markdown = format!(
+            "## {}\n\n{}",
+            self.year,
+            markdown_table(&header, &rows, &[0])
+        );
+        if !self.top.is_empty() {
+            let rows: Vec<Vec<String>> = self
+                .top
+                .iter()
+                .map(|entry| {
+                    vec![
+                        entry.date.map(|date| date.to_string()).unwrap_or_default(),
+                        entry.name.clone().unwrap_or_default(),
+                        entry
+                            .amount
+                            .as_deref()
+                            .and_then(|amount| amount.parse().ok())
+                            .map(format_amount)
+                            .unwrap_or_default(),
+                        entry.category.clone().unwrap_or_default(),
+                    ]
+                })
+                .collect();
+            markdown.push_str(&format!(
+                "\n\n### The {} most expensive\n\n{}",
+                self.top.len(),
+                markdown_table(
+                    &["Date", "Name", "Amount", "Category"].map(String::from),
+                    &rows,
+                    &[0, 1, 3],
+                )
+            ));
+        }
+        markdown
+    }
+}
+
+impl Comparison {
+    pub fn markdown(&self) -> String {
+        let row = |name: String, previous: f64, current: f64| {
+            vec![
+                name,
+                format_amount(previous),
+                format_amount(current),
+                format!("{:+.2}", current - previous),
+                Self::change(previous, current)
+                    .map(|percent| format!("{:+.0}%", percent))
+                    .unwrap_or_default(),
+            ]
+        };
+
+        let mut rows: Vec<Vec<String>> = self
+            .rows
+            .iter()
+            .map(|(name, previous, current)| row(name.clone(), *previous, *current))
+            .collect();
+        let (previous, current) = self.totals();
+        rows.push(
+            row("Total".to_string(), previous, current)
+                .into_iter()
+                .map(|cell| match cell.is_empty() {
+                    true => cell,
+                    false => bold(cell),
+                })
+                .collect(),
+        );
+
+        format!(
+            "## {} against {}\n\n{}",
+            self.current,
+            self.previous,
+            markdown_table(
+                &[
+                    "Category".to_string(),
+                    self.previous.clone(),
+                    self.current.clone(),
+                    "Change".to_string(),
+                    "%".to_string(),
+                ],
+                &rows,
+                &[0],
+            )
+        )
+    }
+}
+
+impl TripReport {
+    pub fn markdown(&self) -> String {
+        let mut rows: Vec<Vec<String>> = self
+            .days
+            .iter()
+            .map(|(date, total)| {
+                vec![
+                    date.format("%Y-%m-%d %a").to_string(),
+                    format_amount(*total),
+                ]
+            })
+            .collect();
+        rows.push(vec![bold("Total"), bold(format_amount(self.total()))]);
+        rows.push(vec!["Per day".to_string(), format_amount(self.per_day())]);
+
+        format!(
+            "## {}\n\n{}",
+            self.name,
+            markdown_table(&["Day".to_string(), "Spent".to_string()], &rows, &[0])
+        )
+    }
+}
+
+impl ReimbursableReport {
+    pub fn markdown(&self) -> String {
+        let mut rows: Vec<Vec<String>> = vec![];
+        for (name, entries) in &self.projects {
+            for entry in entries {
+                rows.push(vec![
+                    name.clone(),
+                    entry.date.map(|date| date.to_string()).unwrap_or_default(),
+                    entry.name.clone().unwrap_or("Untitled".to_string()),
+                    format_amount(Self::amount(entry)),
+                ]);
+            }
+            rows.push(vec![
+                bold(name),
+                String::new(),
+                String::new(),
+                bold(format_amount(Self::total(entries))),
+            ]);
+        }
+        rows.push(vec![
+            bold("Total"),
+            String::new(),
+            String::new(),
+            bold(format_amount(self.grand_total())),
+        ]);
+
+        format!(
+            "## Outstanding reimbursements\n\n{}",
+            markdown_table(
+                &["Project", "Date", "Name", "Amount"].map(String::from),
+                &rows,
+                &[0, 1, 2],
+            )
+        )
+    }
+}
+
+impl Settlement {
+    pub fn markdown(&self) -> String {
+        let rows: Vec<Vec<String>> = self
+            .people
+            .iter()
+            .map(|(name, paid, share)| {
+                vec![name.clone(), format_amount(*paid), format_amount(*share)]
+            })
+            .collect();
+
+        format!(
+            "## {}\n\n{}\n\n{}",
+            self.title(),
+            markdown_table(
+                &["Payer".to_string(), "Paid".to_string(), "Share".to_string()],
+                &rows,
+                &[0],
+            ),
+            self.lines().join("\n\n")
+        )
+    }
+}
+
+/// `entries` oldest first as a table of date, name, amount and category,
+/// followed by their total.
+pub fn entries_table(
+    entries: &[notion::models::Page],
+    categories: &[notion::models::Page],
+    properties: &crate::settings::PropertyNames,
+    display: &crate::settings::DisplaySettings,
+) -> comfy_table::Table {
+    let names = category_names(categories);
+    let rows: Vec<crate::cli::AddArgs> = entries
+        .iter()
+        .rev()
+        .map(|entry| page_fields(entry, &names, properties))
+        .collect();
+
+    fields_table(&rows, display)
+}
+
+/// The title, amount, date, category and type of an entry, for tables.
+pub fn page_fields(
+    entry: &notion::models::Page,
+    names: &HashMap<&notion::ids::PageId, String>,
+    properties: &crate::settings::PropertyNames,
+) -> crate::cli::AddArgs {
+    crate::cli::AddArgs {
+        name: Some(entry.title().unwrap_or("Untitled".to_string())),
+        amount: page_amount(entry, &properties.amount).map(|amount| amount.to_string()),
+        date: page_date(entry, &properties.date),
+        category: page_category(entry, &properties.category, names),
+        entry_type: page_select_name(entry, &properties.entry_type),
+        payer: page_payer(entry, &properties.payer),
+        ..Default::default()
+    }
+}
+
+/// Like `entries_table`, for entries that aren't in Notion yet.
+pub fn fields_table(
+    rows: &[crate::cli::AddArgs],
+    display: &crate::settings::DisplaySettings,
+) -> comfy_table::Table {
+    use comfy_table::{Attribute, Cell, CellAlignment, Color};
+
+    let mut table = comfy_table::Table::new();
+    table
+        .load_style(comfy_table::presets::UTF8_FULL_CONDENSED)
+        .set_header(
+            ["Date", "Name", "Amount", "Category"]
+                .map(|name| Cell::new(name).add_attribute(Attribute::Bold)),
+        );
+
+    let mut total = 0.0;
+    for row in rows {
+        let amount = row.amount.as_deref().and_then(|amount| amount.parse().ok());
+        total += amount.unwrap_or(0.0);
+
+        let color = match EntryType::named(row.entry_type.as_deref()) {
+            EntryType::Expense => Color::Reset,
+            EntryType::Income => Color::Green,
+            EntryType::Transfer => Color::DarkGrey,
+        };
+
+        table.add_row([
+            Cell::new(
+                row.date
+                    .map(|date| display.format_date(date))
+                    .unwrap_or_default(),
+            ),
+            Cell::new(row.name.clone().unwrap_or_default()),
+            Cell::new(
+                amount
+                    .map(|amount: f64| format!("{:.2}", amount))
+                    .unwrap_or_default(),
+            )
+            .fg(color),
+            Cell::new(row.category.clone().unwrap_or_default()),
+        ]);
+    }
+
+    table.add_row([
+        Cell::new(""),
+        Cell::new("Total").add_attribute(Attribute::Bold),
+        Cell::new(format!("{:.2}", total)).add_attribute(Attribute::Bold),
+        Cell::new(""),
+    ]);
+
+    if let Some(column) = table.column_mut(2) {
+        column.set_cell_alignment(CellAlignment::Right);
+    }
+
+    table
+}
+
+/// An entry with `properties`, for the tests.
+#[cfg(test)]
+pub(crate) fn page(properties: serde_json::Value) -> notion::models::Page {
+    serde_json::from_value(serde_json::json!({
+        "id": "entry",
+        "created_time": "2024-05-01T00:00:00Z",
+        "last_edited_time": "2024-05-01T00:00:00Z",
+        "archived": false,
+        "parent": {"type": "workspace"},
+        "properties": properties,
+    }))
+    .unwrap()
+}
+
+#[test]
+fn month_report_test() {
+    let title = |name: &str| {
+        serde_json::json!({"Name": {"id": "title", "type": "title", "title": [
+            {"type": "text", "plain_text": name, "text": {"content": name, "link": null}}
+        ]}})
+    };
+    let entry = |amount: f64, category: Option<&str>| {
+        page(serde_json::json!({
+            "Amount": {"id": "a", "type": "number", "number": amount},
+            "Category": {"id": "c", "type": "relation", "relation": category.map(|id| vec![serde_json::json!({"id": id})])},
+        }))
+    };
+
+    let mut food = page(title("Food"));
+    food.id = "food".parse().unwrap();
+
+    let month = NaiveDate::from_ymd_opt(2024, 5, 17).unwrap();
+    let report = MonthReport::new(
+        month,
+        &[
+            entry(10.5, Some("food")),
+            entry(2.0, None),
+            entry(4.5, Some("food")),
+            page(serde_json::json!({
+                "Amount": {"id": "a", "type": "number", "number": 3.0},
+                "Category": {"id": "c", "type": "select", "select": {"name": "Food", "color": "default"}},
+            })),
+        ],
+        &[food],
+        &crate::settings::PropertyNames::default(),
+    );
+
+    assert_eq!(report.category_total("Food"), 18.0);
+    assert_eq!(report.category_total("Rent"), 0.0);
+    assert_eq!(
+        report.to_string(),
+        "May 2024\nFood                18.00\nUncategorized        2.00\nTotal               20.00"
+    );
+    let budgets = HashMap::from([("food".to_string(), 30.0)]);
+    assert!(report
+        .forecast(NaiveDate::from_ymd_opt(2024, 6, 1).unwrap(), &budgets)
+        .is_none());
+    assert_eq!(
+        report
+            .forecast(NaiveDate::from_ymd_opt(2024, 5, 10).unwrap(), &budgets)
+            .unwrap()
+            .to_string(),
+        "2.00 a day over 10 of 31 days, 62.00 projected for May against the 30.00 budgeted, 32.00 over\nfood: 55.80 of 30.00, 25.80 over"
+    );
+    assert_eq!(
+        report.markdown(None),
+        "## May 2024
+
+| Category | Amount |
+| --- | ---: |
+| Food | 18.00 |
+| Uncategorized | 2.00 |
+| **Total** | **20.00** |"
+    );
+    let blocks = report.blocks(None);
+    assert_eq!(blocks.len(), 1);
+    assert_eq!(
+        blocks[0]["table"]["children"][1]["table_row"]["cells"],
+        serde_json::json!([rich_text("Food"), rich_text("18.00")])
+    );
+    assert_eq!(
+        report.chart(40),
+        "Food           ████████████████████  90%\nUncategorized  ██▎                   10%"
+    );
+    assert_eq!(
+        report.json(),
+        serde_json::json!({
+            "month": "2024-05",
+            "categories": [
+                {"name": "Food", "total": 18.0},
+                {"name": "Uncategorized", "total": 2.0},
+            ],
+            "total": 20.0,
+            "income": 0.0,
+        })
+    );
+}
+
+#[test]
+fn month_report_income_test() {
+    let entry = |amount: f64, entry_type: &str| -> notion::models::Page {
+        page(serde_json::json!({
+            "Amount": {"id": "a", "type": "number", "number": amount},
+            "Type": {"id": "t", "type": "select", "select": {"name": entry_type, "color": "default"}},
+        }))
+    };
+
+    let report = MonthReport::new(
+        NaiveDate::from_ymd_opt(2024, 5, 1).unwrap(),
+        &[
+            entry(30.0, "Expense"),
+            entry(100.0, "Income"),
+            entry(50.0, "Transfer"),
+        ],
+        &[],
+        &crate::settings::PropertyNames::default(),
+    );
+
+    assert_eq!(
+        report.to_string(),
+        "May 2024\nUncategorized       30.00\nTotal               30.00\nIncome             100.00\nNet                 70.00"
+    );
+}
+
+#[test]
+fn trend_report_test() {
+    let entry = |date: &str, amount: f64, category: &str| -> notion::models::Page {
+        page(serde_json::json!({
+            "Amount": {"id": "a", "type": "number", "number": amount},
+            "Date": {"id": "d", "type": "date", "date": {"start": date}},
+            "Category": {"id": "c", "type": "select", "select": {"name": category, "color": "default"}},
+        }))
+    };
+
+    let report = TrendReport::new(
+        NaiveDate::from_ymd_opt(2024, 5, 17).unwrap(),
+        3,
+        &[
+            entry("2024-05-02", 30.0, "Food"),
+            entry("2024-05-10", 30.0, "Rent"),
+            entry("2024-03-04", 15.0, "Food"),
+            entry("2024-01-04", 99.0, "Food"),
+        ],
+        &[],
+        &["food".to_string()],
+        &crate::settings::PropertyNames::default(),
+    );
+
+    assert_eq!(
+        report.to_string(),
+        "Mar 2024  ███████▌                             15.00\nApr 2024                                        0.00\nMay 2024  ██████████████████████████████       60.00\n\nTotal  ▃▁█  average 25.00\nfood   ▅▁█  average 15.00"
+    );
+    assert_eq!(report.json()["months"][2]["categories"]["food"], 30.0);
+}
+
+#[test]
+fn comparison_test() {
+    let entry = |date: &str, amount: f64, category: &str| -> notion::models::Page {
+        page(serde_json::json!({
+            "Amount": {"id": "a", "type": "number", "number": amount},
+            "Date": {"id": "d", "type": "date", "date": {"start": date}},
+            "Category": {"id": "c", "type": "select", "select": {"name": category, "color": "default"}},
+        }))
+    };
+
+    let today = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+    let weeks = Comparison::weeks(today, notion::chrono::Weekday::Mon);
+    assert_eq!(weeks.0 .0, NaiveDate::from_ymd_opt(2024, 4, 22).unwrap());
+    assert_eq!(weeks.1 .1, NaiveDate::from_ymd_opt(2024, 5, 6).unwrap());
+    assert_eq!(
+        Comparison::weeks(today, notion::chrono::Weekday::Sun).1 .0,
+        NaiveDate::from_ymd_opt(2024, 4, 28).unwrap()
+    );
+
+    let comparison = Comparison::new(
+        Comparison::months(today),
+        ("April 2024".to_string(), "May 2024".to_string()),
+        &[
+            entry("2024-04-02", 40.0, "Food"),
+            entry("2024-05-01", 30.0, "Food"),
+            entry("2024-05-01", 12.0, "Books"),
+            entry("2024-03-31", 99.0, "Food"),
+        ],
+        &[],
+        &crate::settings::PropertyNames::default(),
+    );
+
+    assert_eq!(
+        comparison.json(),
+        serde_json::json!({
+            "previous": "April 2024",
+            "current": "May 2024",
+            "categories": [
+                {"name": "Food", "previous": 40.0, "current": 30.0, "change": -10.0, "percent": -25.0},
+                {"name": "Books", "previous": 0.0, "current": 12.0, "change": 12.0, "percent": null},
+            ],
+            "previous_total": 40.0,
+            "current_total": 42.0,
+        })
+    );
+}
+
+#[test]
+fn year_report_test() {
+    let entry = |date: &str, amount: f64, category: &str| -> notion::models::Page {
+        page(serde_json::json!({
+            "Amount": {"id": "a", "type": "number", "number": amount},
+            "Date": {"id": "d", "type": "date", "date": {"start": date}},
+            "Category": {"id": "c", "type": "select", "select": {"name": category, "color": "default"}},
+        }))
+    };
+
+    let report = YearReport::new(
+        2024,
+        NaiveDate::from_ymd_opt(2024, 4, 30).unwrap(),
+        &[
+            entry("2024-01-10", 10.0, "Food"),
+            entry("2024-01-20", 800.0, "Rent"),
+            entry("2024-03-01", 30.0, "Food"),
+            entry("2023-12-31", 99.0, "Food"),
+        ],
+        &[],
+        &crate::settings::PropertyNames::default(),
+    );
+    let json = report.json();
+
+    assert_eq!(json["categories"][0]["name"], "Rent");
+    assert_eq!(
+        json["categories"][1]["months"],
+        serde_json::json!([10.0, 0.0, 30.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0])
+    );
+    assert_eq!(json["categories"][1]["average"], 10.0);
+    assert_eq!(json["total"], 840.0);
+    assert_eq!(json["top"][0]["amount"], "800");
+    assert_eq!(json["top"].as_array().unwrap().len(), 3);
+}
+
+#[test]
+fn digest_test() {
+    let entry = |name: &str, date: &str, amount: f64, category: &str| -> notion::models::Page {
+        page(serde_json::json!({
+            "Name": {"id": "title", "type": "title", "title": [
+                {"type": "text", "plain_text": name, "text": {"content": name, "link": null}}
+            ]},
+            "Amount": {"id": "a", "type": "number", "number": amount},
+            "Date": {"id": "d", "type": "date", "date": {"start": date}},
+            "Category": {"id": "c", "type": "select", "select": {"name": category, "color": "default"}},
+        }))
+    };
+
+    let (last_week, _) = Comparison::weeks(
+        NaiveDate::from_ymd_opt(2024, 5, 8).unwrap(),
+        notion::chrono::Weekday::Mon,
+    );
+    let digest = Digest::new(
+        last_week,
+        &[
+            entry("Groceries", "2024-04-29", 43.2, "Food"),
+            entry("Cinema", "2024-05-03", 12.0, "Fun"),
+            entry("Bakery", "2024-05-05", 4.5, "Food"),
+            entry("Rent", "2024-05-06", 800.0, "Home"),
+        ],
+        &[],
+        &crate::settings::PropertyNames::default(),
+    );
+
+    assert_eq!(
+        digest.to_string(),
+        "Week of Apr 29: 59.70 spent\nTop categories: Food 47.70, Fun 12.00\nBiggest expenses: Groceries 43.20, Cinema 12.00, Bakery 4.50"
+    );
+}
+
+#[test]
+fn entries_table_test() {
+    let entry = |name: &str, date: &str, amount: f64| -> notion::models::Page {
+        page(serde_json::json!({
+            "Name": {"id": "title", "type": "title", "title": [
+                {"type": "text", "plain_text": name, "text": {"content": name, "link": null}}
+            ]},
+            "Amount": {"id": "a", "type": "number", "number": amount},
+            "Date": {"id": "d", "type": "date", "date": {"start": date}},
+            "Category": {"id": "c", "type": "select", "select": {"name": "Food", "color": "default"}},
+        }))
+    };
+
+    assert_eq!(
+        entries_table(
+            &[
+                entry("Coffee", "2024-05-03", 3.5),
+                entry("Groceries", "2024-05-02", 43.2),
+            ],
+            &[],
+            &crate::settings::PropertyNames::default(),
+            &Default::default(),
+        )
+        .to_string(),
+        "┌────────────┬───────────┬────────┬──────────┐\n\
+         │ Date       ┆ Name      ┆ Amount ┆ Category │\n\
+         ╞════════════╪═══════════╪════════╪══════════╡\n\
+         │ 2024-05-02 ┆ Groceries ┆  43.20 ┆ Food     │\n\
+         │ 2024-05-03 ┆ Coffee    ┆   3.50 ┆ Food     │\n\
+         │            ┆ Total     ┆  46.70 ┆          │\n\
+         └────────────┴───────────┴────────┴──────────┘"
+    );
+}
+
+#[test]
+fn subscription_report_test() {
+    let entry = |name: &str, date: &str, amount: f64| -> notion::models::Page {
+        page(serde_json::json!({
+            "Name": {"id": "title", "type": "title", "title": [
+                {"type": "text", "plain_text": name, "text": {"content": name, "link": null}}
+            ]},
+            "Amount": {"id": "a", "type": "number", "number": amount},
+            "Date": {"id": "d", "type": "date", "date": {"start": date}},
+        }))
+    };
+
+    // Newest first, like the query returns them.
+    let report = SubscriptionReport::new(
+        &[
+            entry("Streaming", "2024-05-03", 12.99),
+            entry("Coffee", "2024-05-02", 3.5),
+            entry("Coffee", "2024-05-01", 3.5),
+            entry("Streaming", "2024-04-03", 9.99),
+            entry("Coffee", "2024-04-02", 3.5),
+            entry("Gym", "2024-04-01", 30.0),
+            entry("Streaming", "2024-03-03", 9.99),
+            entry("Coffee", "2024-03-02", 3.5),
+            entry("Gym", "2024-02-01", 30.0),
+            entry("Gym", "2024-01-01", 30.0),
+        ],
+        &crate::settings::PropertyNames::default(),
+    );
+
+    assert_eq!(
+        report.to_string(),
+        "              Monthly      Yearly\n\
+         Streaming       12.99      155.88\n\
+         \x20          ! 9.99 → 12.99 in May 2024\n\
+         Total           12.99      155.88"
+    );
+}
+
+#[test]
+fn settle_test() {
+    let entry = |amount: f64, payer: Option<&str>, entry_type: &str| -> notion::models::Page {
+        let mut properties = serde_json::json!({
+            "Amount": {"id": "a", "type": "number", "number": amount},
+            "Type": {"id": "t", "type": "select", "select": {"name": entry_type, "color": "default"}},
+        });
+        if let Some(payer) = payer {
+            properties["Payer"] = serde_json::json!(
+                {"id": "p", "type": "select", "select": {"name": payer, "color": "default"}}
+            );
+        }
+        page(properties)
+    };
+    let entries = [
+        entry(60.0, Some("Alex"), "Expense"),
+        entry(30.0, Some("Sam"), "Expense"),
+        entry(-10.0, Some("Alex"), "Expense"),
+        entry(1000.0, Some("Sam"), "Income"),
+        entry(5.0, None, "Expense"),
+    ];
+    let month = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+    let properties = crate::settings::PropertyNames::default();
+
+    let equal = Settlement::new(month, &entries, &HashMap::new(), &properties);
+    assert_eq!(
+        equal.to_string(),
+        "Settlement for May 2024\n\
+         \x20           Paid       Share\n\
+         Alex       50.00       40.00\n\
+         Sam        30.00       40.00\n\
+         \n\
+         Sam owes Alex 10.00\n\
+         1 expenses without a payer left out"
+    );
+
+    let shares = HashMap::from([("alex".to_string(), 3.0), ("sam".to_string(), 1.0)]);
+    let weighted = Settlement::new(month, &entries, &shares, &properties);
+    assert_eq!(
+        weighted.transfers,
+        vec![("Alex".to_string(), "Sam".to_string(), 10.0)]
+    );
+}
+
+#[test]
+fn reimbursable_report_test() {
+    let entry = |name: &str, date: &str, amount: f64, extra: serde_json::Value| {
+        let mut properties = serde_json::json!({
+            "Name": {"id": "title", "type": "title", "title": [
+                {"type": "text", "plain_text": name, "text": {"content": name, "link": null}}
+            ]},
+            "Amount": {"id": "a", "type": "number", "number": amount},
+            "Date": {"id": "d", "type": "date", "date": {"start": date}},
+            "Reimbursable": {"id": "r", "type": "checkbox", "checkbox": true},
+        });
+        for (key, value) in extra.as_object().unwrap() {
+            properties[key] = value.clone();
+        }
+        page(properties)
+    };
+    let project = |name: &str| serde_json::json!({"id": "p", "type": "select", "select": {"name": name, "color": "default"}});
+    let status = |name: &str| serde_json::json!({"id": "s", "type": "status", "status": {"name": name, "color": "default"}});
+
+    // Newest first, like the query returns them.
+    let entries = [
+        entry("Taxi", "2024-05-12", 18.0, serde_json::json!({})),
+        entry(
+            "Hotel",
+            "2024-05-10",
+            120.0,
+            serde_json::json!({"Project": project("Acme"), "Reimbursed": status("In progress")}),
+        ),
+        entry(
+            "Lunch",
+            "2024-05-03",
+            25.5,
+            serde_json::json!({"Project": project("Acme"), "Reimbursed": status("Paid")}),
+        ),
+        entry(
+            "Train",
+            "2024-05-02",
+            43.2,
+            serde_json::json!({"Project": project("Acme")}),
+        ),
+        entry(
+            "Coffee",
+            "2024-05-01",
+            3.5,
+            serde_json::json!({"Reimbursable": {"id": "r", "type": "checkbox", "checkbox": false}}),
+        ),
+    ];
+    let report = ReimbursableReport::new(
+        &entries,
+        &["Paid".to_string()],
+        &crate::settings::PropertyNames::default(),
+    );
+
+    assert_eq!(
+        report.to_string(),
+        "Acme                    163.20\n\
+         \x20 2024-05-02 Train       43.20\n\
+         \x20 2024-05-10 Hotel      120.00\n\
+         No project               18.00\n\
+         \x20 2024-05-12 Taxi        18.00\n\
+         Total                   181.20"
+    );
+}
+
+#[test]
+fn trip_report_test() {
+    let entry = |date: &str, amount: f64, entry_type: &str| -> notion::models::Page {
+        page(serde_json::json!({
+            "Amount": {"id": "a", "type": "number", "number": amount},
+            "Date": {"id": "d", "type": "date", "date": {"start": date}},
+            "Type": {"id": "t", "type": "select", "select": {"name": entry_type, "color": "default"}},
+        }))
+    };
+
+    let report = TripReport::new(
+        "Lisbon",
+        &[
+            entry("2024-05-05", 30.0, "Expense"),
+            entry("2024-05-05", 1000.0, "Income"),
+            entry("2024-05-02", 80.0, "Expense"),
+            entry("2024-05-02", 10.0, "Expense"),
+        ],
+        &crate::settings::PropertyNames::default(),
+    );
+
+    assert_eq!(
+        report.to_string(),
+        "Lisbon\n\
+         2024-05-02 Thu       90.00\n\
+         2024-05-05 Sun       30.00\n\
+         Total               120.00\n\
+         Per day              30.00"
+    );
+}
@@ -0,0 +1,160 @@
+use crate::settings::CategoryRule;
+use eyre::{eyre, Result};
+use regex_automata::meta::Regex;
+use rust_decimal::Decimal;
+
+enum Pattern {
+    /// In lowercase, titles are matched ignoring case.
+    Glob(String),
+    Regex(Regex),
+}
+
+/// A rule from the `rules` section, ready to be tried.
+pub struct Rule<'a> {
+    pub rule: &'a CategoryRule,
+    pattern: Pattern,
+}
+
+/// Why a rule did or didn't fire for an entry.
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    Fires,
+    NoMatch,
+    OutOfRange,
+}
+
+/// Whether `text` is `pattern`, `*` standing for any text and `?` for any
+/// character.
+fn glob(pattern: &[char], text: &[char]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some(('*', rest)) => (0..=text.len()).any(|skip| glob(rest, &text[skip..])),
+        Some(('?', rest)) => !text.is_empty() && glob(rest, &text[1..]),
+        Some((c, rest)) => text.first() == Some(c) && glob(rest, &text[1..]),
+    }
+}
+
+impl<'a> Rule<'a> {
+    pub fn new(rule: &'a CategoryRule) -> Result<Self> {
+        let pattern = match (&rule.glob, &rule.regex) {
+            (Some(glob), None) => Pattern::Glob(glob.to_lowercase()),
+            (None, Some(regex)) => Pattern::Regex(
+                Regex::new(regex).map_err(|err| eyre!("Invalid rule `{}`: {}", regex, err))?,
+            ),
+            _ => {
+                return Err(eyre!(
+                    "The rule for {} needs either `glob` or `regex`",
+                    rule.category
+                ))
+            }
+        };
+
+        Ok(Self { rule, pattern })
+    }
+
+    pub fn check(&self, title: &str, amount: Option<Decimal>) -> Outcome {
+        let title = title.trim();
+        let matches = match &self.pattern {
+            Pattern::Glob(pattern) => glob(
+                &pattern.chars().collect::<Vec<_>>(),
+                &title.to_lowercase().chars().collect::<Vec<_>>(),
+            ),
+            Pattern::Regex(regex) => regex.is_match(title),
+        };
+        if !matches {
+            return Outcome::NoMatch;
+        }
+
+        let in_range = |bound: Option<Decimal>, within: fn(Decimal, Decimal) -> bool| match bound {
+            Some(bound) => amount.is_some_and(|amount| within(amount, bound)),
+            None => true,
+        };
+        if in_range(self.rule.min, |amount, min| amount >= min)
+            && in_range(self.rule.max, |amount, max| amount <= max)
+        {
+            Outcome::Fires
+        } else {
+            Outcome::OutOfRange
+        }
+    }
+}
+
+impl std::fmt::Display for Rule<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.rule.glob, &self.rule.regex) {
+            (Some(glob), _) => write!(f, "`{}`", glob)?,
+            (_, regex) => write!(f, "/{}/", regex.as_deref().unwrap_or_default())?,
+        }
+        match (self.rule.min, self.rule.max) {
+            (Some(min), Some(max)) => write!(f, " from {:.2} to {:.2}", min, max)?,
+            (Some(min), None) => write!(f, " from {:.2}", min)?,
+            (None, Some(max)) => write!(f, " up to {:.2}", max)?,
+            (None, None) => {}
+        }
+        write!(f, " → {}", self.rule.category)
+    }
+}
+
+pub fn compile(rules: &[CategoryRule]) -> Result<Vec<Rule<'_>>> {
+    rules.iter().map(Rule::new).collect()
+}
+
+/// The category of the first rule firing for `title`.
+pub fn category<'a>(rules: &[Rule<'a>], title: &str, amount: Option<Decimal>) -> Option<&'a str> {
+    rules
+        .iter()
+        .find(|rule| rule.check(title, amount) == Outcome::Fires)
+        .map(|rule| rule.rule.category.as_str())
+}
+
+#[test]
+fn category_test() {
+    let rules: Vec<CategoryRule> = serde_yaml::from_str(
+        r#"
+        - glob: "LIDL*"
+          category: Groceries
+        - regex: "(?i)^(uber|bolt)\\b"
+          category: Transport
+          max: 50
+        - glob: "*"
+          category: Other
+        "#,
+    )
+    .unwrap();
+    let rules = compile(&rules).unwrap();
+
+    assert_eq!(
+        category(&rules, "Lidl Berlin", "12.0".parse().ok()),
+        Some("Groceries")
+    );
+    assert_eq!(
+        category(&rules, "Uber trip", "18.5".parse().ok()),
+        Some("Transport")
+    );
+    assert_eq!(
+        rules[1].check("Uber trip", "80.0".parse().ok()),
+        Outcome::OutOfRange
+    );
+    assert_eq!(
+        rules[1].check("Ubering", "8.0".parse().ok()),
+        Outcome::NoMatch
+    );
+    assert_eq!(
+        category(&rules, "Uber trip", "80.0".parse().ok()),
+        Some("Other")
+    );
+    assert_eq!(
+        rules[1].to_string(),
+        r"/(?i)^(uber|bolt)\b/ up to 50.00 → Transport"
+    );
+
+    assert!(glob(&['a', '?', 'c'], &['a', 'b', 'c']));
+    assert!(compile(&[CategoryRule {
+        glob: None,
+        regex: None,
+        category: "Food".to_string(),
+        min: None,
+        max: None,
+    }])
+    .is_err());
+}
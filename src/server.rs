@@ -0,0 +1,169 @@
+use axum::extract::{FromRequest, Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::{Form, Json};
+use eyre::Result;
+use std::sync::Arc;
+
+/// A failed request, answered with `{"error": "..."}`.
+struct ApiError(StatusCode, String);
+
+impl ApiError {
+    fn bad_request(err: impl std::fmt::Display) -> Self {
+        Self(StatusCode::BAD_REQUEST, err.to_string())
+    }
+}
+
+impl From<eyre::Report> for ApiError {
+    fn from(err: eyre::Report) -> Self {
+        Self(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(serde_json::json!({ "error": self.1 }))).into_response()
+    }
+}
+
+/// Requests are handled one at a time, like the prompts would be.
+#[derive(Clone)]
+struct ServerState {
+    app: Arc<tokio::sync::Mutex<crate::app::App>>,
+    token: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct MonthParams {
+    /// Like `2024-05`, defaults to the current month.
+    month: Option<String>,
+}
+
+/// Serves the API on `listen` until the process is stopped.
+///
+/// - `POST /expenses` creates an entry from a JSON or form body with the
+///   fields of `add`
+/// - `GET /expenses` lists entries, filtered by the query like `list`
+/// - `GET /report/month` totals a month, `?month=2024-05`
+pub async fn serve(app: crate::app::App, listen: &str, token: Option<String>) -> Result<()> {
+    let state = ServerState {
+        app: Arc::new(tokio::sync::Mutex::new(app)),
+        token,
+    };
+    let router = axum::Router::new()
+        .route(
+            "/expenses",
+            axum::routing::get(list_expenses).post(create_expense),
+        )
+        .route("/report/month", axum::routing::get(month_report))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            authorize,
+        ))
+        .with_state(state);
+
+    let listener = tokio::net::TcpListener::bind(listen).await?;
+    println!("Listening on http://{}", listener.local_addr()?);
+    axum::serve(listener, router).await?;
+
+    Ok(())
+}
+
+async fn authorize(
+    State(state): State<ServerState>,
+    request: Request,
+    next: axum::middleware::Next,
+) -> Response {
+    if let Some(token) = &state.token {
+        let expected = format!("Bearer {}", token);
+        let authorized = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .is_some_and(|value| value.as_bytes() == expected.as_bytes());
+        if !authorized {
+            tracing::warn!(uri = %request.uri(), "Rejected a request without the token");
+            return ApiError(
+                StatusCode::UNAUTHORIZED,
+                "Missing or wrong token".to_string(),
+            )
+            .into_response();
+        }
+    }
+
+    next.run(request).await
+}
+
+async fn create_expense(
+    State(state): State<ServerState>,
+    request: Request,
+) -> Result<(StatusCode, Json<serde_json::Value>), ApiError> {
+    let form = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|value| {
+            value
+                .as_bytes()
+                .starts_with(b"application/x-www-form-urlencoded")
+        });
+
+    let args = if form {
+        let Form(mut args) = Form::<crate::cli::AddArgs>::from_request(request, &())
+            .await
+            .map_err(ApiError::bad_request)?;
+        // Forms send the fields left empty too.
+        for field in [
+            &mut args.name,
+            &mut args.amount,
+            &mut args.category,
+            &mut args.entry_type,
+            &mut args.notes,
+            &mut args.payer,
+            &mut args.project,
+            &mut args.trip,
+        ] {
+            field.take_if(|value| value.trim().is_empty());
+        }
+        args
+    } else {
+        let Json(args) = Json::<crate::cli::AddArgs>::from_request(request, &())
+            .await
+            .map_err(ApiError::bad_request)?;
+        args
+    };
+    if args.name.is_none() || args.amount.is_none() {
+        return Err(ApiError::bad_request("The name and amount are required"));
+    }
+
+    let mut app = state.app.lock().await;
+    let db = app.load_database().await?;
+    let page = app.add_entry(&db, args).await?;
+
+    Ok((StatusCode::CREATED, Json(app.page_json(&page))))
+}
+
+async fn list_expenses(
+    State(state): State<ServerState>,
+    Query(args): Query<crate::cli::ListArgs>,
+) -> Result<Json<Vec<serde_json::Value>>, ApiError> {
+    let mut app = state.app.lock().await;
+    let db = app.load_database().await?;
+    let pages = app.entries(&db, args).await?;
+
+    Ok(Json(pages.iter().map(|page| app.page_json(page)).collect()))
+}
+
+async fn month_report(
+    State(state): State<ServerState>,
+    Query(params): Query<MonthParams>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let month = match params.month {
+        Some(month) => crate::cli::parse_month(&month).map_err(ApiError::bad_request)?,
+        None => notion::chrono::offset::Local::now().date_naive(),
+    };
+
+    let mut app = state.app.lock().await;
+    let db = app.load_database().await?;
+    let report = app.month_report(&db, month).await?;
+
+    Ok(Json(report.json()))
+}
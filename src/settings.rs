@@ -1,66 +1,1123 @@
-use eyre::{eyre, Result};
 use directories::ProjectDirs;
-use serde::Deserialize;
-use std::collections::HashMap;
+use eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct NotionSettings {
+    /// Only used when the system keychain has no key.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub api_key: String,
+    /// The last of `databases` when not set.
+    #[serde(default = "no_database")]
     pub database_id: notion::ids::DatabaseId,
+    /// Expense databases picked from at startup or with `--database`, by name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub databases: BTreeMap<String, notion::ids::DatabaseId>,
+    #[serde(default)]
+    pub retry: RetrySettings,
+    /// Where `report month --to-notion` adds the reports.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reports: Option<ReportsTarget>,
+    /// Test database `--sandbox` works on instead of the expense database.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sandbox: Option<notion::ids::DatabaseId>,
+}
+
+impl Default for NotionSettings {
+    fn default() -> Self {
+        Self {
+            api_key: String::new(),
+            database_id: no_database(),
+            databases: BTreeMap::new(),
+            retry: RetrySettings::default(),
+            reports: None,
+            sandbox: None,
+        }
+    }
+}
+
+fn no_database() -> notion::ids::DatabaseId {
+    "".parse().unwrap()
+}
+
+/// Whether `a` and `b` are the same, with or without dashes.
+fn same_id(a: &notion::ids::DatabaseId, b: &notion::ids::DatabaseId) -> bool {
+    a.to_string().replace('-', "") == b.to_string().replace('-', "")
+}
+
+impl NotionSettings {
+    /// The name under `databases` of `database_id`.
+    pub fn current_database(&self) -> Option<&String> {
+        self.databases
+            .iter()
+            .find(|(_, id)| same_id(id, &self.database_id))
+            .map(|(name, _)| name)
+    }
+
+    /// Switches to the database called `name` under `databases`.
+    pub fn use_database(&mut self, name: &str) -> Result<()> {
+        self.database_id = self.databases.get(name).cloned().ok_or_else(|| {
+            eyre!(
+                "No database `{}` under `notion.databases`, expected one of: {}",
+                name,
+                self.databases
+                    .keys()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })?;
+        Ok(())
+    }
+}
+
+/// A page to append the reports to, or a database to add a page per report
+/// to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportsTarget {
+    Page(notion::ids::PageId),
+    Database(notion::ids::DatabaseId),
+}
+
+/// How often failed Notion requests are repeated.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetrySettings {
+    /// Attempts per request, including the first one.
+    pub attempts: u32,
+    /// Delay before the first retry, doubled after each further one.
+    pub delay_ms: u64,
+}
+
+impl Default for RetrySettings {
+    fn default() -> Self {
+        Self {
+            attempts: 4,
+            delay_ms: 500,
+        }
+    }
+}
+
+/// Names of the database properties the dedicated prompts write to.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PropertyNames {
+    pub name: String,
+    pub amount: String,
+    pub date: String,
+    pub category: String,
+    /// Select property telling expenses, income and transfers apart.
+    #[serde(rename = "type")]
+    pub entry_type: String,
+    /// Rich text property with a longer description.
+    pub notes: String,
+    /// Multi-select property.
+    pub tags: String,
+    /// Relation property linking a refund to the expense it returns.
+    pub refund_of: String,
+    /// Select or people property naming who paid.
+    pub payer: String,
+    /// Checkbox property marking expenses that are paid back.
+    pub reimbursable: String,
+    /// Checkbox or status property telling whether they were. Statuses in the
+    /// Complete group count as reimbursed.
+    pub reimbursed: String,
+    /// Select property naming the project or employer that pays back.
+    pub project: String,
+    /// Relation property grouping entries by trip, prompted like the category.
+    pub trip: String,
+    /// Files property attached receipts go to; without it they are added to
+    /// the page content.
+    pub receipt: String,
+    /// Select property set to the currency an amount was typed with.
+    pub currency: String,
+}
+
+impl Default for PropertyNames {
+    fn default() -> Self {
+        Self {
+            name: "Name".to_string(),
+            amount: "Amount".to_string(),
+            date: "Date".to_string(),
+            category: "Category".to_string(),
+            entry_type: "Type".to_string(),
+            notes: "Notes".to_string(),
+            tags: "Tags".to_string(),
+            refund_of: "Refund of".to_string(),
+            payer: "Payer".to_string(),
+            reimbursable: "Reimbursable".to_string(),
+            reimbursed: "Reimbursed".to_string(),
+            project: "Project".to_string(),
+            trip: "Trip".to_string(),
+            receipt: "Receipt".to_string(),
+            currency: "Currency".to_string(),
+        }
+    }
+}
+
+impl PropertyNames {
+    pub fn contains(&self, property: &str) -> bool {
+        [
+            &self.name,
+            &self.amount,
+            &self.date,
+            &self.category,
+            &self.entry_type,
+            &self.notes,
+            &self.tags,
+            &self.refund_of,
+            &self.payer,
+            &self.reimbursable,
+            &self.reimbursed,
+            &self.project,
+            &self.trip,
+            &self.receipt,
+            &self.currency,
+        ]
+        .iter()
+        .any(|name| name.as_str() == property)
+    }
+}
+
+/// Columns of a bank statement export and how to read them.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CsvColumns {
+    pub name: String,
+    pub amount: String,
+    pub date: String,
+    /// `chrono` format of the date column.
+    pub date_format: String,
+    pub delimiter: char,
+}
+
+impl Default for CsvColumns {
+    fn default() -> Self {
+        Self {
+            name: "Name".to_string(),
+            amount: "Amount".to_string(),
+            date: "Date".to_string(),
+            date_format: "%Y-%m-%d".to_string(),
+            delimiter: ',',
+        }
+    }
+}
+
+/// Accounts the entries are booked to by `export --format ledger`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LedgerAccounts {
+    /// The account expenses are paid from and income is paid into.
+    pub account: String,
+    /// Parent of the category accounts, by entry type.
+    pub expenses: String,
+    pub income: String,
+    pub transfers: String,
+    /// Accounts by category name, instead of one under the parent.
+    pub categories: HashMap<String, String>,
+    /// Written after every amount, like `EUR`.
+    pub commodity: Option<String>,
+}
+
+impl Default for LedgerAccounts {
+    fn default() -> Self {
+        Self {
+            account: "Assets:Checking".to_string(),
+            expenses: "Expenses".to_string(),
+            income: "Income".to_string(),
+            transfers: "Assets".to_string(),
+            categories: HashMap::new(),
+            commodity: None,
+        }
+    }
+}
+
+impl LedgerAccounts {
+    /// The account `category` is booked to for entries of `entry_type`.
+    pub fn category_account(
+        &self,
+        category: Option<&str>,
+        entry_type: crate::report::EntryType,
+    ) -> String {
+        let category = category.unwrap_or("Uncategorized");
+        if let Some(account) = self.categories.get(category) {
+            return account.clone();
+        }
+
+        let parent = match entry_type {
+            crate::report::EntryType::Expense => &self.expenses,
+            crate::report::EntryType::Income => &self.income,
+            crate::report::EntryType::Transfer => &self.transfers,
+        };
+        format!("{}:{}", parent, category)
+    }
+}
+
+/// An expense booked every month, see `recurring run`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RecurringExpense {
+    pub name: String,
+    pub amount: rust_decimal::Decimal,
+    pub category: String,
+    /// Day of the month it's due on, the last day in shorter months.
+    pub day: u32,
+}
+
+/// Body of the notifications.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotifyFormat {
+    /// The message as plain text, for ntfy.
+    #[default]
+    Text,
+    /// `{"event": ..., "message": ...}` with the entry's fields.
+    Json,
+    /// `{"text": ...}` for a Slack incoming webhook.
+    Slack,
+}
+
+/// Messages posted to `url` when an entry is added, and when it takes its
+/// category past `budget_threshold` of the budget. Entries uploaded in bulk
+/// aren't announced.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NotifySettings {
+    pub url: String,
+    #[serde(default)]
+    pub format: NotifyFormat,
+    /// `{name}`, `{amount}`, `{category}` and `{date}` are filled in.
+    #[serde(default = "default_entry_template")]
+    pub entry_template: String,
+    /// `{category}`, `{spent}` and `{limit}` are filled in.
+    #[serde(default = "default_budget_template")]
+    pub budget_template: String,
+    /// Share of the budget, 1 is all of it.
+    #[serde(default = "default_budget_threshold")]
+    pub budget_threshold: f64,
+}
+
+/// How the connection to the SMTP server is secured.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpSecurity {
+    /// TLS from the start, port 465 by default.
+    Tls,
+    /// Upgraded to TLS after connecting, port 587 by default.
+    #[default]
+    Starttls,
+    /// Unencrypted, for a relay on the same machine. Port 25 by default.
+    None,
+}
+
+/// Where `--email` sends reports and digests.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EmailSettings {
+    pub host: String,
+    /// Defaults to the one for `security`.
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub security: SmtpSecurity,
+    pub username: Option<String>,
+    /// Taken from `EXPENSE_TRACKER_SMTP_PASSWORD` when not set.
+    pub password: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+}
+
+/// Picks the category of the entries whose title matches, without asking.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CategoryRule {
+    /// Like `LIDL*`, ignoring case; `*` is any text and `?` any character.
+    pub glob: Option<String>,
+    /// A regular expression the title has to match, instead of `glob`.
+    pub regex: Option<String>,
+    pub category: String,
+    /// The smallest amount the rule fires for.
+    pub min: Option<rust_decimal::Decimal>,
+    /// The largest amount the rule fires for.
+    pub max: Option<rust_decimal::Decimal>,
+}
+
+/// Where `import bank` pulls transactions from, a GoCardless Bank Account
+/// Data app.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BankSettings {
+    pub secret_id: String,
+    /// Taken from `EXPENSE_TRACKER_BANK_SECRET_KEY` when not set.
+    pub secret_key: Option<String>,
+    /// As printed by `import bank --link`.
+    #[serde(default)]
+    pub accounts: Vec<String>,
+    /// How many days back to look.
+    #[serde(default = "default_bank_days")]
+    pub days: u32,
+}
+
+fn default_bank_days() -> u32 {
+    30
+}
+
+/// Where `ingest imap` looks for e-receipts.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImapSettings {
+    pub host: String,
+    #[serde(default = "default_imap_port")]
+    pub port: u16,
+    pub username: String,
+    /// Taken from `EXPENSE_TRACKER_IMAP_PASSWORD` when not set.
+    pub password: Option<String>,
+    #[serde(default = "default_imap_folder")]
+    pub folder: String,
+    /// Only mails with one of these in the subject are read, ignoring case.
+    #[serde(default = "default_imap_subjects")]
+    pub subjects: Vec<String>,
+    /// How many days back to look.
+    #[serde(default = "default_imap_days")]
+    pub days: u32,
+    /// Tried in order, the first one finding an amount wins.
+    #[serde(default = "default_receipt_rules")]
+    pub rules: Vec<ReceiptRule>,
+}
+
+fn default_imap_port() -> u16 {
+    993
+}
+
+fn default_imap_folder() -> String {
+    "INBOX".to_string()
+}
+
+fn default_imap_subjects() -> Vec<String> {
+    ["receipt", "order", "订单"].map(String::from).to_vec()
+}
+
+fn default_imap_days() -> u32 {
+    30
+}
+
+/// What was paid, and failing that any total, since a subtotal can come first.
+fn default_receipt_rules() -> Vec<ReceiptRule> {
+    [
+        r"(?i)(?:grand total|order total|total paid|amount paid|实付款|实付)\D{0,20}?(\d[\d,]*\.\d{2})",
+        r"(?i)(?:total|合计|总计)\D{0,20}?(\d[\d,]*\.\d{2})",
+    ]
+    .map(|amount| ReceiptRule {
+        from: None,
+        merchant: None,
+        amount: amount.to_string(),
+        date: None,
+        date_format: None,
+    })
+    .to_vec()
+}
+
+/// Regular expressions reading an e-receipt. Each one's first group is used,
+/// or the whole match when it has none.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReceiptRule {
+    /// Only applies to mails whose sender contains this, ignoring case.
+    pub from: Option<String>,
+    /// The sender's name when not set.
+    pub merchant: Option<String>,
+    pub amount: String,
+    /// The day the mail was sent when not set.
+    pub date: Option<String>,
+    /// Format of the `date` match, like `%Y年%m月%d日`. The common numeric
+    /// formats are tried when not set.
+    pub date_format: Option<String>,
+}
+
+/// How `serve` listens for requests.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ServerSettings {
+    pub listen: String,
+    /// Required as `Authorization: Bearer <token>` when set.
+    pub token: Option<String>,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self {
+            listen: "127.0.0.1:8080".to_string(),
+            token: None,
+        }
+    }
+}
+
+/// How `add --from-receipt` reads the text off a photo.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct OcrSettings {
+    /// Program and arguments printing the text of the image, `{path}` being
+    /// replaced by its path.
+    pub command: Vec<String>,
+}
+
+impl Default for OcrSettings {
+    fn default() -> Self {
+        Self {
+            command: ["tesseract", "{path}", "stdout"].map(String::from).to_vec(),
+        }
+    }
+}
+
+/// Range of days the Date prompt offers.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DateSettings {
+    /// How far back from today dates can be picked.
+    pub min_days_back: u64,
+    /// Whether dates after today can be picked.
+    pub allow_future: bool,
+    /// Whether to also ask for the time of day and store a timestamp.
+    pub with_time: bool,
+}
+
+impl Default for DateSettings {
+    fn default() -> Self {
+        Self {
+            min_days_back: 30,
+            allow_future: false,
+            with_time: false,
+        }
+    }
+}
+
+/// When an amount has to be confirmed, so a missing decimal point is caught.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LargeAmountSettings {
+    /// Times the average of the category among the recent entries.
+    pub factor: Option<rust_decimal::Decimal>,
+    /// Any amount above this.
+    pub limit: Option<rust_decimal::Decimal>,
+}
+
+impl Default for LargeAmountSettings {
+    fn default() -> Self {
+        Self {
+            factor: Some(rust_decimal::Decimal::from(3)),
+            limit: None,
+        }
+    }
+}
+
+/// What the recent entries are ordered by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecentSort {
+    #[default]
+    Date,
+    /// For databases without a Date property.
+    CreatedTime,
+}
+
+/// How the recent entries are listed at startup.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DisplaySettings {
+    pub recent_count: u8,
+    pub sort_by: RecentSort,
+    /// Open each created entry in the browser and copy its link, like `add --open`.
+    pub open_created: bool,
+    /// How the dates of the entries are shown, like `%d.%m.%Y`.
+    #[serde(deserialize_with = "de_date_format")]
+    pub date_format: String,
+    /// The day weeks start on, like `sun`.
+    pub week_start: notion::chrono::Weekday,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        Self {
+            recent_count: 5,
+            sort_by: RecentSort::Date,
+            open_created: false,
+            date_format: "%Y-%m-%d".to_string(),
+            week_start: notion::chrono::Weekday::Mon,
+        }
+    }
+}
+
+impl DisplaySettings {
+    pub fn format_date(&self, date: notion::chrono::NaiveDate) -> String {
+        date.format(&self.date_format).to_string()
+    }
+}
+
+/// A field of a new entry, prompted for in the order of `prompts.order`.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PromptField {
+    Name,
+    Amount,
+    Date,
+    Category,
+    Trip,
+    Type,
+    Payer,
+    Reimbursable,
+    Notes,
+    Tags,
+    /// The properties of the database without a setting of their own.
+    Other,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PromptSettings {
+    /// Like `[amount, name]`, the fields left out follow in the usual order.
+    pub order: Vec<PromptField>,
+    /// Fields never asked for, left out unless given a value.
+    pub skip: Vec<PromptField>,
+    /// Values new entries get as if passed as flags, like `category: Food`.
+    pub defaults: crate::cli::AddArgs,
+}
+
+impl PromptSettings {
+    /// Every field of `order` once, then the rest.
+    pub fn sequence(&self) -> Vec<PromptField> {
+        let mut sequence = vec![];
+        let usual = [
+            PromptField::Name,
+            PromptField::Amount,
+            PromptField::Date,
+            PromptField::Category,
+            PromptField::Trip,
+            PromptField::Type,
+            PromptField::Payer,
+            PromptField::Reimbursable,
+            PromptField::Notes,
+            PromptField::Tags,
+            PromptField::Other,
+        ];
+        for field in self.order.iter().chain(&usual) {
+            if !sequence.contains(field) {
+                sequence.push(*field);
+            }
+        }
+        sequence
+    }
+}
+
+/// What the entries of a title under `map` are filled in with, leaving only
+/// the date to be asked for.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Template {
+    pub title: String,
+    pub amount: Option<rust_decimal::Decimal>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// A title under `map`, alone like `Coffee` or as a template like
+/// `{title: Coffee, amount: 3.5, tags: [daily]}`.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum MappedTitle {
+    Title(String),
+    Template(Template),
 }
 
 #[derive(Debug, Default)]
 struct PredefinedExpenses {
     normalized: HashMap<String, String>,
     original: Vec<String>,
+    /// By the titles in lowercase.
+    templates: HashMap<String, Template>,
+}
+
+/// Where the expenses are kept.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Storage {
+    #[default]
+    Notion,
+    /// A local database in the data directory, the `notion` section isn't needed.
+    Sqlite,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct Settings {
+    #[serde(default)]
+    pub storage: Storage,
+    #[serde(default)]
     pub notion: NotionSettings,
-    #[serde(deserialize_with = "de_map")]
+    #[serde(default)]
+    pub properties: PropertyNames,
+    /// Monthly spending limits by category name.
+    #[serde(default)]
+    pub budgets: HashMap<String, f64>,
+    /// Tried in order when adding and importing, before asking for a category.
+    #[serde(default)]
+    pub rules: Vec<CategoryRule>,
+    /// Shares of the shared expenses by payer, like `Alex: 60`; equal when unset.
+    #[serde(default)]
+    pub shares: HashMap<String, f64>,
+    /// Symbols an amount can be typed with by the currency code they stand
+    /// for, like `EUR: [€]`. The codes themselves are known too.
+    #[serde(default = "default_currencies")]
+    pub currencies: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    pub csv: CsvColumns,
+    #[serde(default)]
+    pub ledger: LedgerAccounts,
+    #[serde(default)]
+    pub display: DisplaySettings,
+    #[serde(default)]
+    pub date: DateSettings,
+    #[serde(default)]
+    pub large_amount: LargeAmountSettings,
+    #[serde(default, alias = "prompt")]
+    pub prompts: PromptSettings,
+    #[serde(default)]
+    pub recurring: Vec<RecurringExpense>,
+    #[serde(default)]
+    pub server: ServerSettings,
+    #[serde(default)]
+    pub ocr: OcrSettings,
+    pub notify: Option<NotifySettings>,
+    pub email: Option<EmailSettings>,
+    pub imap: Option<ImapSettings>,
+    pub bank: Option<BankSettings>,
+    /// Minutes the category pages are cached for.
+    #[serde(default = "default_cache_ttl")]
+    pub cache_ttl: i64,
+    /// How typed amounts are written, like `en` for `1,234.56` or `de` for
+    /// `1.234,56`.
+    #[serde(default = "default_locale")]
+    pub locale: String,
+    /// Digits after the point amounts are rounded to before they're stored.
+    #[serde(default = "default_decimal_places")]
+    pub decimal_places: u32,
+    /// Names typed amounts can use, like `lunch: 12` for `lunch+2.5`.
+    #[serde(default)]
+    pub variables: HashMap<String, rust_decimal::Decimal>,
+    #[serde(default, deserialize_with = "de_map")]
     map: PredefinedExpenses,
+    /// Name of the profile under `profiles` these settings were read from.
+    #[serde(skip)]
+    pub profile: Option<String>,
+    /// The config file, unless everything came from the environment.
+    #[serde(skip)]
+    path: Option<PathBuf>,
+    /// Set by `--read-only`, nothing is written then.
+    #[serde(skip)]
+    pub read_only: bool,
+    /// Set by `--sandbox`, which keeps its own journal and local databases.
+    #[serde(skip)]
+    pub sandbox: bool,
+}
+
+/// Why a change was refused with `--read-only`.
+pub fn read_only_error() -> eyre::Report {
+    eyre!("Nothing is changed in read-only mode")
+}
+
+/// Replaces `notion.api_key` in the config.
+pub const API_KEY_VAR: &str = "NOTION_API_KEY";
+/// Replaces `notion.database_id` in the config.
+pub const DATABASE_ID_VAR: &str = "EXPENSE_TRACKER_DATABASE_ID";
+/// The SMTP password, unless `email.password` is set.
+pub const SMTP_PASSWORD_VAR: &str = "EXPENSE_TRACKER_SMTP_PASSWORD";
+/// The IMAP password, unless `imap.password` is set.
+pub const IMAP_PASSWORD_VAR: &str = "EXPENSE_TRACKER_IMAP_PASSWORD";
+/// The GoCardless secret key, unless `bank.secret_key` is set.
+pub const BANK_SECRET_KEY_VAR: &str = "EXPENSE_TRACKER_BANK_SECRET_KEY";
+
+/// Sets `notion.<key>` in a parsed config, creating the sections as needed.
+fn override_notion(config: &mut serde_yaml::Value, key: &str, value: Option<String>) {
+    let Some(value) = value else {
+        return;
+    };
+
+    if !config.is_mapping() {
+        *config = serde_yaml::Mapping::new().into();
+    }
+    let notion = config
+        .as_mapping_mut()
+        .unwrap()
+        .entry("notion".into())
+        .or_insert_with(|| serde_yaml::Mapping::new().into());
+    if !notion.is_mapping() {
+        *notion = serde_yaml::Mapping::new().into();
+    }
+
+    notion
+        .as_mapping_mut()
+        .unwrap()
+        .insert(key.into(), value.into());
+}
+
+const KEYRING_SERVICE: &str = "notion-bot";
+
+fn keyring_entry(profile: Option<&str>) -> Result<keyring::Entry> {
+    let user = match profile {
+        Some(profile) => format!("api_key.{}", profile),
+        None => "api_key".to_string(),
+    };
+
+    Ok(keyring::Entry::new(KEYRING_SERVICE, &user)?)
+}
+
+/// The API key kept in the system keychain by `config set-key`.
+pub fn keychain_api_key(profile: Option<&str>) -> Result<Option<String>> {
+    match keyring_entry(profile)?.get_password() {
+        Ok(api_key) => Ok(Some(api_key)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub fn store_keychain_api_key(profile: Option<&str>, api_key: &str) -> Result<()> {
+    Ok(keyring_entry(profile)?.set_password(api_key)?)
+}
+
+/// Lays the sections of `profiles.<profile>` over the top-level ones.
+fn select_profile(config: serde_yaml::Value, profile: Option<&str>) -> Result<serde_yaml::Value> {
+    let serde_yaml::Value::Mapping(mut config) = config else {
+        return match profile {
+            Some(profile) => Err(eyre!("Unknown profile `{}`", profile)),
+            None => Ok(config),
+        };
+    };
+
+    let profiles = config.remove("profiles");
+    let Some(profile) = profile else {
+        return Ok(config.into());
+    };
+
+    let sections = match profiles.as_ref().and_then(|profiles| profiles.get(profile)) {
+        Some(serde_yaml::Value::Mapping(sections)) => sections.clone(),
+        Some(serde_yaml::Value::Null) => serde_yaml::Mapping::new(),
+        _ => return Err(eyre!("Unknown profile `{}`", profile)),
+    };
+    config.extend(sections);
+
+    Ok(config.into())
+}
+
+/// The sections holding `key` for `profile`: the profile's own when it has
+/// it, the top-level ones otherwise.
+fn profile_sections<'a>(
+    config: &'a mut serde_yaml::Value,
+    profile: Option<&str>,
+    key: &str,
+) -> &'a mut serde_yaml::Value {
+    let in_profile = profile.is_some_and(|profile| {
+        config
+            .get("profiles")
+            .and_then(|profiles| profiles.get(profile))
+            .and_then(|sections| sections.get(key))
+            .is_some()
+    });
+    match profile {
+        Some(profile) if in_profile => &mut config["profiles"][profile],
+        _ => config,
+    }
+}
+
+/// The `map` section in effect for `profile`: the profile's own when it has
+/// one, the top-level one otherwise.
+fn map_section<'a>(
+    config: &'a mut serde_yaml::Value,
+    profile: Option<&str>,
+) -> &'a mut serde_yaml::Mapping {
+    let section = profile_sections(config, profile, "map");
+
+    if !section["map"].is_mapping() {
+        section["map"] = serde_yaml::Mapping::new().into();
+    }
+    section["map"].as_mapping_mut().unwrap()
+}
+
+/// Points `notion.database_id` at `database_id`, and lists it as `name` under
+/// `notion.databases` when there are any.
+fn set_database(
+    config: &mut serde_yaml::Value,
+    profile: Option<&str>,
+    name: &str,
+    database_id: &str,
+) {
+    let notion = &mut profile_sections(config, profile, "notion")["notion"];
+    notion["database_id"] = database_id.into();
+    if let Some(databases) = notion
+        .get_mut("databases")
+        .and_then(|databases| databases.as_mapping_mut())
+    {
+        databases.insert(name.into(), database_id.into());
+    }
+}
+
+/// Sets `properties.<setting>` to `property`.
+fn set_property(
+    config: &mut serde_yaml::Value,
+    profile: Option<&str>,
+    setting: &str,
+    property: &str,
+) {
+    let section = profile_sections(config, profile, "properties");
+    if !section["properties"].is_mapping() {
+        section["properties"] = serde_yaml::Mapping::new().into();
+    }
+    section["properties"][setting] = property.into();
+}
+
+/// The title of an item under `map`, either a title or a template.
+fn mapped_title(item: &serde_yaml::Value) -> Option<&str> {
+    item.as_str().or_else(|| item.get("title")?.as_str())
+}
+
+/// Adds `title` to the titles of `category` under `map`.
+fn add_mapping(config: &mut serde_yaml::Value, profile: Option<&str>, category: &str, title: &str) {
+    let map = map_section(config, profile);
+
+    let key = map
+        .keys()
+        .find(|key| {
+            key.as_str()
+                .is_some_and(|key| key.eq_ignore_ascii_case(category))
+        })
+        .cloned()
+        .unwrap_or_else(|| category.into());
+    let titles = map
+        .entry(key)
+        .or_insert(serde_yaml::Value::Sequence(vec![]));
+    if !titles.is_sequence() {
+        *titles = serde_yaml::Value::Sequence(vec![]);
+    }
+
+    let titles = titles.as_sequence_mut().unwrap();
+    if !titles.iter().any(|t| mapped_title(t) == Some(title)) {
+        titles.push(title.into());
+    }
+}
+
+/// Removes `title` from `map`, along with categories left without titles.
+/// Returns whether it was there.
+fn remove_mapping(config: &mut serde_yaml::Value, profile: Option<&str>, title: &str) -> bool {
+    let map = map_section(config, profile);
+
+    let mut removed = false;
+    for titles in map.values_mut() {
+        if let Some(titles) = titles.as_sequence_mut() {
+            let count = titles.len();
+            titles.retain(|t| !mapped_title(t).is_some_and(|t| t.eq_ignore_ascii_case(title)));
+            removed |= titles.len() != count;
+        }
+    }
+    map.retain(|_, titles| !titles.as_sequence().is_some_and(Vec::is_empty));
+
+    removed
+}
+
+fn default_cache_ttl() -> i64 {
+    24 * 60
+}
+
+fn default_currencies() -> HashMap<String, Vec<String>> {
+    [
+        ("EUR", "€"),
+        ("USD", "$"),
+        ("GBP", "£"),
+        ("JPY", "¥"),
+        ("INR", "₹"),
+        ("RUB", "₽"),
+    ]
+    .into_iter()
+    .map(|(code, symbol)| (code.to_string(), vec![symbol.to_string()]))
+    .collect()
+}
+
+fn default_locale() -> String {
+    "en".to_string()
+}
+
+fn default_decimal_places() -> u32 {
+    2
+}
+
+fn default_entry_template() -> String {
+    "{name}: {amount} ({category})".to_string()
+}
+
+fn default_budget_template() -> String {
+    "{category}: {spent} of the {limit} budget spent this month".to_string()
+}
+
+fn default_budget_threshold() -> f64 {
+    1.0
+}
+
+pub fn project_dirs() -> Result<ProjectDirs> {
+    ProjectDirs::from("", "", "notion").ok_or_else(|| eyre!("Couldn't retrive project dirs"))
+}
+
+/// Where the journal and local data of `profile` live, each profile having its
+/// own directory next to the default one.
+pub fn data_dir(profile: Option<&str>) -> Result<PathBuf> {
+    let mut dir = project_dirs()?.data_dir().to_path_buf();
+    if let Some(profile) = profile {
+        dir = dir.join("profiles").join(profile);
+    }
+    std::fs::create_dir_all(&dir)?;
+
+    Ok(dir)
+}
+
+fn de_date_format<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let format: String = Deserialize::deserialize(deserializer)?;
+    match notion::chrono::format::StrftimeItems::new(&format).parse() {
+        Ok(_) => Ok(format),
+        Err(_) => Err(serde::de::Error::custom(format!(
+            "invalid date format `{}`",
+            format
+        ))),
+    }
 }
 
 fn de_map<'de, D>(deserializer: D) -> Result<PredefinedExpenses, D::Error>
 where
-    D: serde::Deserializer<'de>
+    D: serde::Deserializer<'de>,
 {
-    let map: HashMap<String, Vec<String>> = Deserialize::deserialize(deserializer)?;
+    let map: HashMap<String, Vec<MappedTitle>> = Deserialize::deserialize(deserializer)?;
 
-    let result = map.iter().fold(PredefinedExpenses::default(), |mut acc, item| {
-        item.1.iter().for_each(|name| {
-            let _ = acc.normalized.insert(name.clone().to_lowercase(), item.0.clone());
-            if !acc.original.contains(name) {
-                acc.original.push(name.to_string());
-            }
-        });
+    let result = map
+        .iter()
+        .fold(PredefinedExpenses::default(), |mut acc, item| {
+            item.1.iter().for_each(|title| {
+                let name = match title {
+                    MappedTitle::Title(name) => name,
+                    MappedTitle::Template(template) => {
+                        acc.templates
+                            .insert(template.title.to_lowercase(), template.clone());
+                        &template.title
+                    }
+                };
+                let _ = acc
+                    .normalized
+                    .insert(name.clone().to_lowercase(), item.0.clone());
+                if !acc.original.contains(name) {
+                    acc.original.push(name.to_string());
+                }
+            });
 
-        acc
-    });
+            acc
+        });
 
     Ok(result)
 }
 
 impl Settings {
-    pub fn new() -> Result<Self> {
-        Self::config_path()
-            .and_then(|path| File::open(path).map_err(eyre::Error::new))
-            .and_then(|file| serde_yaml::from_reader(file).map_err(eyre::Error::new))
+    /// Reads the config at `config`, or at the default location, with the
+    /// environment overrides applied. The file may be missing when the
+    /// environment provides the Notion connection.
+    ///
+    /// The API key comes from the environment, then the system keychain, then
+    /// the file.
+    pub fn new(config: Option<&Path>, profile: Option<&str>) -> Result<Self> {
+        let path = Self::config_path(config)?;
+        let api_key = std::env::var(API_KEY_VAR)
+            .ok()
+            .or_else(|| keychain_api_key(profile).ok().flatten());
+        let database_id = std::env::var(DATABASE_ID_VAR).ok();
+
+        let exists = Self::exists(&path)?;
+        tracing::debug!(
+            path = %path.display(),
+            exists,
+            profile,
+            api_key_from_env = std::env::var(API_KEY_VAR).is_ok(),
+            database_id_from_env = database_id.is_some(),
+            "Loading the config"
+        );
+        let value = if exists {
+            serde_yaml::from_reader(File::open(&path)?)?
+        } else if api_key.is_some() && database_id.is_some() && profile.is_none() {
+            serde_yaml::Value::Null
+        } else {
+            return Err(eyre!(
+                "{} is missing, run `notion-bot init` to create it",
+                path.display()
+            ));
+        };
+
+        let mut value = select_profile(value, profile)?;
+        override_notion(&mut value, "api_key", api_key);
+        override_notion(&mut value, "database_id", database_id);
+
+        let mut settings: Self = serde_yaml::from_value(value)?;
+        settings.profile = profile.map(str::to_string);
+        settings.path = exists.then_some(path);
+        if settings.storage == Storage::Notion && settings.notion.api_key.is_empty() {
+            return Err(eyre!(
+                "No Notion API key, run `notion-bot config set-key` to store one"
+            ));
+        }
+        let notion = &mut settings.notion;
+        if notion.database_id.to_string().is_empty() {
+            match notion.databases.values().last() {
+                Some(id) => notion.database_id = id.clone(),
+                None if settings.storage == Storage::Notion => {
+                    return Err(eyre!("Set `notion.database_id` or `notion.databases`"))
+                }
+                None => {}
+            }
+        }
+
+        Ok(settings)
     }
 
-    pub fn config_path() -> Result<PathBuf> {
-        let config_path = ProjectDirs::from("", "", "notion")
-            .ok_or_else(|| eyre!("Couldn't retrive project dirs"))
-            .map(|prj_dirs| prj_dirs.config_dir().join("config.yaml"))?;
+    /// Names of the profiles defined in the config.
+    pub fn profiles(config: Option<&Path>) -> Result<Vec<String>> {
+        let path = Self::config_path(config)?;
+        if !Self::exists(&path)? {
+            return Ok(vec![]);
+        }
+
+        let value: serde_yaml::Value = serde_yaml::from_reader(File::open(&path)?)?;
+        let mut names: Vec<String> = match value.get("profiles") {
+            Some(serde_yaml::Value::Mapping(profiles)) => profiles
+                .keys()
+                .filter_map(|name| name.as_str().map(str::to_string))
+                .collect(),
+            _ => vec![],
+        };
+        names.sort();
 
-        if !config_path.exists() {
-            File::create(&config_path)?;
+        Ok(names)
+    }
+
+    /// `config` if given, the file in the user's config directory otherwise.
+    pub fn config_path(config: Option<&Path>) -> Result<PathBuf> {
+        if let Some(config) = config {
+            return Ok(config.to_path_buf());
         }
 
-        Ok(config_path)
+        let config_dir = project_dirs()?.config_dir().to_path_buf();
+        std::fs::create_dir_all(&config_dir)?;
+
+        Ok(config_dir.join("config.yaml"))
+    }
+
+    /// Whether there is a non-empty config file at `path`.
+    pub fn exists(path: &Path) -> Result<bool> {
+        Ok(path.exists() && std::fs::metadata(path)?.len() > 0)
+    }
+
+    /// Writes a config with just the Notion connection, replacing any existing one.
+    pub fn init(path: &Path, notion: &NotionSettings) -> Result<()> {
+        let config = serde_json::json!({ "notion": notion, "map": {} });
+        serde_yaml::to_writer(File::create(path)?, &config)?;
+
+        Ok(())
     }
 
     pub fn get(&self, key: &str) -> Option<&String> {
@@ -70,4 +1127,479 @@ impl Settings {
     pub fn list(&self) -> Vec<&str> {
         self.map.original.iter().map(|s| s.as_str()).collect()
     }
+
+    pub fn template(&self, title: &str) -> Option<&Template> {
+        self.map.templates.get(&title.to_lowercase())
+    }
+
+    /// Removes the mapping of `title`, also from the config file. Returns whether
+    /// there was one.
+    pub fn forget(&mut self, title: &str) -> Result<bool> {
+        let removed = self.edit_config(|config, profile| remove_mapping(config, profile, title))?;
+
+        self.map.normalized.remove(&title.to_lowercase());
+        self.map.templates.remove(&title.to_lowercase());
+        self.map
+            .original
+            .retain(|original| !original.eq_ignore_ascii_case(title));
+
+        Ok(removed)
+    }
+
+    /// Rewrites the config file with `edit` applied to its contents.
+    fn edit_config<T>(
+        &self,
+        edit: impl FnOnce(&mut serde_yaml::Value, Option<&str>) -> T,
+    ) -> Result<T> {
+        self.check_config_writes()?;
+        let path = self
+            .path
+            .as_ref()
+            .ok_or_else(|| eyre!("There is no config file to write to"))?;
+
+        let mut config: serde_yaml::Value = serde_yaml::from_reader(File::open(path)?)?;
+        let result = edit(&mut config, self.profile.as_deref());
+        tracing::debug!(path = %path.display(), "Writing the config");
+        serde_yaml::to_writer(File::create(path)?, &config)?;
+
+        Ok(result)
+    }
+
+    /// Whether there's a config file `remember` can write to.
+    pub fn is_writable(&self) -> bool {
+        self.path.is_some() && self.check_config_writes().is_ok()
+    }
+
+    /// Fails with `--read-only`.
+    pub fn check_writes(&self) -> Result<()> {
+        match self.read_only {
+            true => Err(read_only_error()),
+            false => Ok(()),
+        }
+    }
+
+    /// Fails with `--read-only`, and with `--sandbox` so its database and
+    /// properties stay out of the config.
+    pub fn check_config_writes(&self) -> Result<()> {
+        self.check_writes()?;
+        match self.sandbox {
+            true => Err(eyre!("The config isn't changed with `--sandbox`")),
+            false => Ok(()),
+        }
+    }
+
+    /// Where the journal and the local databases are kept, apart for
+    /// `--sandbox`.
+    pub fn data_dir(&self) -> Result<PathBuf> {
+        let dir = data_dir(self.profile.as_deref())?;
+        if !self.sandbox {
+            return Ok(dir);
+        }
+
+        let dir = dir.join("sandbox");
+        std::fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    /// Maps `title` to `category` from now on, and saves it in the config file.
+    pub fn remember(&mut self, title: &str, category: &str) -> Result<()> {
+        self.edit_config(|config, profile| add_mapping(config, profile, category, title))?;
+
+        self.map
+            .normalized
+            .insert(title.to_lowercase(), category.to_string());
+        if !self.map.original.iter().any(|original| original == title) {
+            self.map.original.push(title.to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Reads the property `setting` names, like `amount`, from `property` from
+    /// now on, and saves it in the config file.
+    pub fn rename_property(&mut self, setting: &str, property: &str) -> Result<()> {
+        let mut names = self.properties.clone();
+        let name = match setting {
+            "name" => &mut names.name,
+            "amount" => &mut names.amount,
+            "date" => &mut names.date,
+            "category" => &mut names.category,
+            _ => return Err(eyre!("Unknown property setting `{}`", setting)),
+        };
+        *name = property.to_string();
+
+        self.edit_config(|config, profile| set_property(config, profile, setting, property))?;
+        self.properties = names;
+
+        Ok(())
+    }
+
+    /// Makes `database_id` the expense database, also in the config file.
+    pub fn switch_database(
+        &mut self,
+        name: &str,
+        database_id: &notion::ids::DatabaseId,
+    ) -> Result<()> {
+        self.edit_config(|config, profile| {
+            set_database(config, profile, name, &database_id.to_string())
+        })?;
+
+        if !self.notion.databases.is_empty() {
+            self.notion
+                .databases
+                .insert(name.to_string(), database_id.clone());
+        }
+        self.notion.database_id = database_id.clone();
+
+        Ok(())
+    }
+
+    /// Newest first by the Date property, or by creation with `sort_by: created_time`.
+    pub fn recent_sorts(&self) -> Vec<notion::models::search::DatabaseSort> {
+        match self.display.sort_by {
+            RecentSort::Date => crate::backend::descending(&self.properties.date),
+            RecentSort::CreatedTime => crate::backend::newest_created(),
+        }
+    }
+
+    pub fn budget(&self, category: &str) -> Option<f64> {
+        let category = category.to_lowercase();
+        self.budgets
+            .iter()
+            .find(|(name, _)| name.to_lowercase() == category)
+            .map(|(_, limit)| *limit)
+    }
+}
+
+#[test]
+fn property_names_test() {
+    let settings: Settings = serde_yaml::from_str(
+        r#"
+notion:
+  api_key: secret
+  database_id: db
+properties:
+  amount: Betrag
+  date: Datum
+map:
+  Food: [Coffee]
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(settings.properties.name, "Name");
+    assert_eq!(settings.properties.amount, "Betrag");
+    assert_eq!(settings.properties.date, "Datum");
+    assert!(settings.properties.contains("Category"));
+    assert_eq!(settings.get("coffee").map(String::as_str), Some("Food"));
+}
+
+#[test]
+fn budgets_test() {
+    let settings: Settings = serde_yaml::from_str(
+        r#"
+notion:
+  api_key: secret
+  database_id: db
+budgets:
+  Food: 300
+  Transport: 50.5
+map: {}
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(settings.budget("food"), Some(300.0));
+    assert_eq!(settings.budget("Transport"), Some(50.5));
+    assert_eq!(settings.budget("Rent"), None);
+}
+
+#[test]
+fn recent_sorts_test() {
+    let settings: Settings = serde_yaml::from_str(
+        r#"
+notion:
+  api_key: secret
+  database_id: db
+display:
+  sort_by: created_time
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(settings.display.recent_count, 5);
+    assert_eq!(settings.recent_sorts(), crate::backend::newest_created());
+}
+
+#[test]
+fn display_test() {
+    let settings: Settings = serde_yaml::from_str(
+        r#"
+notion:
+  api_key: secret
+  database_id: db
+display:
+  date_format: "%d.%m.%Y"
+  week_start: sun
+prompts:
+  order: [amount, name, tags]
+  skip: [notes]
+  defaults:
+    category: Food
+    type: Expense
+"#,
+    )
+    .unwrap();
+
+    let date = notion::chrono::NaiveDate::from_ymd_opt(2024, 5, 2).unwrap();
+    assert_eq!(settings.display.format_date(date), "02.05.2024");
+    assert_eq!(settings.display.week_start, notion::chrono::Weekday::Sun);
+    let sequence = settings.prompts.sequence();
+    assert_eq!(
+        sequence[..4],
+        [
+            PromptField::Amount,
+            PromptField::Name,
+            PromptField::Tags,
+            PromptField::Date
+        ]
+    );
+    assert_eq!(sequence.len(), 11);
+    assert_eq!(settings.prompts.skip, [PromptField::Notes]);
+    assert_eq!(settings.prompts.defaults.category.as_deref(), Some("Food"));
+    assert_eq!(
+        settings.prompts.defaults.entry_type.as_deref(),
+        Some("Expense")
+    );
+
+    let invalid = "notion: {api_key: secret, database_id: db}\ndisplay: {date_format: \"%Q\"}";
+    assert!(serde_yaml::from_str::<Settings>(invalid).is_err());
+}
+
+#[test]
+fn edit_mapping_test() {
+    let mut config: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+map:
+  food: [Coffee, {title: Latte, amount: 4}]
+profiles:
+  work:
+    map: {}
+  family: {}
+"#,
+    )
+    .unwrap();
+
+    add_mapping(&mut config, None, "Food", "Bagel");
+    add_mapping(&mut config, None, "Food", "Bagel");
+    add_mapping(&mut config, None, "Food", "Latte");
+    add_mapping(&mut config, Some("work"), "Travel", "Taxi");
+    add_mapping(&mut config, Some("family"), "Rent", "Flat");
+    assert!(remove_mapping(&mut config, None, "coffee"));
+    assert!(!remove_mapping(&mut config, None, "Tea"));
+    set_property(&mut config, Some("family"), "amount", "Cost");
+
+    assert_eq!(
+        config,
+        serde_yaml::from_str::<serde_yaml::Value>(
+            r#"
+map:
+  food: [{title: Latte, amount: 4}, Bagel]
+  Rent: [Flat]
+properties:
+  amount: Cost
+profiles:
+  work:
+    map:
+      Travel: [Taxi]
+  family: {}
+"#
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn template_test() {
+    let settings: Settings = serde_yaml::from_str(
+        r#"
+notion:
+  api_key: secret
+  database_id: db
+map:
+  food:
+    - Bagel
+    - title: Coffee
+      amount: 3.5
+      tags: [daily]
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(settings.get("coffee").map(String::as_str), Some("food"));
+    assert_eq!(settings.list().len(), 2);
+    assert_eq!(
+        settings.template("Coffee"),
+        Some(&Template {
+            title: "Coffee".to_string(),
+            amount: Some(rust_decimal::Decimal::new(35, 1)),
+            tags: vec!["daily".to_string()],
+        })
+    );
+    assert_eq!(settings.template("Bagel"), None);
+}
+
+#[test]
+fn override_notion_test() {
+    let mut config: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+notion:
+  api_key: from-file
+  database_id: db
+"#,
+    )
+    .unwrap();
+    override_notion(&mut config, "api_key", Some("from-env".to_string()));
+    override_notion(&mut config, "database_id", None);
+
+    let settings: Settings = serde_yaml::from_value(config).unwrap();
+    assert_eq!(settings.notion.api_key, "from-env");
+    assert_eq!(settings.notion.database_id.to_string(), "db");
+
+    let mut config = serde_yaml::Value::Null;
+    override_notion(&mut config, "api_key", Some("key".to_string()));
+    override_notion(&mut config, "database_id", Some("db".to_string()));
+
+    let settings: Settings = serde_yaml::from_value(config).unwrap();
+    assert_eq!(settings.notion.api_key, "key");
+    assert!(settings.list().is_empty());
+}
+
+#[test]
+fn select_profile_test() {
+    let config: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+notion:
+  api_key: personal
+  database_id: personal-db
+properties:
+  amount: Betrag
+map:
+  Food: [Coffee]
+profiles:
+  business:
+    notion:
+      api_key: business
+      database_id: business-db
+    map:
+      Travel: [Taxi]
+"#,
+    )
+    .unwrap();
+
+    let settings: Settings =
+        serde_yaml::from_value(select_profile(config.clone(), Some("business")).unwrap()).unwrap();
+    assert_eq!(settings.notion.database_id.to_string(), "business-db");
+    assert_eq!(settings.properties.amount, "Betrag");
+    assert_eq!(settings.list(), vec!["Taxi"]);
+
+    let settings: Settings =
+        serde_yaml::from_value(select_profile(config.clone(), None).unwrap()).unwrap();
+    assert_eq!(settings.notion.database_id.to_string(), "personal-db");
+
+    assert!(select_profile(config, Some("missing")).is_err());
+}
+
+#[test]
+fn databases_test() {
+    let mut settings: Settings = serde_yaml::from_str(
+        r#"
+notion:
+  api_key: secret
+  database_id: 2024aaaa-0000-0000-0000-000000000000
+  databases:
+    "2024": 2024aaaa000000000000000000000000
+    "2025": 2025aaaa000000000000000000000000
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        settings.notion.current_database().map(String::as_str),
+        Some("2024")
+    );
+    settings.notion.use_database("2025").unwrap();
+    assert_eq!(
+        settings.notion.database_id.to_string(),
+        "2025aaaa000000000000000000000000"
+    );
+    assert!(settings.notion.use_database("2023").is_err());
+
+    let mut config: serde_yaml::Value = serde_yaml::from_str(
+        r#"
+notion:
+  database_id: old
+  databases: {"2025": old}
+profiles:
+  work:
+    notion: {database_id: work}
+"#,
+    )
+    .unwrap();
+    set_database(&mut config, None, "2026", "new");
+    set_database(&mut config, Some("work"), "2026", "work2");
+    assert_eq!(
+        config,
+        serde_yaml::from_str::<serde_yaml::Value>(
+            r#"
+notion:
+  database_id: new
+  databases: {"2025": old, "2026": new}
+profiles:
+  work:
+    notion: {database_id: work2}
+"#
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn read_only_test() {
+    let mut settings: Settings =
+        serde_yaml::from_str("notion: {sandbox: 5ab0aaaa000000000000000000000000}").unwrap();
+    settings.path = Some(PathBuf::from("config.yaml"));
+    assert!(settings.is_writable());
+    assert!(settings.check_writes().is_ok());
+
+    settings.read_only = true;
+    assert!(!settings.is_writable());
+    assert!(settings.check_writes().is_err());
+    assert!(settings
+        .edit_config(|_, _| unreachable!("the config isn't read"))
+        .is_err());
+    assert!(settings.notion.sandbox.is_some());
+}
+
+#[test]
+fn rename_property_test() {
+    let mut settings: Settings = serde_yaml::from_str("{}").unwrap();
+    assert!(settings.rename_property("amount", "Cost").is_err());
+    assert_eq!(settings.properties.amount, "Amount");
+    assert!(settings.rename_property("notes", "Memo").is_err());
+}
+
+#[test]
+fn sandbox_test() {
+    let mut settings: Settings = serde_yaml::from_str("{}").unwrap();
+    settings.path = Some(PathBuf::from("config.yaml"));
+    settings.sandbox = true;
+    assert!(settings.check_writes().is_ok());
+    assert!(!settings.is_writable());
+
+    let database_id: notion::ids::DatabaseId = "5ab0aaaa000000000000000000000000".parse().unwrap();
+    assert!(settings.switch_database("2027", &database_id).is_err());
+    assert_ne!(settings.notion.database_id, database_id);
+    assert!(settings.rename_property("amount", "Cost").is_err());
+    assert_eq!(settings.properties.amount, "Amount");
 }
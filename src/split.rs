@@ -0,0 +1,143 @@
+use eyre::{eyre, Result};
+use rust_decimal::Decimal;
+
+/// Share of a split amount.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Part {
+    pub amount: Decimal,
+    pub category: String,
+}
+
+/// An amount split across categories, each part created as its own entry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Split {
+    pub total: Decimal,
+    pub parts: Vec<Part>,
+}
+
+/// `input` cut at each `+` that follows a category, so `10+5 Groceries` stays
+/// one part.
+fn cut(input: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut start = 0;
+    for (i, c) in input.char_indices() {
+        if c == '+' && input[start..i].chars().any(char::is_alphabetic) {
+            parts.push(&input[start..i]);
+            start = i + 1;
+        }
+    }
+    parts.push(&input[start..]);
+    parts
+}
+
+/// Reads amounts like `30 Groceries + 13.20 Household`, optionally after the
+/// total, as in `43.20 = 30 Groceries + 13.20 Household`. With the total, one
+/// part may leave out its amount to get the rest. `None` when `input` names
+/// no category, so it's a plain amount.
+pub fn parse(input: &str) -> Result<Option<Split>> {
+    if !input.chars().any(char::is_alphabetic) {
+        return Ok(None);
+    }
+
+    let (total, input) = match input.split_once('=') {
+        Some((total, parts)) => (Some(crate::calc::calc(total)?), parts),
+        None => (None, input),
+    };
+
+    let mut parts = vec![];
+    let mut rest = None;
+    for part in cut(input) {
+        let at = part.find(char::is_alphabetic).unwrap_or(part.len());
+        let (amount, category) = (part[..at].trim(), part[at..].trim());
+        if category.is_empty() {
+            return Err(eyre!("Each part needs a category, like `30 Groceries`"));
+        }
+
+        if amount.is_empty() {
+            if total.is_none() || rest.is_some() {
+                return Err(eyre!(
+                    "Only one part may leave out its amount, after the total like `43.20 = Groceries + 13.20 Household`"
+                ));
+            }
+            rest = Some(parts.len());
+            parts.push(Part {
+                amount: Decimal::ZERO,
+                category: category.to_string(),
+            });
+        } else {
+            parts.push(Part {
+                amount: crate::calc::calc(amount)?,
+                category: category.to_string(),
+            });
+        }
+    }
+    if parts.len() < 2 {
+        return Err(eyre!(
+            "Split into two parts or more, like `30 Groceries + 13.20 Household`"
+        ));
+    }
+
+    let sum: Decimal = parts.iter().map(|part| part.amount).sum();
+    let total = match (total, rest) {
+        (Some(total), Some(rest)) => {
+            parts[rest].amount = crate::calc::round(total - sum, 2);
+            if parts[rest].amount <= Decimal::ZERO {
+                return Err(eyre!(
+                    "The parts add up to {:.2}, more than {:.2}",
+                    crate::calc::round(sum, 2),
+                    crate::calc::round(total, 2)
+                ));
+            }
+            total
+        }
+        (Some(total), None) if !crate::calc::round(total - sum, 2).is_zero() => {
+            return Err(eyre!(
+                "The parts add up to {:.2}, not {:.2}",
+                crate::calc::round(sum, 2),
+                crate::calc::round(total, 2)
+            ));
+        }
+        (Some(total), None) => total,
+        (None, _) => sum,
+    };
+
+    Ok(Some(Split { total, parts }))
+}
+
+impl std::fmt::Display for Split {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parts: Vec<String> = self
+            .parts
+            .iter()
+            .map(|part| {
+                format!(
+                    "{:.2} {}",
+                    crate::calc::round(part.amount, 2),
+                    part.category
+                )
+            })
+            .collect();
+        write!(f, "{}", parts.join(" + "))
+    }
+}
+
+#[test]
+fn parse_test() {
+    assert_eq!(parse("10+2.5").unwrap(), None);
+
+    let split = parse("43.20 = 10+20 Groceries + Home care")
+        .unwrap()
+        .unwrap();
+    assert_eq!(split.total.to_string(), "43.2");
+    assert_eq!(split.parts[0].amount.to_string(), "30");
+    assert_eq!(split.parts[1].category, "Home care");
+    assert_eq!(split.parts[1].amount.to_string(), "13.2");
+    assert_eq!(split.to_string(), "30.00 Groceries + 13.20 Home care");
+
+    let split = parse("30 Groceries + 13.20 Household").unwrap().unwrap();
+    assert_eq!(split.total.to_string(), "43.2");
+
+    assert!(parse("43.20 = 30 Groceries + 10 Household").is_err());
+    assert!(parse("30 Groceries + Household").is_err());
+    assert!(parse("30 Groceries").is_err());
+}
@@ -0,0 +1,600 @@
+use eyre::{eyre, Result};
+use notion::chrono::{DateTime, Utc};
+use notion::models::properties::{DateOrDateTime, PropertyValue};
+use notion::models::search::{
+    CheckboxCondition, DatabaseSort, DatabaseSortTimestamp, DateCondition, PropertyCondition,
+    RelationCondition, SelectCondition, SortDirection, TextCondition,
+};
+use std::cmp::Ordering;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+const ENTRIES_ID: &str = "expenses";
+const CATEGORIES_ID: &str = "categories";
+
+/// Expenses and categories in a local SQLite file, for use without Notion.
+///
+/// Entries keep their properties as Notion JSON, so whatever the prompts create
+/// round-trips, and queries are evaluated in memory.
+pub struct SqliteBackend {
+    connection: Mutex<rusqlite::Connection>,
+    names: crate::settings::PropertyNames,
+}
+
+fn timestamp(value: &str) -> Result<DateTime<Utc>> {
+    Ok(DateTime::parse_from_rfc3339(value)?.with_timezone(&Utc))
+}
+
+fn category_page(id: &str, name: &str, created_time: &str) -> Result<notion::models::Page> {
+    let created_time = timestamp(created_time)?;
+
+    Ok(notion::models::Page {
+        id: notion::ids::PageId::from_str(id)?,
+        created_time,
+        last_edited_time: created_time,
+        archived: false,
+        properties: notion::models::Properties {
+            properties: [(
+                "Name".to_string(),
+                PropertyValue::Title {
+                    id: notion::ids::PropertyId::from_str("title")?,
+                    title: crate::prompt::rich_text(name),
+                },
+            )]
+            .into(),
+        },
+        parent: notion::models::Parent::Database {
+            database_id: notion::ids::DatabaseId::from_str(CATEGORIES_ID)?,
+        },
+    })
+}
+
+fn plain_text(page: &notion::models::Page, property: &str) -> Option<String> {
+    match page.properties.properties.get(property)? {
+        PropertyValue::Title { title: text, .. }
+        | PropertyValue::Text {
+            rich_text: text, ..
+        } => Some(text.iter().map(|text| text.plain_text()).collect()),
+        _ => None,
+    }
+}
+
+fn date_matches(date: notion::chrono::NaiveDate, condition: &DateCondition) -> Result<bool> {
+    Ok(match condition {
+        DateCondition::Equals(other) => date == other.date_naive(),
+        DateCondition::Before(other) => date < other.date_naive(),
+        DateCondition::After(other) => date > other.date_naive(),
+        DateCondition::OnOrBefore(other) => date <= other.date_naive(),
+        DateCondition::OnOrAfter(other) => date >= other.date_naive(),
+        condition => return Err(eyre!("Unsupported date filter: {:?}", condition)),
+    })
+}
+
+fn condition_matches(
+    page: &notion::models::Page,
+    property: &str,
+    condition: &PropertyCondition,
+) -> Result<bool> {
+    Ok(match condition {
+        PropertyCondition::Date(condition) => match crate::report::page_date(page, property) {
+            Some(date) => date_matches(date, condition)?,
+            None => false,
+        },
+        PropertyCondition::Relation(RelationCondition::Contains(id)) => {
+            crate::report::page_relation_ids(page, property).contains(id)
+        }
+        PropertyCondition::Select(SelectCondition::Equals(name)) => {
+            crate::report::page_select_name(page, property).as_ref() == Some(name)
+        }
+        PropertyCondition::Checkbox(CheckboxCondition::Equals(value)) => {
+            crate::report::page_checkbox(page, property) == *value
+        }
+        // Notion compares text case-insensitively.
+        PropertyCondition::RichText(TextCondition::Contains(text)) => plain_text(page, property)
+            .is_some_and(|value| value.to_lowercase().contains(&text.to_lowercase())),
+        PropertyCondition::And(conditions) => {
+            for condition in conditions {
+                if !condition_matches(page, property, condition)? {
+                    return Ok(false);
+                }
+            }
+            true
+        }
+        PropertyCondition::Or(conditions) => {
+            for condition in conditions {
+                if condition_matches(page, property, condition)? {
+                    return Ok(true);
+                }
+            }
+            false
+        }
+        condition => return Err(eyre!("Unsupported filter on {}: {:?}", property, condition)),
+    })
+}
+
+fn matches(page: &notion::models::Page, filter: &crate::backend::Filter) -> Result<bool> {
+    match filter {
+        crate::backend::Filter::Property(condition) => {
+            condition_matches(page, &condition.property, &condition.condition)
+        }
+        crate::backend::Filter::And { and } => {
+            for filter in and {
+                if !matches(page, filter)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        crate::backend::Filter::Or { or } => {
+            for filter in or {
+                if matches(page, filter)? {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        }
+        crate::backend::Filter::LastEdited {
+            last_edited_time, ..
+        } => Ok(match last_edited_time {
+            DateCondition::OnOrAfter(time) => page.last_edited_time >= *time,
+            condition => return Err(eyre!("Unsupported timestamp filter: {:?}", condition)),
+        }),
+    }
+}
+
+#[derive(PartialEq, PartialOrd)]
+enum SortKey {
+    Number(f64),
+    Date(DateTime<Utc>),
+    Text(String),
+}
+
+fn sort_key(page: &notion::models::Page, sort: &DatabaseSort) -> Option<SortKey> {
+    let property = match (&sort.property, sort.timestamp) {
+        (Some(property), _) => property,
+        (None, Some(DatabaseSortTimestamp::CreatedTime)) => {
+            return Some(SortKey::Date(page.created_time))
+        }
+        (None, Some(DatabaseSortTimestamp::LastEditedTime)) => {
+            return Some(SortKey::Date(page.last_edited_time))
+        }
+        (None, None) => return None,
+    };
+
+    match page.properties.properties.get(property)? {
+        PropertyValue::Number { number, .. } => number.as_ref()?.as_f64().map(SortKey::Number),
+        PropertyValue::Date { date, .. } => Some(SortKey::Date(match date.as_ref()?.start {
+            DateOrDateTime::Date(date) => crate::backend::midnight(date),
+            DateOrDateTime::DateTime(date_time) => date_time,
+        })),
+        _ => plain_text(page, property).map(SortKey::Text),
+    }
+}
+
+/// Orders by each sort in turn, entries without a value last either way.
+fn compare(a: &notion::models::Page, b: &notion::models::Page, sorts: &[DatabaseSort]) -> Ordering {
+    for sort in sorts {
+        let ordering = match (sort_key(a, sort), sort_key(b, sort)) {
+            (Some(a), Some(b)) => {
+                let ordering = a.partial_cmp(&b).unwrap_or(Ordering::Equal);
+                match sort.direction {
+                    SortDirection::Ascending => ordering,
+                    SortDirection::Descending => ordering.reverse(),
+                }
+            }
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+
+    Ordering::Equal
+}
+
+impl SqliteBackend {
+    pub fn open(path: &std::path::Path, names: &crate::settings::PropertyNames) -> Result<Self> {
+        Self::with_connection(rusqlite::Connection::open(path)?, names)
+    }
+
+    pub(crate) fn with_connection(
+        connection: rusqlite::Connection,
+        names: &crate::settings::PropertyNames,
+    ) -> Result<Self> {
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS entries (
+                id TEXT PRIMARY KEY,
+                created_time TEXT NOT NULL,
+                last_edited_time TEXT NOT NULL,
+                archived INTEGER NOT NULL DEFAULT 0,
+                properties TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS categories (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE,
+                created_time TEXT NOT NULL
+            );",
+        )?;
+
+        Ok(Self {
+            connection: Mutex::new(connection),
+            names: names.clone(),
+        })
+    }
+
+    pub(crate) fn connection(&self) -> std::sync::MutexGuard<'_, rusqlite::Connection> {
+        self.connection
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Entries that aren't archived, oldest first.
+    fn entries(&self) -> Result<Vec<notion::models::Page>> {
+        let connection = self.connection();
+        let mut statement = connection.prepare(
+            "SELECT id, created_time, last_edited_time, properties FROM entries
+            WHERE archived = 0 ORDER BY created_time",
+        )?;
+        let rows = statement.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })?;
+
+        rows.map(|row| {
+            let (id, created_time, last_edited_time, properties) = row?;
+            Ok(notion::models::Page {
+                id: notion::ids::PageId::from_str(&id)?,
+                created_time: timestamp(&created_time)?,
+                last_edited_time: timestamp(&last_edited_time)?,
+                archived: false,
+                properties: serde_json::from_str(&properties)?,
+                parent: notion::models::Parent::Database {
+                    database_id: notion::ids::DatabaseId::from_str(ENTRIES_ID)?,
+                },
+            })
+        })
+        .collect()
+    }
+
+    pub(crate) fn entry(&self, page_id: &notion::ids::PageId) -> Result<notion::models::Page> {
+        self.entries()?
+            .into_iter()
+            .find(|page| &page.id == page_id)
+            .ok_or_else(|| eyre!("Unknown entry: {}", page_id))
+    }
+
+    /// Stores `page` as it is, keeping its id and timestamps.
+    pub(crate) fn put_entry(&self, page: &notion::models::Page) -> Result<()> {
+        self.connection().execute(
+            "INSERT OR REPLACE INTO entries
+            (id, created_time, last_edited_time, archived, properties)
+            VALUES (?1, ?2, ?3, ?4, ?5)",
+            (
+                page.id.to_string(),
+                page.created_time.to_rfc3339(),
+                page.last_edited_time.to_rfc3339(),
+                page.archived,
+                serde_json::to_string(&page.properties)?,
+            ),
+        )?;
+
+        Ok(())
+    }
+
+    pub(crate) fn remove_entry(&self, page_id: &notion::ids::PageId) -> Result<()> {
+        self.connection()
+            .execute("DELETE FROM entries WHERE id = ?1", [page_id.to_string()])?;
+
+        Ok(())
+    }
+
+    /// Replaces the categories with `pages`, named by their titles.
+    pub(crate) fn replace_categories(&self, pages: &[notion::models::Page]) -> Result<()> {
+        let mut connection = self.connection();
+        let transaction = connection.transaction()?;
+        transaction.execute("DELETE FROM categories", [])?;
+        for page in pages {
+            transaction.execute(
+                "INSERT OR REPLACE INTO categories (id, name, created_time) VALUES (?1, ?2, ?3)",
+                (
+                    page.id.to_string(),
+                    page.title().unwrap_or("Untitled".to_string()),
+                    page.created_time.to_rfc3339(),
+                ),
+            )?;
+        }
+        transaction.commit()?;
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::backend::ExpenseBackend for SqliteBackend {
+    async fn schema(&self) -> Result<notion::models::Database> {
+        let names = &self.names;
+
+        let entries = self.entries()?;
+        let mut tags: Vec<String> = entries
+            .iter()
+            .flat_map(|page| match page.properties.properties.get(&names.tags) {
+                Some(PropertyValue::MultiSelect {
+                    multi_select: Some(values),
+                    ..
+                }) => values
+                    .iter()
+                    .filter_map(|value| value.name.clone())
+                    .collect(),
+                _ => vec![],
+            })
+            .collect();
+        tags.sort();
+        tags.dedup();
+        let mut projects: Vec<String> = entries
+            .iter()
+            .filter_map(|page| crate::report::page_select_name(page, &names.project))
+            .collect();
+        projects.sort();
+        projects.dedup();
+        let mut currencies: Vec<String> = entries
+            .iter()
+            .filter_map(|page| crate::report::page_select_name(page, &names.currency))
+            .collect();
+        currencies.sort();
+        currencies.dedup();
+
+        let options = |names: Vec<String>| -> Vec<serde_json::Value> {
+            names
+                .into_iter()
+                .map(|name| serde_json::json!({"id": name, "name": name, "color": "default"}))
+                .collect()
+        };
+        let types = [
+            crate::report::EntryType::Expense,
+            crate::report::EntryType::Income,
+            crate::report::EntryType::Transfer,
+        ]
+        .map(|entry_type| entry_type.name().to_string());
+
+        Ok(serde_json::from_value(serde_json::json!({
+            "id": ENTRIES_ID,
+            "created_time": "1970-01-01T00:00:00Z",
+            "last_edited_time": "1970-01-01T00:00:00Z",
+            "title": [{"type": "text", "plain_text": "Expenses", "text": {"content": "Expenses", "link": null}}],
+            "properties": {
+                &names.name: {"id": "title", "type": "title", "title": {}},
+                &names.amount: {"id": "amount", "type": "number", "number": {"format": "number"}},
+                &names.date: {"id": "date", "type": "date", "date": {}},
+                &names.category: {"id": "category", "type": "relation", "relation": {
+                    "database_id": CATEGORIES_ID,
+                    "synced_property_name": null,
+                    "synced_property_id": null,
+                }},
+                &names.entry_type: {"id": "type", "type": "select", "select": {
+                    "options": options(types.to_vec()),
+                }},
+                &names.notes: {"id": "notes", "type": "rich_text", "rich_text": {}},
+                &names.tags: {"id": "tags", "type": "multi_select", "multi_select": {
+                    "options": options(tags),
+                }},
+                &names.refund_of: {"id": "refund_of", "type": "relation", "relation": {
+                    "database_id": ENTRIES_ID,
+                    "synced_property_name": null,
+                    "synced_property_id": null,
+                }},
+                &names.reimbursable: {"id": "reimbursable", "type": "checkbox", "checkbox": {}},
+                &names.reimbursed: {"id": "reimbursed", "type": "checkbox", "checkbox": {}},
+                &names.project: {"id": "project", "type": "select", "select": {
+                    "options": options(projects),
+                }},
+                &names.currency: {"id": "currency", "type": "select", "select": {
+                    "options": options(currencies),
+                }},
+            },
+        }))?)
+    }
+
+    async fn query_entries(
+        &self,
+        query: crate::backend::Query,
+    ) -> Result<Vec<notion::models::Page>> {
+        let mut pages = vec![];
+        for page in self.entries()? {
+            if query
+                .filter
+                .as_ref()
+                .map_or(Ok(true), |filter| matches(&page, filter))?
+            {
+                pages.push(page);
+            }
+        }
+
+        if let Some(sorts) = &query.sorts {
+            pages.sort_by(|a, b| compare(a, b, sorts));
+        }
+        if let Some(page_size) = query.paging.and_then(|paging| paging.page_size) {
+            pages.truncate(page_size.into());
+        }
+
+        Ok(pages)
+    }
+
+    async fn categories(
+        &self,
+        _database_id: &notion::ids::DatabaseId,
+    ) -> Result<Vec<notion::models::Page>> {
+        let connection = self.connection();
+        let mut statement =
+            connection.prepare("SELECT id, name, created_time FROM categories ORDER BY name")?;
+        let rows = statement.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?;
+
+        rows.map(|row| {
+            let (id, name, created_time) = row?;
+            category_page(&id, &name, &created_time)
+        })
+        .collect()
+    }
+
+    async fn create_entry(
+        &self,
+        properties: notion::models::Properties,
+    ) -> Result<notion::models::Page> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let now = Utc::now().to_rfc3339();
+
+        self.connection().execute(
+            "INSERT INTO entries (id, created_time, last_edited_time, properties)
+            VALUES (?1, ?2, ?2, ?3)",
+            (&id, &now, serde_json::to_string(&properties)?),
+        )?;
+
+        self.entry(&notion::ids::PageId::from_str(&id)?)
+    }
+
+    async fn create_category(
+        &self,
+        _database_id: &notion::ids::DatabaseId,
+        name: &str,
+    ) -> Result<notion::models::Page> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let now = Utc::now().to_rfc3339();
+
+        self.connection().execute(
+            "INSERT INTO categories (id, name, created_time) VALUES (?1, ?2, ?3)",
+            (&id, name, &now),
+        )?;
+
+        category_page(&id, name, &now)
+    }
+
+    async fn update_entry(
+        &self,
+        page_id: &notion::ids::PageId,
+        properties: notion::models::Properties,
+    ) -> Result<notion::models::Page> {
+        let mut page = self.entry(page_id)?;
+        page.properties.properties.extend(properties.properties);
+
+        self.connection().execute(
+            "UPDATE entries SET properties = ?1, last_edited_time = ?2 WHERE id = ?3",
+            (
+                serde_json::to_string(&page.properties)?,
+                Utc::now().to_rfc3339(),
+                page_id.to_string(),
+            ),
+        )?;
+
+        self.entry(page_id)
+    }
+
+    async fn archive_entry(&self, page_id: &notion::ids::PageId) -> Result<()> {
+        let updated = self.connection().execute(
+            "UPDATE entries SET archived = 1 WHERE id = ?1 AND archived = 0",
+            [page_id.to_string()],
+        )?;
+        if updated == 0 {
+            return Err(eyre!("Unknown entry: {}", page_id));
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn sqlite_backend_test() {
+    use crate::backend::ExpenseBackend;
+
+    futures::executor::block_on(async {
+        let names = crate::settings::PropertyNames::default();
+        let backend =
+            SqliteBackend::with_connection(rusqlite::Connection::open_in_memory().unwrap(), &names)
+                .unwrap();
+
+        let schema = backend.schema().await.unwrap();
+        assert!(crate::doctor::schema_problems(&schema, &names).is_empty());
+
+        let food = backend
+            .create_category(&CATEGORIES_ID.parse().unwrap(), "Food")
+            .await
+            .unwrap();
+        let entry = |name: &str, amount: f64, date: &str| -> notion::models::Properties {
+            crate::report::page(serde_json::json!({
+                "Name": {"id": "title", "type": "title", "title": [
+                    {"type": "text", "plain_text": name, "text": {"content": name, "link": null}}
+                ]},
+                "Amount": {"id": "amount", "type": "number", "number": amount},
+                "Date": {"id": "date", "type": "date", "date": {"start": date}},
+                "Category": {"id": "category", "type": "relation", "relation": [{"id": food.id}]},
+            }))
+            .properties
+        };
+
+        let bread = backend
+            .create_entry(entry("Bread", 2.5, "2024-05-02"))
+            .await
+            .unwrap();
+        backend
+            .create_entry(entry("Coffee", 3.0, "2024-05-20"))
+            .await
+            .unwrap();
+        backend
+            .create_entry(entry("Rent", 800.0, "2024-06-01"))
+            .await
+            .unwrap();
+
+        let may = backend
+            .query_entries(crate::report::month_query(
+                &names.date,
+                notion::chrono::NaiveDate::from_ymd_opt(2024, 5, 10).unwrap(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(may.len(), 2);
+
+        let recent = backend
+            .recent_entries(crate::backend::descending(&names.date), 2)
+            .await
+            .unwrap();
+        let titles: Vec<_> = recent.iter().filter_map(|page| page.title()).collect();
+        assert_eq!(titles, vec!["Rent", "Coffee"]);
+
+        let mut amount = entry("Bread", 2.5, "2024-05-02");
+        amount.properties.retain(|name, _| name == "Amount");
+        amount.properties.insert(
+            "Amount".to_string(),
+            PropertyValue::Number {
+                id: "amount".parse().unwrap(),
+                number: serde_json::Number::from_f64(3.5),
+            },
+        );
+        let updated = backend.update_entry(&bread.id, amount).await.unwrap();
+        assert_eq!(crate::report::page_amount(&updated, "Amount"), Some(3.5));
+        assert_eq!(updated.title().as_deref(), Some("Bread"));
+
+        backend.archive_entry(&bread.id).await.unwrap();
+        let all = backend
+            .query_entries(crate::backend::Query::default())
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 2);
+        assert!(backend.archive_entry(&bread.id).await.is_err());
+
+        let categories = backend
+            .categories(&CATEGORIES_ID.parse().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(categories[0].title().as_deref(), Some("Food"));
+    });
+}
@@ -0,0 +1,309 @@
+use crate::backend::ExpenseBackend;
+use eyre::{eyre, Result};
+use std::str::FromStr;
+
+/// A local copy of the Notion expense database, kept up to date by `sync` so
+/// that `--offline` commands don't need Notion.
+///
+/// Entries are pulled incrementally by their last edit time. Entries added
+/// offline are kept apart until `sync` uploads them.
+pub struct Mirror {
+    store: crate::sqlite::SqliteBackend,
+}
+
+/// What a sync changed.
+#[derive(Debug, Default, PartialEq)]
+pub struct SyncSummary {
+    /// Entries added offline that are now in Notion.
+    pub pushed: usize,
+    /// Entries downloaded because they are new or were edited.
+    pub pulled: usize,
+}
+
+const SCHEMA_KEY: &str = "schema";
+const CURSOR_KEY: &str = "last_edited_time";
+
+impl Mirror {
+    pub fn open(path: &std::path::Path, names: &crate::settings::PropertyNames) -> Result<Self> {
+        Self::with_store(crate::sqlite::SqliteBackend::open(path, names)?)
+    }
+
+    fn with_store(store: crate::sqlite::SqliteBackend) -> Result<Self> {
+        store.connection().execute_batch(
+            "CREATE TABLE IF NOT EXISTS sync_state (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS local_entries (
+                id TEXT PRIMARY KEY
+            );",
+        )?;
+
+        Ok(Self { store })
+    }
+
+    fn state(&self, key: &str) -> Result<Option<String>> {
+        use rusqlite::OptionalExtension;
+
+        Ok(self
+            .store
+            .connection()
+            .query_row(
+                "SELECT value FROM sync_state WHERE key = ?1",
+                [key],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    fn set_state(&self, key: &str, value: &str) -> Result<()> {
+        self.store.connection().execute(
+            "INSERT OR REPLACE INTO sync_state (key, value) VALUES (?1, ?2)",
+            [key, value],
+        )?;
+
+        Ok(())
+    }
+
+    /// Entries added offline, oldest first.
+    fn local_entries(&self) -> Result<Vec<notion::ids::PageId>> {
+        let connection = self.store.connection();
+        let mut statement = connection.prepare("SELECT id FROM local_entries ORDER BY rowid")?;
+        let ids = statement.query_map([], |row| row.get::<_, String>(0))?;
+
+        ids.map(|id| Ok(notion::ids::PageId::from_str(&id?)?))
+            .collect()
+    }
+
+    fn is_local(&self, page_id: &notion::ids::PageId) -> Result<bool> {
+        Ok(self.local_entries()?.contains(page_id))
+    }
+
+    fn forget_local(&self, page_id: &notion::ids::PageId) -> Result<()> {
+        self.store.connection().execute(
+            "DELETE FROM local_entries WHERE id = ?1",
+            [page_id.to_string()],
+        )?;
+
+        Ok(())
+    }
+
+    /// Uploads the entries added offline, then downloads whatever changed in
+    /// Notion since the last sync. `full` downloads everything again, which
+    /// also drops the entries deleted in Notion.
+    pub async fn sync(
+        &self,
+        notion: &dyn ExpenseBackend,
+        category_property: &str,
+        full: bool,
+    ) -> Result<SyncSummary> {
+        let mut summary = SyncSummary::default();
+
+        for page_id in self.local_entries()? {
+            let page = self.store.entry(&page_id)?;
+            let created = notion.create_entry(page.properties).await?;
+            self.store.remove_entry(&page_id)?;
+            self.forget_local(&page_id)?;
+            self.store.put_entry(&created)?;
+            summary.pushed += 1;
+        }
+
+        let schema = notion.schema().await?;
+        self.set_state(SCHEMA_KEY, &serde_json::to_string(&schema)?)?;
+        if let Some(notion::models::properties::PropertyConfiguration::Relation {
+            relation, ..
+        }) = schema.properties.get(category_property)
+        {
+            let categories = notion.categories(&relation.database_id).await?;
+            self.store.replace_categories(&categories)?;
+        }
+
+        let cursor = match full {
+            true => None,
+            false => self
+                .state(CURSOR_KEY)?
+                .map(|cursor| cursor.parse())
+                .transpose()?,
+        };
+        let pages = notion
+            .query_entries(crate::backend::Query {
+                filter: cursor.map(crate::backend::Filter::edited_since),
+                ..Default::default()
+            })
+            .await?;
+
+        if full {
+            let local = self.local_entries()?;
+            let stored = self
+                .store
+                .query_entries(crate::backend::Query::default())
+                .await?;
+            for page in stored.iter().filter(|page| !local.contains(&page.id)) {
+                self.store.remove_entry(&page.id)?;
+            }
+        }
+        for page in &pages {
+            self.store.put_entry(page)?;
+        }
+        summary.pulled = pages.len();
+
+        // Notion rounds edit times to the minute, so the next sync asks from the
+        // newest one again rather than after it.
+        if let Some(newest) = pages.iter().map(|page| page.last_edited_time).max() {
+            self.set_state(CURSOR_KEY, &newest.to_rfc3339())?;
+        }
+
+        Ok(summary)
+    }
+}
+
+#[async_trait::async_trait]
+impl ExpenseBackend for Mirror {
+    async fn schema(&self) -> Result<notion::models::Database> {
+        match self.state(SCHEMA_KEY)? {
+            Some(schema) => Ok(serde_json::from_str(&schema)?),
+            None => Err(eyre!(
+                "There's no local copy yet, run `notion-bot sync` first"
+            )),
+        }
+    }
+
+    async fn query_entries(
+        &self,
+        query: crate::backend::Query,
+    ) -> Result<Vec<notion::models::Page>> {
+        self.store.query_entries(query).await
+    }
+
+    async fn categories(
+        &self,
+        database_id: &notion::ids::DatabaseId,
+    ) -> Result<Vec<notion::models::Page>> {
+        self.store.categories(database_id).await
+    }
+
+    async fn create_entry(
+        &self,
+        properties: notion::models::Properties,
+    ) -> Result<notion::models::Page> {
+        let page = self.store.create_entry(properties).await?;
+        self.store.connection().execute(
+            "INSERT INTO local_entries (id) VALUES (?1)",
+            [page.id.to_string()],
+        )?;
+
+        Ok(page)
+    }
+
+    async fn create_category(
+        &self,
+        _database_id: &notion::ids::DatabaseId,
+        _name: &str,
+    ) -> Result<notion::models::Page> {
+        Err(eyre!("Categories can't be created offline"))
+    }
+
+    async fn update_entry(
+        &self,
+        page_id: &notion::ids::PageId,
+        properties: notion::models::Properties,
+    ) -> Result<notion::models::Page> {
+        if !self.is_local(page_id)? {
+            return Err(eyre!("Only entries added offline can be changed offline"));
+        }
+
+        self.store.update_entry(page_id, properties).await
+    }
+
+    async fn archive_entry(&self, page_id: &notion::ids::PageId) -> Result<()> {
+        if !self.is_local(page_id)? {
+            return Err(eyre!("Only entries added offline can be deleted offline"));
+        }
+
+        self.store.remove_entry(page_id)?;
+        self.forget_local(page_id)
+    }
+}
+
+#[test]
+fn mirror_sync_test() {
+    futures::executor::block_on(async {
+        let names = crate::settings::PropertyNames::default();
+        let store = || {
+            crate::sqlite::SqliteBackend::with_connection(
+                rusqlite::Connection::open_in_memory().unwrap(),
+                &names,
+            )
+            .unwrap()
+        };
+        // A local database stands in for Notion.
+        let notion = store();
+        let mirror = Mirror::with_store(store()).unwrap();
+        assert!(mirror.schema().await.is_err());
+
+        let entry = |name: &str| -> notion::models::Properties {
+            crate::report::page(serde_json::json!({
+                "Name": {"id": "title", "type": "title", "title": [
+                    {"type": "text", "plain_text": name, "text": {"content": name, "link": null}}
+                ]},
+                "Amount": {"id": "amount", "type": "number", "number": 2.5},
+                "Date": {"id": "date", "type": "date", "date": {"start": "2024-05-02"}},
+            }))
+            .properties
+        };
+        let titles = |pages: Vec<notion::models::Page>| -> Vec<String> {
+            let mut titles: Vec<_> = pages.iter().filter_map(|page| page.title()).collect();
+            titles.sort();
+            titles
+        };
+
+        notion
+            .create_category(&"categories".parse().unwrap(), "Food")
+            .await
+            .unwrap();
+        let bread = notion.create_entry(entry("Bread")).await.unwrap();
+        let summary = mirror.sync(&notion, &names.category, false).await.unwrap();
+        assert_eq!(
+            summary,
+            SyncSummary {
+                pushed: 0,
+                pulled: 1
+            }
+        );
+        assert!(mirror.schema().await.is_ok());
+        assert_eq!(
+            mirror
+                .store
+                .categories(&"categories".parse().unwrap())
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // Offline additions are uploaded, and kept under their new id.
+        mirror.create_entry(entry("Coffee")).await.unwrap();
+        assert!(mirror.archive_entry(&bread.id).await.is_err());
+        mirror.sync(&notion, &names.category, false).await.unwrap();
+        assert!(mirror.local_entries().unwrap().is_empty());
+        let all = crate::backend::Query::default();
+        assert_eq!(
+            titles(notion.query_entries(all.clone()).await.unwrap()),
+            vec!["Bread", "Coffee"]
+        );
+        assert_eq!(
+            titles(mirror.query_entries(all.clone()).await.unwrap()),
+            vec!["Bread", "Coffee"]
+        );
+
+        // Deletions only show up in a full sync.
+        notion.archive_entry(&bread.id).await.unwrap();
+        mirror.sync(&notion, &names.category, false).await.unwrap();
+        assert_eq!(mirror.query_entries(all.clone()).await.unwrap().len(), 2);
+        mirror.sync(&notion, &names.category, true).await.unwrap();
+        assert_eq!(
+            titles(mirror.query_entries(all).await.unwrap()),
+            vec!["Coffee"]
+        );
+    });
+}
@@ -0,0 +1,408 @@
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use eyre::Result;
+use futures::StreamExt;
+use notion::chrono::NaiveDate;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph, Row, Table, TableState};
+
+const FIELDS: [&str; 4] = ["Name", "Amount", "Category", "Date"];
+const ENTRY_WIDTHS: [Constraint; 4] = [
+    Constraint::Length(10),
+    Constraint::Min(12),
+    Constraint::Length(12),
+    Constraint::Length(14),
+];
+const CATEGORY_WIDTHS: [Constraint; 3] = [
+    Constraint::Min(10),
+    Constraint::Length(12),
+    Constraint::Length(5),
+];
+const HELP: &str = "←/→ month  ↑/↓ select  a add  r reload  q quit";
+
+/// The quick-add form, one line of fields.
+#[derive(Debug, Default)]
+struct QuickAdd {
+    values: [String; 4],
+    focused: usize,
+}
+
+impl QuickAdd {
+    /// The entry to create. Empty fields are left out, so the category and
+    /// date get their defaults.
+    fn args(&self) -> Result<crate::cli::AddArgs, String> {
+        let value = |index: usize| {
+            Some(self.values[index].trim().to_string()).filter(|value| !value.is_empty())
+        };
+        let date = match value(3) {
+            Some(date) => Some(
+                NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+                    .map_err(|_| format!("Expected a date like 2024-05-02, got `{}`", date))?,
+            ),
+            None => None,
+        };
+
+        Ok(crate::cli::AddArgs {
+            name: value(0),
+            amount: value(1),
+            category: value(2),
+            date,
+            ..Default::default()
+        })
+    }
+}
+
+/// What a key press asks the dashboard to do.
+#[derive(Debug, PartialEq)]
+enum Action {
+    None,
+    Quit,
+    /// Load the entries of the shown month.
+    Reload,
+    Add(Box<crate::cli::AddArgs>),
+}
+
+/// The dashboard's state, apart from the app that loads and creates entries.
+struct Dashboard {
+    month: NaiveDate,
+    rows: Vec<crate::cli::AddArgs>,
+    report: Option<crate::report::MonthReport>,
+    table: TableState,
+    form: Option<QuickAdd>,
+    status: String,
+}
+
+impl Dashboard {
+    fn new(today: NaiveDate) -> Self {
+        Self {
+            month: crate::report::month_start(today),
+            rows: vec![],
+            report: None,
+            table: TableState::default(),
+            form: None,
+            status: HELP.to_string(),
+        }
+    }
+
+    fn show(&mut self, rows: Vec<crate::cli::AddArgs>, report: crate::report::MonthReport) {
+        self.table.select((!rows.is_empty()).then_some(0));
+        self.rows = rows;
+        self.report = Some(report);
+    }
+
+    fn select(&mut self, offset: isize) {
+        if self.rows.is_empty() {
+            return;
+        }
+        let selected = self.table.selected().unwrap_or(0) as isize + offset;
+        self.table.select(Some(
+            selected.clamp(0, self.rows.len() as isize - 1) as usize
+        ));
+    }
+
+    fn handle(&mut self, key: KeyEvent) -> Action {
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            return Action::Quit;
+        }
+
+        let Some(form) = &mut self.form else {
+            return match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => Action::Quit,
+                KeyCode::Left | KeyCode::Char('h') => {
+                    self.month = self
+                        .month
+                        .pred_opt()
+                        .map_or(self.month, crate::report::month_start);
+                    Action::Reload
+                }
+                KeyCode::Right | KeyCode::Char('l') => {
+                    self.month = crate::report::next_month(self.month);
+                    Action::Reload
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.select(-1);
+                    Action::None
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.select(1);
+                    Action::None
+                }
+                KeyCode::Char('a') => {
+                    self.form = Some(QuickAdd::default());
+                    self.status = "Tab next field  Enter save  Esc cancel".to_string();
+                    Action::None
+                }
+                KeyCode::Char('r') => Action::Reload,
+                _ => Action::None,
+            };
+        };
+
+        match key.code {
+            KeyCode::Esc => {
+                self.form = None;
+                self.status = HELP.to_string();
+            }
+            KeyCode::Tab | KeyCode::Down => form.focused = (form.focused + 1) % FIELDS.len(),
+            KeyCode::BackTab | KeyCode::Up => {
+                form.focused = (form.focused + FIELDS.len() - 1) % FIELDS.len()
+            }
+            KeyCode::Backspace => {
+                form.values[form.focused].pop();
+            }
+            KeyCode::Char(c) => form.values[form.focused].push(c),
+            KeyCode::Enter => match form.args() {
+                Ok(args) => return Action::Add(Box::new(args)),
+                Err(err) => self.status = err,
+            },
+            _ => {}
+        }
+
+        Action::None
+    }
+
+    fn draw(&mut self, frame: &mut ratatui::Frame) {
+        let [header, body, footer] = split(
+            Direction::Vertical,
+            [
+                Constraint::Length(1),
+                Constraint::Min(0),
+                Constraint::Length(if self.form.is_some() { 3 } else { 1 }),
+            ],
+            frame.size(),
+        );
+        let [entries, categories] = split(
+            Direction::Horizontal,
+            [Constraint::Percentage(65), Constraint::Percentage(35)],
+            body,
+        );
+
+        let mut title = vec![Span::styled(
+            self.month.format("%B %Y").to_string(),
+            Style::default().add_modifier(Modifier::BOLD),
+        )];
+        if let Some(report) = &self.report {
+            title.push(Span::raw(format!(
+                "  spent {}  income {}",
+                crate::report::format_amount(report.total()),
+                crate::report::format_amount(report.income())
+            )));
+        }
+        frame.render_widget(Paragraph::new(Line::from(title)), header);
+
+        let rows = self.rows.iter().map(|row| {
+            let color = match crate::report::EntryType::named(row.entry_type.as_deref()) {
+                crate::report::EntryType::Expense => Color::Reset,
+                crate::report::EntryType::Income => Color::Green,
+                crate::report::EntryType::Transfer => Color::DarkGray,
+            };
+            Row::new([
+                row.date.map(|date| date.to_string()).unwrap_or_default(),
+                row.name.clone().unwrap_or_default(),
+                row.amount
+                    .as_deref()
+                    .and_then(|amount| amount.parse().ok())
+                    .map(crate::report::format_amount)
+                    .unwrap_or_default(),
+                row.category.clone().unwrap_or_default(),
+            ])
+            .style(Style::default().fg(color))
+        });
+        let table = Table::new(rows)
+            .header(
+                Row::new(["Date", "Name", "Amount", "Category"])
+                    .style(Style::default().add_modifier(Modifier::BOLD)),
+            )
+            .widths(&ENTRY_WIDTHS)
+            .block(Block::default().borders(Borders::ALL).title("Entries"))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(table, entries, &mut self.table);
+
+        let totals = self
+            .report
+            .as_ref()
+            .map(|report| (report.totals(), report.total()))
+            .unwrap_or_default();
+        let rows = totals.0.iter().map(|(name, amount)| {
+            let share = match totals.1 {
+                0.0 => 0.0,
+                total => amount / total * 100.0,
+            };
+            Row::new([
+                name.clone(),
+                crate::report::format_amount(*amount),
+                format!("{:.0}%", share),
+            ])
+        });
+        let table = Table::new(rows)
+            .widths(&CATEGORY_WIDTHS)
+            .block(Block::default().borders(Borders::ALL).title("Categories"));
+        frame.render_widget(table, categories);
+
+        let Some(form) = &self.form else {
+            frame.render_widget(
+                Paragraph::new(self.status.as_str()).style(Style::default().fg(Color::DarkGray)),
+                footer,
+            );
+            return;
+        };
+
+        let [fields, status] = split(
+            Direction::Vertical,
+            [Constraint::Length(2), Constraint::Length(1)],
+            footer,
+        );
+        let mut spans = vec![];
+        let mut cursor = fields.x;
+        for (index, (label, value)) in FIELDS.iter().zip(&form.values).enumerate() {
+            let label = format!("{}: ", label);
+            let style = match index == form.focused {
+                true => Style::default().add_modifier(Modifier::UNDERLINED),
+                false => Style::default(),
+            };
+            if index == form.focused {
+                cursor += (label.chars().count() + value.chars().count()) as u16;
+            } else if index < form.focused {
+                cursor += (label.chars().count() + value.chars().count() + 2) as u16;
+            }
+            spans.push(Span::styled(label, Style::default().fg(Color::DarkGray)));
+            spans.push(Span::styled(value.clone(), style));
+            spans.push(Span::raw("  "));
+        }
+        frame.render_widget(
+            Paragraph::new(Line::from(spans))
+                .block(Block::default().borders(Borders::TOP).title("New entry")),
+            fields,
+        );
+        frame.set_cursor(cursor, fields.y + 1);
+        frame.render_widget(
+            Paragraph::new(self.status.as_str()).style(Style::default().fg(Color::DarkGray)),
+            status,
+        );
+    }
+}
+
+fn split<const N: usize>(
+    direction: Direction,
+    constraints: [Constraint; N],
+    area: Rect,
+) -> [Rect; N] {
+    let areas = Layout::default()
+        .direction(direction)
+        .constraints(constraints)
+        .split(area);
+    std::array::from_fn(|index| areas[index])
+}
+
+/// Leaves the alternate screen and raw mode when dropped, on errors too.
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter() -> Result<Self> {
+        crossterm::terminal::enable_raw_mode()?;
+        crossterm::execute!(std::io::stdout(), crossterm::terminal::EnterAlternateScreen)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = crossterm::execute!(std::io::stdout(), crossterm::terminal::LeaveAlternateScreen);
+        let _ = crossterm::terminal::disable_raw_mode();
+    }
+}
+
+/// Runs the dashboard until it's quit: the month's entries, their totals by
+/// category, and a form to add entries like `serve` does.
+pub async fn run(mut app: crate::app::App) -> Result<()> {
+    let (db, _) = app.prepare().await?;
+    let today = notion::chrono::offset::Local::now().date_naive();
+    let mut dashboard = Dashboard::new(today);
+    let (rows, report) = app.month_overview(&db, dashboard.month).await?;
+    dashboard.show(rows, report);
+
+    let _guard = TerminalGuard::enter()?;
+    let mut terminal =
+        ratatui::Terminal::new(ratatui::backend::CrosstermBackend::new(std::io::stdout()))?;
+    let mut events = crossterm::event::EventStream::new();
+
+    loop {
+        terminal.draw(|frame| dashboard.draw(frame))?;
+
+        let Some(event) = events.next().await else {
+            break;
+        };
+        let Event::Key(key) = event? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        let reload = match dashboard.handle(key) {
+            Action::None => false,
+            Action::Quit => break,
+            Action::Reload => true,
+            Action::Add(args) => {
+                let name = args.name.clone().unwrap_or_default();
+                match app.add_entry(&db, *args).await {
+                    Ok(_) => {
+                        dashboard.form = None;
+                        dashboard.status = format!("Added {}  ·  {}", name, HELP);
+                        true
+                    }
+                    Err(err) => {
+                        dashboard.status = err.to_string();
+                        false
+                    }
+                }
+            }
+        };
+
+        if reload {
+            match app.month_overview(&db, dashboard.month).await {
+                Ok((rows, report)) => dashboard.show(rows, report),
+                Err(err) => dashboard.status = format!("Couldn't load the entries: {}", err),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn dashboard_keys_test() {
+    let key = |code: KeyCode| KeyEvent::new(code, KeyModifiers::NONE);
+    let mut dashboard = Dashboard::new(NaiveDate::from_ymd_opt(2024, 1, 15).unwrap());
+
+    assert_eq!(dashboard.handle(key(KeyCode::Left)), Action::Reload);
+    assert_eq!(
+        dashboard.month,
+        NaiveDate::from_ymd_opt(2023, 12, 1).unwrap()
+    );
+
+    dashboard.handle(key(KeyCode::Char('a')));
+    for c in "Coffee".chars() {
+        dashboard.handle(key(KeyCode::Char(c)));
+    }
+    dashboard.handle(key(KeyCode::Tab));
+    for c in "3.50".chars() {
+        dashboard.handle(key(KeyCode::Char(c)));
+    }
+    dashboard.handle(key(KeyCode::BackTab));
+    // `q` is typed into the form rather than quitting.
+    assert_eq!(dashboard.handle(key(KeyCode::Char('q'))), Action::None);
+    dashboard.handle(key(KeyCode::Backspace));
+
+    assert_eq!(
+        dashboard.handle(key(KeyCode::Enter)),
+        Action::Add(Box::new(crate::cli::AddArgs {
+            name: Some("Coffee".to_string()),
+            amount: Some("3.50".to_string()),
+            ..Default::default()
+        }))
+    );
+
+    dashboard.handle(key(KeyCode::Esc));
+    assert_eq!(dashboard.handle(key(KeyCode::Char('q'))), Action::Quit);
+}